@@ -3,17 +3,49 @@ use clap::{Args, Parser, Subcommand};
 
 use datapostproc_rust::data::H5File;
 use datapostproc_rust::hdf5::{Block, BlockValue, H5Data};
-use datapostproc_rust::math::avg::avg_to_profile;
+use datapostproc_rust::math::anisotropy::{anisotropy, AnisotropyFields};
+use datapostproc_rust::math::avg::{avg_axis, avg_to_profile};
+use datapostproc_rust::math::check::{bulk_flow_rate, profile_rms, relative_spread, rms, Check};
+use datapostproc_rust::math::closure::{closure_metrics, WallStencil};
+use datapostproc_rust::math::control::{
+    control_efficiency, detect_control, drag_reduction, region_mean, wall_plane, wall_power_input,
+};
+use datapostproc_rust::math::correlation::{correlation_scales, PlaneCorrelation};
+use datapostproc_rust::math::deriv::{set_scheme, Scheme};
+use datapostproc_rust::math::diagnostics::shear_diagnostics;
 use datapostproc_rust::math::fik::{
-    fik_average, fik_decomposition, fik_decomposition_planes, FikDecomposition,
+    fik_average, fik_decomposition, fik_decomposition_planes, fik_turb_y_modes, FikDecomposition,
+};
+use datapostproc_rust::math::invariants::{gradient_invariants, QrJointPdf};
+use datapostproc_rust::math::isosurface::isosurface;
+use datapostproc_rust::math::loglaw::{fit_log_law, log_shift, musker, reichardt};
+use datapostproc_rust::math::momentum::momentum_budget;
+use datapostproc_rust::math::ns::{
+    divergence, ns_momentum_residual, ns_residual_fortran_stencil, ns_residual_l2,
+    rans_momentum_residual,
 };
 use datapostproc_rust::math::rd::{rd_decomposition, RdDecomposition};
-use datapostproc_rust::math::spectrum::SpanwiseSpectrum;
-use datapostproc_rust::math::tke::{stress_budget, tke_fields, BudgetTerms, TkeFields};
-use datapostproc_rust::math::vortex::vortex_criteria;
+use datapostproc_rust::math::sanity::{count_nonfinite, ghost_mismatch, is_increasing, spacing_deviation};
+use datapostproc_rust::math::scales::{
+    grid_ratios, kolmogorov_scales, stretching, wall_unit_grid, ScaleAccumulator,
+};
+use datapostproc_rust::math::spectral_budget::SpectralBudget;
+use datapostproc_rust::math::spectrum::{PlaneSpectrum, SpanwiseCospectrum, SpanwiseSpectrum};
+use datapostproc_rust::math::structures::{
+    histogram_pdf, label_structures, spanwise_rms, threshold_mask, Structure, Threshold,
+};
+use datapostproc_rust::math::tke::{mke_budget, stress_budget, tke_fields, BudgetTerms, TkeFields};
+use datapostproc_rust::math::trim::{detect_trim, trim_score, x_profile_rms};
+use datapostproc_rust::math::vortex::{
+    velocity_gradient, vortex_fields, vorticity_fields, Criterion,
+};
 use datapostproc_rust::math::wall::{friction_velocity, wall_shear_stress};
 use datapostproc_rust::output::dat::write_dat;
-use datapostproc_rust::output::normalize::Profiles;
+use datapostproc_rust::output::mesh::{write_mesh, MeshFormat};
+use datapostproc_rust::output::normalize::{
+    fields_to_outer_units, fields_to_wall_units, local_tau, InnerScaling, OuterScaling, Profiles,
+};
+use datapostproc_rust::output::units::UnitRegistry;
 use datapostproc_rust::output::xdmf::{write_xdmf, VarSpec};
 
 use ndarray::{s, Array1, ArrayD, Axis, Ix1};
@@ -27,6 +59,11 @@ use std::path::Path;
 struct Cli {
     #[command(subcommand)]
     command: Command,
+    /// Derivative scheme for every sub-command: second (3-point, default),
+    /// fourth (5-point explicit), pade (4th-order compact) or spectral (FFT
+    /// along periodic axes, pade elsewhere).
+    #[arg(long, global = true, default_value = "second")]
+    scheme: String,
 }
 
 #[derive(Subcommand)]
@@ -38,6 +75,16 @@ enum Command {
     /// Spanwise energy spectra and two-point correlations from
     /// instantaneous snapshots.
     Spectra(SpectraArgs),
+    /// Two-dimensional (k_x, k_y) premultiplied spectra over Hann-windowed
+    /// streamwise ranges, written to an HDF5 file.
+    Spectra2d(Spectra2dArgs),
+    /// Two-point correlations R(Δx, Δz) in the x–z plane about user-given
+    /// reference points, with integral length scales and inclination angles
+    /// (HDF5 + XDMF output).
+    Correlation(CorrelationArgs),
+    /// Integral, Taylor and Kolmogorov scales and grid spacings in
+    /// Kolmogorov units as (z, x) maps (HDF5) and wall-normal profiles (.dat).
+    Scales(ScalesArgs),
     /// FIK skin-friction decomposition from a subavg HDF5 file.
     Fik(FikArgs),
     /// FIK skin-friction decomposition from instantaneous snapshots: the
@@ -56,9 +103,62 @@ enum Command {
     /// Reynolds-stress components and TKE profiles from instantaneous
     /// snapshots (fluctuation moments formed on the fly, like `fik-inst`).
     TkeInst(TkeInstArgs),
-    /// Q and λ₂ vortex-identification fields from instantaneous snapshots,
-    /// written to an HDF5 file (use `hdfview` to make an XDMF for Paraview).
+    /// Vortex-identification fields (Q, λ₂, λ_ci, Δ, Ω, Liutex) from an
+    /// instantaneous snapshot, written to an HDF5 file (use `hdfview` to make
+    /// an XDMF for Paraview).
     Vortex(VortexArgs),
+    /// Vorticity, enstrophy, helicity and strain-rate fields from
+    /// instantaneous snapshots (HDF5), plus ensemble ω'_rms profiles (.dat).
+    Vorticity(VorticityArgs),
+    /// Coherent-structure extraction: threshold a field, label connected
+    /// objects (periodic in y), write per-object tables and size/height PDFs.
+    Structures(StructuresArgs),
+    /// Isosurface of any dataset as a triangle mesh (VTK PolyData, binary PLY
+    /// or STL), optionally coloured by a second field.
+    Isosurface(IsosurfaceArgs),
+    /// Velocity-gradient invariants: joint PDFs of (R, Q) normalised by
+    /// ⟨Q_W⟩ in wall-normal bands and streamwise regions (HDF5).
+    Qr(QrArgs),
+    /// Reynolds-stress anisotropy: b_ij, invariants, eigenvectors, Lumley
+    /// triangle and barycentric-map coordinates as (nz, nx) maps and profiles.
+    Anisotropy(AnisotropyArgs),
+    /// Mean streamwise momentum balance term by term (convection, Reynolds-
+    /// stress divergence, pressure gradient, viscous diffusion, residual
+    /// `res_x`).
+    MomentumBudget(MomentumBudgetArgs),
+    /// Spanwise-wavenumber TKE budget from instantaneous snapshots:
+    /// spectral production, dissipation, pressure-strain and inter-scale
+    /// transfer as (z, k_y) maps over streamwise windows (HDF5).
+    TkeSpectral(TkeSpectralArgs),
+    /// Spanwise-wavelength decomposition of the FIK turbulent term
+    /// cf_turb_y from the u–w co-spectrum of instantaneous snapshots.
+    FikSpectral(FikSpectralArgs),
+    /// Mean-shear diagnostics profiles: ∂ū/∂z, eddy viscosity, mixing
+    /// length, log-law indicator and Reynolds-stress fraction.
+    Diagnostics(DiagnosticsArgs),
+    /// Wall-unit or outer-unit profiles and (nz, nx) maps of a spatially
+    /// developing flow with per-station scales u_τ(x), δ99(x), U_b(x).
+    Scaling(ScalingArgs),
+    /// Controlled vs reference case: local and integrated drag reduction,
+    /// FIK/RD term-by-term ΔC_f, wall power input, net saving and gain.
+    CompareControl(CompareControlArgs),
+    /// Detect the blowing/suction region from w̄ at the wall: streamwise
+    /// extent, slit period and duty cycle, amplitude, momentum coefficient.
+    ControlRegion(ControlRegionArgs),
+    /// FIK/RD closure against the direct C_f over a sweep of half-height,
+    /// trim, periodic_x and wall-gradient stencil, per x-region.
+    Closure(ClosureArgs),
+    /// Conservation check: divergence, momentum residual and bulk flow rate
+    /// Q(x) against tolerances, with residual fields for Paraview (HDF5).
+    Check(CheckArgs),
+    /// Scan files or a snapshot series for NaN/Inf, shape and ghost-column
+    /// consistency, coordinate sanity and a common ν; writes a report and
+    /// the list of good snapshots.
+    Validate(ValidateArgs),
+    /// Grid resolution in local wall units along x: Δx⁺, Δy⁺, Δz⁺ at the
+    /// wall and centreline, first-point z⁺, points below z⁺ = 10 and
+    /// stretching ratios, with under-resolved regions flagged.
+    Grid(GridArgs),
 }
 
 /// Hyperslab [start stride count block] for output (4 values, HDF5 convention).
@@ -109,6 +209,20 @@ struct OutputArgs {
     /// Separate file containing u for computing uτ (default: same as --file).
     #[arg(long, value_name = "FILE")]
     uout: Option<String>,
+    /// Fit the log law u⁺ = (1/κ) ln z⁺ + B over the z⁺ band ZP0 ZP1 and
+    /// report κ, B and the shift ΔU⁺ (requires --normalize and u).
+    #[arg(long, num_args = 2, value_names = ["ZP0", "ZP1"])]
+    loglaw: Option<Vec<f64>>,
+    /// Reference slope κ for ΔU⁺ and the composite profiles.
+    #[arg(long, default_value_t = 0.41)]
+    kappa_ref: f64,
+    /// Reference intercept B for ΔU⁺.
+    #[arg(long, default_value_t = 5.2)]
+    b_ref: f64,
+    /// Add Reichardt and Musker composite profiles (u_reichardt, u_musker)
+    /// as columns (requires --normalize).
+    #[arg(long, default_value_t = false)]
+    reference: bool,
     #[command(flatten)]
     block: BlockArgs,
 }
@@ -163,10 +277,16 @@ struct SpectraArgs {
 
 fn main() {
     let cli = Cli::parse();
+    set_scheme(Scheme::from_name(&cli.scheme).unwrap_or_else(|| {
+        panic!("unknown --scheme '{}' (second, fourth, pade, spectral)", cli.scheme)
+    }));
     match cli.command {
         Command::Output(args) => run_output(args),
         Command::Hdfview(args) => run_hdfview(args),
         Command::Spectra(args) => run_spectra(args),
+        Command::Spectra2d(args) => run_spectra2d(args),
+        Command::Correlation(args) => run_correlation(args),
+        Command::Scales(args) => run_scales(args),
         Command::Fik(args) => run_fik(args),
         Command::FikInst(args) => run_fik_inst(args),
         Command::Rd(args) => run_rd(args),
@@ -174,6 +294,22 @@ fn main() {
         Command::Tke(args) => run_tke(args),
         Command::TkeInst(args) => run_tke_inst(args),
         Command::Vortex(args) => run_vortex(args),
+        Command::Vorticity(args) => run_vorticity(args),
+        Command::Structures(args) => run_structures(args),
+        Command::Isosurface(args) => run_isosurface(args),
+        Command::Qr(args) => run_qr(args),
+        Command::Anisotropy(args) => run_anisotropy(args),
+        Command::MomentumBudget(args) => run_momentum_budget(args),
+        Command::TkeSpectral(args) => run_tke_spectral(args),
+        Command::FikSpectral(args) => run_fik_spectral(args),
+        Command::Diagnostics(args) => run_diagnostics(args),
+        Command::Scaling(args) => run_scaling(args),
+        Command::CompareControl(args) => run_compare_control(args),
+        Command::ControlRegion(args) => run_control_region(args),
+        Command::Closure(args) => run_closure(args),
+        Command::Check(args) => run_check(args),
+        Command::Validate(args) => run_validate(args),
+        Command::Grid(args) => run_grid(args),
     }
 }
 
@@ -254,7 +390,7 @@ fn run_output(args: OutputArgs) {
     let ret = utau / nu;
 
     // ── Optional normalization ────────────────────────────────────────────────
-    let final_profiles = if args.normalize {
+    let mut final_profiles = if args.normalize {
         let p = Profiles::new(&args.dire, nu, tau, profiles)
             .expect("Profiles::new failed");
         p.to_wall_units().expect("normalization failed")
@@ -262,6 +398,34 @@ fn run_output(args: OutputArgs) {
         profiles
     };
 
+    // ── Law-of-the-wall fit / reference profiles ──────────────────────────────
+    if args.loglaw.is_some() || args.reference {
+        assert!(args.normalize, "--loglaw and --reference need --normalize");
+        let plus_key = format!("{}plus", args.dire.chars().next().unwrap_or('z'));
+        let zplus = final_profiles[&plus_key].clone();
+        if let Some(band) = &args.loglaw {
+            let uplus = final_profiles.get("u").cloned()
+                .expect("--loglaw needs u among the --variables");
+            let band = (band[0], band[1]);
+            let fit = fit_log_law(&zplus, &uplus, band).expect("log-law fit failed");
+            let du = log_shift(&zplus, &uplus, band, args.kappa_ref, args.b_ref)
+                .expect("log-law shift failed");
+            eprintln!(
+                "log law over z⁺ ∈ [{}, {}] ({} points): κ = {:.4}, B = {:.4}, rms = {:.3e}",
+                band.0, band.1, fit.points, fit.kappa, fit.b, fit.rms
+            );
+            eprintln!("ΔU⁺ = {du:.4} relative to κ = {}, B = {}", args.kappa_ref, args.b_ref);
+            final_profiles.insert(
+                "u_logfit".into(),
+                zplus.mapv(|z| if z > 0.0 { z.ln() / fit.kappa + fit.b } else { f64::NAN }),
+            );
+        }
+        if args.reference {
+            final_profiles.insert("u_reichardt".into(), zplus.mapv(|z| reichardt(z, args.kappa_ref)));
+            final_profiles.insert("u_musker".into(), zplus.mapv(musker));
+        }
+    }
+
     // ── Write .dat ────────────────────────────────────────────────────────────
     let output_path = args.output.unwrap_or_else(|| "output".to_string());
     write_dat(Path::new(&output_path), &args.dire, ret, &final_profiles)
@@ -473,6 +637,457 @@ fn run_spectra(args: SpectraArgs) {
     );
 }
 
+// ─── spectra2d sub-command ────────────────────────────────────────────────────
+
+#[derive(Args)]
+struct Spectra2dArgs {
+    /// Instantaneous snapshot HDF5 files; all these plus any generated by
+    /// --pattern/--range are ensemble-averaged together.
+    #[arg(short, long, num_args = 1.., value_name = "FILE")]
+    files: Vec<String>,
+    /// Filename pattern with a time placeholder `{t}`, e.g. `inst_{t}_u.h5`.
+    #[arg(long, value_name = "PATTERN")]
+    pattern: Option<String>,
+    /// Timestep range START END STEP (inclusive) used with --pattern.
+    #[arg(long, num_args = 3, value_names = ["START", "END", "STEP"])]
+    range: Option<Vec<usize>>,
+    /// Dataset name to analyse (u, v, w, p, …).
+    #[arg(short, long, default_value = "u")]
+    variable: String,
+    /// Output HDF5 file with the 2-D and integrated 1-D spectra.
+    #[arg(short, long, value_name = "FILE", default_value = "spectra2d.h5")]
+    output: String,
+    /// x-index range START END (0-based, half-open) to analyse; the
+    /// streamwise grid must be uniform over it.  Default: full extent.
+    #[arg(long, num_args = 2, value_name = "INT")]
+    xrange: Option<Vec<usize>>,
+    /// Streamwise points per Hann window (windows overlap by 50 %);
+    /// default: the whole --xrange as a single window.
+    #[arg(long, value_name = "INT")]
+    window: Option<usize>,
+    /// Wall-normal locations (physical z, wall at 0) to analyse; each is
+    /// snapped to the nearest grid point.
+    #[arg(long, value_delimiter = ',', default_value = "0.05,0.1,0.2,0.5,1.0")]
+    zloc: Vec<f64>,
+}
+
+fn run_spectra2d(args: Spectra2dArgs) {
+    let files = snapshot_templates(&args.files, &args.pattern, &args.range);
+
+    let read_coord = |h5: &H5File, name: &str| -> Array1<f64> {
+        match h5
+            .coord(name)
+            .unwrap_or_else(|| panic!("coord '{name}' not found"))
+            .read_data::<f64>()
+            .unwrap()
+        {
+            H5Data::Array(a) => a.into_dimensionality::<Ix1>().unwrap(),
+            H5Data::Scalar(_) => panic!("coord '{name}' is scalar"),
+        }
+    };
+    let (y, zc, x, nu) = {
+        let mut h5 = H5File::new(&files[0]).expect("failed to open first file");
+        h5.get_info().expect("failed to read DNS info");
+        h5.load_coords().expect("failed to load coordinates");
+        let nu = h5.info().nu.expect("'nu' not in HDF5 file");
+        (
+            read_coord(&h5, "y"),
+            read_coord(&h5, "zc"),
+            read_coord(&h5, "x"),
+            nu,
+        )
+    };
+    let (ny, nxc, nz) = (y.len(), x.len(), zc.len());
+
+    let dy = y[1] - y[0];
+    for j in 1..ny {
+        assert!(
+            ((y[j] - y[j - 1]) - dy).abs() < 1e-8 * dy,
+            "spanwise grid is not uniform at j={j}"
+        );
+    }
+    let ly = dy * ny as f64;
+
+    let (x0, x1) = match &args.xrange {
+        Some(v) => (v[0], v[1]),
+        None => (0, nxc),
+    };
+    assert!(x0 < x1 && x1 <= nxc, "invalid --xrange {x0} {x1} (nx = {nxc})");
+    // a windowed streamwise FFT needs a few points to be meaningful
+    assert!(x1 - x0 >= 4, "--xrange {x0} {x1} has {} points, need at least 4", x1 - x0);
+    let dx = x[x0 + 1] - x[x0];
+    for i in x0 + 1..x1 {
+        assert!(
+            ((x[i] - x[i - 1]) - dx).abs() < 1e-8 * dx,
+            "streamwise grid is not uniform at i={i}; restrict --xrange"
+        );
+    }
+    let nxw = args.window.unwrap_or(x1 - x0);
+    assert!(nxw <= x1 - x0, "--window {nxw} longer than --xrange ({})", x1 - x0);
+    assert!(nxw >= 4, "--window {nxw} too short, need at least 4 points");
+
+    // map requested z locations to grid points (deduplicated)
+    let mut z_indices: Vec<usize> = Vec::new();
+    for &zq in &args.zloc {
+        let iz = (0..nz)
+            .min_by(|&a, &b| {
+                (zc[a] - zq).abs().partial_cmp(&(zc[b] - zq).abs()).unwrap()
+            })
+            .unwrap();
+        if !z_indices.contains(&iz) {
+            z_indices.push(iz);
+        }
+    }
+
+    // ── accumulate all snapshots at the selected heights ─────────────────────
+    let mut spec = PlaneSpectrum::new(z_indices.len(), nxw, ny, dx, ly)
+        .expect("PlaneSpectrum::new");
+    for path in &files {
+        let field = read_inst_field(path, &args.variable, nxc);
+        let planes = field
+            .slice_axis(Axis(2), (x0..x1).into())
+            .select(Axis(0), &z_indices);
+        let nwin = spec.accumulate(&planes).expect("accumulate failed");
+        eprintln!("accumulated {path} ({nwin} window(s))");
+    }
+
+    // ── write outputs ─────────────────────────────────────────────────────────
+    let (kx, ky) = spec.wavenumbers();
+    let e = spec.energy_spectrum().expect("energy_spectrum");
+    let (lambda_x, lambda_y) = spec.wavelengths();
+    let pm = spec.premultiplied().expect("premultiplied");
+    let ex = spec.streamwise_spectrum().expect("streamwise_spectrum");
+    let ey = spec.spanwise_spectrum().expect("spanwise_spectrum");
+    let var = spec.variance().expect("variance");
+    let zsel = Array1::from_iter(z_indices.iter().map(|&iz| zc[iz]));
+
+    let out = hdf5::File::create(&args.output).expect("failed to create output HDF5");
+    write_h5(&out, "kx", &kx);
+    write_h5(&out, "ky", &ky);
+    write_h5(&out, "lambda_x", &lambda_x);
+    write_h5(&out, "lambda_y", &lambda_y);
+    write_h5(&out, "zc", &zsel);
+    write_h5(&out, "e2d", &e);
+    write_h5(&out, "pm2d", &pm);
+    write_h5(&out, "ex", &ex);
+    write_h5(&out, "ey", &ey);
+    write_h5(&out, "variance", &var);
+    write_h5(&out, "nu", &Array1::from_elem(1, nu));
+    drop(out);
+    eprintln!("wrote {}", args.output);
+
+    // consistency: both 1-D spectra integrate back to the same variance
+    let (dkx, dky) = (kx[1], ky[1]);
+    eprintln!("       zc      variance   ∫E(kx)dkx   ∫E(ky)dky");
+    for (s, &z) in zsel.iter().enumerate() {
+        eprintln!(
+            "  {z:>7.4}  {:>12.5e}  {:>10.5e}  {:>10.5e}",
+            var[s],
+            ex.row(s).sum() * dkx,
+            ey.row(s).sum() * dky
+        );
+    }
+    eprintln!(
+        "ensemble: {} snapshot(s), {} windowed plane(s) per z",
+        files.len(),
+        spec.samples()
+    );
+}
+
+// ─── correlation sub-command ──────────────────────────────────────────────────
+
+#[derive(Args)]
+struct CorrelationArgs {
+    /// Instantaneous snapshot HDF5 files; all these plus any generated by
+    /// --pattern/--range are ensemble-averaged together.
+    #[arg(short, long, num_args = 1.., value_name = "FILE")]
+    files: Vec<String>,
+    /// Filename pattern with a time placeholder `{t}`, e.g. `inst_{t}_u.h5`.
+    #[arg(long, value_name = "PATTERN")]
+    pattern: Option<String>,
+    /// Timestep range START END STEP (inclusive) used with --pattern.
+    #[arg(long, num_args = 3, value_names = ["START", "END", "STEP"])]
+    range: Option<Vec<usize>>,
+    /// Dataset name to correlate (u, v, w, p, …).
+    #[arg(short, long, default_value = "u")]
+    variable: String,
+    /// Output HDF5 file; an XDMF with the same stem is written next to it.
+    #[arg(short, long, value_name = "FILE", default_value = "correlation.h5")]
+    output: String,
+    /// Reference x locations (physical), comma-separated; snapped to the grid.
+    #[arg(long, value_delimiter = ',', required = true)]
+    xref: Vec<f64>,
+    /// Reference z locations (physical, wall at 0), comma-separated; every
+    /// (xref, zref) combination is one reference point.
+    #[arg(long, value_delimiter = ',', required = true)]
+    zref: Vec<f64>,
+    /// Correlation level bounding the region used for the inclination angle.
+    #[arg(long, default_value_t = 0.3)]
+    level: f64,
+}
+
+fn run_correlation(args: CorrelationArgs) {
+    let files = snapshot_templates(&args.files, &args.pattern, &args.range);
+
+    let read_coord = |h5: &H5File, name: &str| -> Array1<f64> {
+        match h5
+            .coord(name)
+            .unwrap_or_else(|| panic!("coord '{name}' not found"))
+            .read_data::<f64>()
+            .unwrap()
+        {
+            H5Data::Array(a) => a.into_dimensionality::<Ix1>().unwrap(),
+            H5Data::Scalar(_) => panic!("coord '{name}' is scalar"),
+        }
+    };
+    let (y, zc, x, nu) = {
+        let mut h5 = H5File::new(&files[0]).expect("failed to open first file");
+        h5.get_info().expect("failed to read DNS info");
+        h5.load_coords().expect("failed to load coordinates");
+        let nu = h5.info().nu.expect("'nu' not in HDF5 file");
+        (
+            read_coord(&h5, "y"),
+            read_coord(&h5, "zc"),
+            read_coord(&h5, "x"),
+            nu,
+        )
+    };
+    let (ny, nxc, nz) = (y.len(), x.len(), zc.len());
+    let dy = y[1] - y[0];
+
+    let nearest = |c: &Array1<f64>, q: f64| -> usize {
+        (0..c.len())
+            .min_by(|&a, &b| (c[a] - q).abs().partial_cmp(&(c[b] - q).abs()).unwrap())
+            .unwrap()
+    };
+    let mut refs: Vec<(usize, usize)> = Vec::new();
+    for &xq in &args.xref {
+        for &zq in &args.zref {
+            let r = (nearest(&zc, zq), nearest(&x, xq));
+            if !refs.contains(&r) {
+                refs.push(r);
+            }
+        }
+    }
+
+    // ── accumulate all snapshots ──────────────────────────────────────────────
+    let mut pc = PlaneCorrelation::new(nz, ny, nxc, &refs).expect("PlaneCorrelation::new");
+    for path in &files {
+        let field = read_inst_field(path, &args.variable, nxc);
+        pc.accumulate(&field).expect("accumulate failed");
+        eprintln!("accumulated {path}");
+    }
+
+    // ── write HDF5: one (nz, 1, nx) map per reference point + scales ─────────
+    let var = &args.variable;
+    let out = hdf5::File::create(&args.output).expect("failed to create output HDF5");
+    write_h5(&out, "x", &x);
+    write_h5(&out, "y", &Array1::from_elem(1, 0.0));
+    write_h5(&out, "zc", &zc);
+    write_h5(&out, "nu", &Array1::from_elem(1, nu));
+    write_h5(&out, "ydel", &Array1::from_iter((0..ny / 2 + 1).map(|j| j as f64 * dy)));
+
+    let nref = refs.len();
+    let mut table: HashMap<&str, Array1<f64>> = ["xref", "zref", "lx", "ly", "lz", "angle"]
+        .into_iter()
+        .map(|k| (k, Array1::zeros(nref)))
+        .collect();
+    let mut names: Vec<String> = Vec::new();
+    eprintln!("      xref      zref          lx          ly          lz   angle(deg)");
+    for (k, &(iz, ix)) in refs.iter().enumerate() {
+        let r = pc.correlation(k).expect("correlation");
+        let ry = pc.spanwise_correlation(k).expect("spanwise_correlation");
+        let sc = correlation_scales(r.view(), ry.view(), (iz, ix), &x, &zc, dy, args.level);
+
+        let name = format!("r{var}_{k}");
+        write_h5(&out, &name, &r.clone().insert_axis(Axis(1)));
+        write_h5(&out, &format!("r{var}y_{k}"), &ry);
+        names.push(name);
+
+        for (key, v) in [
+            ("xref", x[ix]), ("zref", zc[iz]),
+            ("lx", sc.lx), ("ly", sc.ly), ("lz", sc.lz), ("angle", sc.angle),
+        ] {
+            table.get_mut(key).unwrap()[k] = v;
+        }
+        eprintln!(
+            "  {:>8.4}  {:>8.4}  {:>10.4e}  {:>10.4e}  {:>10.4e}  {:>9.2}",
+            x[ix], zc[iz], sc.lx, sc.ly, sc.lz, sc.angle
+        );
+    }
+    for (key, v) in &table {
+        write_h5(&out, key, v);
+    }
+    drop(out);
+    eprintln!("wrote {}", args.output);
+
+    // ── XDMF for Paraview ─────────────────────────────────────────────────────
+    let hdf5_abs = std::fs::canonicalize(&args.output)
+        .map(|p| p.to_string_lossy().into_owned())
+        .unwrap_or_else(|_| args.output.clone());
+    let specs: Vec<VarSpec> = names
+        .into_iter()
+        .map(|name| VarSpec {
+            name,
+            full_shape: [nz, 1, nxc],
+            blockz: [0, 1, nz],
+            blocky: [0, 1, 1],
+            blockx: [0, 1, nxc],
+        })
+        .collect();
+    write_xdmf(
+        Path::new(&args.output),
+        &hdf5_abs,
+        "x", "y", "zc",
+        nxc, 1, nz,
+        [0, 1, nxc], [0, 1, 1], [0, 1, nz],
+        &specs,
+    ).expect("Failed to write XDMF file");
+    eprintln!("ensemble: {} snapshot(s), {} y-lines", files.len(), pc.samples());
+}
+
+// ─── scales sub-command ───────────────────────────────────────────────────────
+
+#[derive(Args)]
+struct ScalesArgs {
+    /// Instantaneous snapshot HDF5 files for the integral and Taylor scales;
+    /// all these plus any generated by --pattern/--range are ensemble-averaged.
+    #[arg(short, long, num_args = 1.., value_name = "FILE")]
+    files: Vec<String>,
+    /// Filename pattern with a time placeholder `{t}`, e.g. `inst_{t}_u.h5`.
+    #[arg(long, value_name = "PATTERN")]
+    pattern: Option<String>,
+    /// Timestep range START END STEP (inclusive) used with --pattern.
+    #[arg(long, num_args = 3, value_names = ["START", "END", "STEP"])]
+    range: Option<Vec<usize>>,
+    /// Dataset name the integral/Taylor scales are computed for.
+    #[arg(short, long, default_value = "u")]
+    variable: String,
+    /// Subavg HDF5 file with the stored gradient moments; the TKE dissipation
+    /// of its budget gives the Kolmogorov scales and Δ/η.
+    #[arg(short, long, value_name = "FILE")]
+    budget: Option<String>,
+    /// Output stem: writes <stem>.h5 with (nz, nx) maps and <stem>.dat
+    /// profiles (or <stem>_x<pos>.dat per --xloc station).
+    #[arg(short, long, default_value = "scales")]
+    output: String,
+    /// Streamwise separations (grid points) used for R(Δx).
+    #[arg(long, default_value_t = 64usize)]
+    nsep: usize,
+    /// Physical x locations for wall-normal profiles, comma-separated.
+    #[arg(long, value_delimiter = ',')]
+    xloc: Option<Vec<f64>>,
+    /// x-index range START END (0-based, half-open) to average the profiles
+    /// over when --xloc is not given; default: full streamwise extent.
+    #[arg(long, num_args = 2, value_name = "INT")]
+    xrange: Option<Vec<usize>>,
+    /// Treat streamwise direction as periodic for derivative stencils.
+    #[arg(long, default_value_t = false)]
+    periodic_x: bool,
+}
+
+fn run_scales(args: ScalesArgs) {
+    let has_snapshots = !args.files.is_empty() || args.pattern.is_some();
+    assert!(
+        has_snapshots || args.budget.is_some(),
+        "nothing to do: give snapshots (-f/--pattern) and/or --budget"
+    );
+    let files = if has_snapshots {
+        snapshot_templates(&args.files, &args.pattern, &args.range)
+    } else {
+        Vec::new()
+    };
+
+    let read_coord = |h5: &H5File, name: &str| -> Array1<f64> {
+        match h5
+            .coord(name)
+            .unwrap_or_else(|| panic!("coord '{name}' not found"))
+            .read_data::<f64>()
+            .unwrap()
+        {
+            H5Data::Array(a) => a.into_dimensionality::<Ix1>().unwrap(),
+            H5Data::Scalar(_) => panic!("coord '{name}' is scalar"),
+        }
+    };
+    let first = files.first().or(args.budget.as_ref()).unwrap();
+    let (x, y, zc, nu) = {
+        let mut h5 = H5File::new(first).expect("failed to open HDF5 file");
+        h5.get_info().expect("failed to read DNS info");
+        h5.load_coords().expect("failed to load coordinates");
+        let nu = h5.info().nu.expect("'nu' not in HDF5 file");
+        (read_coord(&h5, "x"), read_coord(&h5, "y"), read_coord(&h5, "zc"), nu)
+    };
+    let (nxc, nz) = (x.len(), zc.len());
+
+    let mut maps: Vec<(String, ArrayD<f64>)> = Vec::new();
+
+    // ── integral and Taylor scales from the snapshots ─────────────────────────
+    if has_snapshots {
+        let mut acc = ScaleAccumulator::new(nz, &x, &y, args.nsep, args.periodic_x)
+            .expect("ScaleAccumulator::new");
+        for path in &files {
+            let field = read_inst_field(path, &args.variable, nxc);
+            acc.accumulate(&field).expect("accumulate failed");
+            eprintln!("accumulated {path}");
+        }
+        let var = &args.variable;
+        let (lx, ly) = acc.integral_scales().expect("integral_scales");
+        let (tx, ty) = acc.taylor_microscales().expect("taylor_microscales");
+        maps.push((format!("{var}{var}"), acc.variance().unwrap().into_dyn()));
+        maps.push((format!("lx_{var}"), lx.into_dyn()));
+        maps.push((format!("ly_{var}"), ly.into_dyn()));
+        maps.push((format!("taylor_x_{var}"), tx.into_dyn()));
+        maps.push((format!("taylor_y_{var}"), ty.into_dyn()));
+        eprintln!("ensemble: {} snapshot(s), {} y-lines per (z, x)", files.len(), acc.samples());
+    }
+
+    // ── Kolmogorov scales from the budget dissipation ─────────────────────────
+    if let Some(budget) = &args.budget {
+        let mut loader = |name: &str| -> Result<ArrayD<f64>, hdf5::Error> {
+            Ok(read_inst_field(budget, name, nxc))
+        };
+        let mut eps: Option<ArrayD<f64>> = None;
+        for i in 0..3 {
+            let b = stress_budget(&mut loader, i, i, &x, &y, &zc, nu, args.periodic_x)
+                .unwrap_or_else(|e| panic!("budget failed: {e}"));
+            let half = b.visc_diss * 0.5;
+            eps = Some(match eps {
+                None => half,
+                Some(e) => e + &half,
+            });
+        }
+        let eps = eps.unwrap();
+        let ks = kolmogorov_scales(&eps, nu).expect("kolmogorov_scales");
+        let [rx, ry, rz] = grid_ratios(&ks.eta, &x, &y, &zc).expect("grid_ratios");
+        maps.push(("eps".into(), eps));
+        maps.push(("eta".into(), ks.eta));
+        maps.push(("tau_eta".into(), ks.tau));
+        maps.push(("v_eta".into(), ks.vel));
+        maps.push(("dx_eta".into(), rx.into_dyn()));
+        maps.push(("dy_eta".into(), ry.into_dyn()));
+        maps.push(("dz_eta".into(), rz.into_dyn()));
+    }
+
+    // ── write (nz, nx) maps and profiles ──────────────────────────────────────
+    let stem = args.output.strip_suffix(".dat").unwrap_or(&args.output);
+    let h5_path = format!("{stem}.h5");
+    let out = hdf5::File::create(&h5_path).expect("failed to create output HDF5");
+    write_h5(&out, "x", &x);
+    write_h5(&out, "zc", &zc);
+    write_h5(&out, "nu", &Array1::from_elem(1, nu));
+    for (name, a) in &maps {
+        write_h5(&out, name, a);
+    }
+    drop(out);
+    eprintln!("wrote {h5_path}");
+
+    let fields: Vec<(&str, &ArrayD<f64>)> = maps.iter().map(|(n, a)| (n.as_str(), a)).collect();
+    write_profile_outputs(
+        &fields, &x, &zc, 1.0 / nu,
+        &args.xloc, &args.xrange, &format!("{stem}.dat"),
+    );
+}
+
 // ─── fik sub-command ──────────────────────────────────────────────────────────
 
 #[derive(Args)]
@@ -497,6 +1112,10 @@ struct FikArgs {
     /// x-range, e.g. to discard a corrupted tail.
     #[arg(long, default_value_t = 0usize)]
     trim_end: usize,
+    /// `auto`: detect the trim from the residuals and the C_f mismatch along
+    /// x (overrides --trim-start/--trim-end; writes `<stem>_trim.dat`).
+    #[arg(long, value_name = "auto")]
+    trim: Option<String>,
     /// Treat streamwise direction as periodic for derivative stencils.
     #[arg(long, default_value_t = false)]
     periodic_x: bool,
@@ -564,8 +1183,16 @@ fn run_fik(args: FikArgs) {
 
     // Trim a corrupted lead-in / tail from the streamwise range.
     let nx_full = x_full.len();
-    let lo = args.trim_start;
-    let hi = nx_full - args.trim_end;
+    let (trim_start, trim_end) = if trim_auto(&args.trim) {
+        let y = load_coord(&mut h5, "y");
+        let [uv, vv, vw, ww] = ["uv", "vv", "vw", "ww"].map(|name| strip(load(&mut h5, name)));
+        let f = [&u, &v, &w, &p, &uu, &uv, &uw, &vv, &vw, &ww];
+        auto_trim(f, &x_full, &y, &zc, nu, args.half_height, args.periodic_x, &args.output)
+    } else {
+        (args.trim_start, args.trim_end)
+    };
+    let lo = trim_start;
+    let hi = nx_full - trim_end;
     assert!(lo < hi, "trim_start ({lo}) must be < nx - trim_end ({hi})");
     let trim = |a: ArrayD<f64>| -> ArrayD<f64> {
         a.slice_axis(Axis(2), (lo..hi).into()).to_owned()
@@ -667,6 +1294,10 @@ struct RdArgs {
     /// x-range, e.g. to discard a corrupted tail.
     #[arg(long, default_value_t = 0usize)]
     trim_end: usize,
+    /// `auto`: detect the trim from the residuals and the C_f mismatch along
+    /// x (overrides --trim-start/--trim-end; writes `<stem>_trim.dat`).
+    #[arg(long, value_name = "auto")]
+    trim: Option<String>,
     /// Treat streamwise direction as periodic for derivative stencils.
     #[arg(long, default_value_t = false)]
     periodic_x: bool,
@@ -710,20 +1341,32 @@ fn run_rd(args: RdArgs) {
     let x_full = load_coord(&mut h5, "x");
     let zc     = load_coord(&mut h5, "zc");
 
+    let strip = |a: ArrayD<f64>| -> ArrayD<f64> {
+        a.slice_axis(Axis(2), (g..nx_ghost - g).into()).to_owned()
+    };
+    let (u, w, p, uu, uw) = (strip(u_raw), strip(w_raw), strip(p_raw), strip(uu_raw), strip(uw_raw));
+
     let nx_full = x_full.len();
-    let lo = args.trim_start;
-    let hi = nx_full - args.trim_end;
+    let (trim_start, trim_end) = if trim_auto(&args.trim) {
+        let y = load_coord(&mut h5, "y");
+        let [v, uv, vv, vw, ww] = ["v", "uv", "vv", "vw", "ww"].map(|name| strip(load(&mut h5, name)));
+        let f = [&u, &v, &w, &p, &uu, &uv, &uw, &vv, &vw, &ww];
+        auto_trim(f, &x_full, &y, &zc, nu, args.half_height, args.periodic_x, &args.output)
+    } else {
+        (args.trim_start, args.trim_end)
+    };
+    let lo = trim_start;
+    let hi = nx_full - trim_end;
     assert!(lo < hi, "trim_start ({lo}) must be < nx - trim_end ({hi})");
 
     let cut = |a: ArrayD<f64>| -> ArrayD<f64> {
-        let stripped = a.slice_axis(Axis(2), (g..nx_ghost - g).into()).to_owned();
-        stripped.slice_axis(Axis(2), (lo..hi).into()).to_owned()
-    };
-    let u  = cut(u_raw);
-    let w  = cut(w_raw);
-    let p  = cut(p_raw);
-    let uu = cut(uu_raw);
-    let uw = cut(uw_raw);
+        a.slice_axis(Axis(2), (lo..hi).into()).to_owned()
+    };
+    let u  = cut(u);
+    let w  = cut(w);
+    let p  = cut(p);
+    let uu = cut(uu);
+    let uw = cut(uw);
     let x  = x_full.slice(s![lo..hi]).to_owned();
 
     // Direct C_f from viscous sublayer: 2ν·u(iz=1) / zc[1]
@@ -803,9 +1446,77 @@ fn write_rd_dat(
     eprintln!("wrote {path}");
 }
 
-// ─── shared: instantaneous-snapshot ensemble ──────────────────────────────────
+// ─── shared: automatic trim detection ─────────────────────────────────────────
 
-/// Build the full snapshot list from explicit templates plus an optional
+/// Whether `--trim` asks for automatic detection.
+fn trim_auto(trim: &Option<String>) -> bool {
+    match trim.as_deref() {
+        None => false,
+        Some("auto") => true,
+        Some(t) => panic!("unknown --trim '{t}' (expected auto)"),
+    }
+}
+
+/// Propose (trim_start, trim_end) from the untrimmed mean fields `[u, v, w,
+/// p, uu, uv, uw, vv, vw, ww]` (total second moments).  Indicators along x,
+/// each the RMS over (z, y) at every station:
+///   res     RANS x-momentum residual of `ns::rans_momentum_residual`
+///   div     continuity `ns::divergence`
+///   cf_err  |C_f,FIK − C_f,direct|
+/// are combined by `trim::trim_score`, so the trim measures what `check`
+/// reports; the diagnostic table is written to `<stem>_trim.dat` next to
+/// `output`.
+#[allow(clippy::too_many_arguments)]
+fn auto_trim(
+    f: [&ArrayD<f64>; 10],
+    x: &Array1<f64>,
+    y: &Array1<f64>,
+    zc: &Array1<f64>,
+    nu: f64,
+    h: f64,
+    periodic_x: bool,
+    output: &str,
+) -> (usize, usize) {
+    // points above TOL × the interior level, in runs of at least RUN, are kept
+    const TOL: f64 = 4.0;
+    const RUN: usize = 5;
+    let [u, v, w, p, uu, uv, uw, vv, vw, ww] = f;
+    let periodic = [false, true, periodic_x];
+    let (res, _, _) = rans_momentum_residual(u, v, w, p, uu, uv, uw, vv, vw, ww, x, y, zc, nu, periodic)
+        .expect("rans_momentum_residual failed");
+    let div = divergence(u, v, w, x, y, zc, periodic).expect("divergence failed");
+    let us = avg_axis(u, 1).expect("spanwise average failed");
+
+    let re_b = 1.0 / nu;
+    let fik = fik_decomposition(u, u, w, p, uu, uw, x, zc, re_b, h, periodic_x)
+        .expect("fik_decomposition failed");
+    let iz1 = 1usize;
+    let cf_direct = us.index_axis(Axis(0), iz1).mapv(|uv| 2.0 * nu * uv / zc[iz1]);
+    let cf_err = (&fik.cf_total() - &cf_direct.into_dimensionality::<Ix1>().unwrap()).mapv(f64::abs);
+
+    let (res, div) = (x_profile_rms(&res), x_profile_rms(&div));
+    let score = trim_score(&[res.clone(), div.clone(), cf_err.clone()]).expect("trim score failed");
+    let (t0, t1) = detect_trim(&score, TOL, RUN).expect("automatic trim detection failed");
+    let nx = x.len();
+    eprintln!("auto trim: --trim-start {t0} --trim-end {t1}  (x ∈ [{:.4}, {:.4}])", x[t0], x[nx - 1 - t1]);
+
+    let mut cols: HashMap<String, Array1<f64>> = HashMap::new();
+    cols.insert("x".into(), x.clone());
+    cols.insert("res".into(), res);
+    cols.insert("div".into(), div);
+    cols.insert("cf_err".into(), cf_err);
+    cols.insert("score".into(), score);
+    cols.insert("keep".into(), Array1::from_shape_fn(nx, |i| if i >= t0 && i < nx - t1 { 1.0 } else { 0.0 }));
+    let stem = output.strip_suffix(".dat").unwrap_or(output);
+    let path = format!("{stem}_trim.dat");
+    write_dat(Path::new(&path), "x", re_b, &cols).expect("failed to write .dat file");
+    eprintln!("wrote {path}");
+    (t0, t1)
+}
+
+// ─── shared: instantaneous-snapshot ensemble ──────────────────────────────────
+
+/// Build the full snapshot list from explicit templates plus an optional
 /// `--pattern`/`--range` series.  Pattern entries have their `{t}` placeholder
 /// replaced by each timestep `START, START+STEP, …, ≤ END`; the component
 /// placeholder `{}` is left for [`inst_path`].
@@ -889,6 +1600,8 @@ struct InstEnsemble {
     uu: ArrayD<f64>,
     uw: ArrayD<f64>,
     uv: ArrayD<f64>,  // empty ([0]) unless `with_uv`
+    pw: ArrayD<f64>,  // empty ([0]) unless `with_power`
+    www: ArrayD<f64>, // empty ([0]) unless `with_power`
     x: Array1<f64>,
     y: Array1<f64>,
     zc: Array1<f64>,
@@ -897,8 +1610,9 @@ struct InstEnsemble {
 
 /// Accumulate ensemble means and second moments over the given snapshot
 /// templates.  With `with_uv` also accumulates v̄ and ⟨u·v⟩ (needed for the
-/// per-plane FIK spanwise terms); otherwise those are left empty to save memory.
-fn accumulate_inst(files: &[String], with_uv: bool) -> InstEnsemble {
+/// per-plane FIK spanwise terms), and with `with_power` ⟨p·w⟩ and ⟨w·w·w⟩ (the
+/// wall power input); otherwise those are left empty to save memory.
+fn accumulate_inst(files: &[String], with_uv: bool, with_power: bool) -> InstEnsemble {
     assert!(!files.is_empty(), "need at least one snapshot file");
 
     let read_coord = |h5: &H5File, name: &str| -> Array1<f64> {
@@ -923,8 +1637,8 @@ fn accumulate_inst(files: &[String], with_uv: bool) -> InstEnsemble {
     let nxc = x.len();
     let empty = || ArrayD::<f64>::zeros(ndarray::IxDyn(&[0]));
 
-    // Accumulators: [u, v, w, p, uu, uw, uv].
-    let mut acc: Option<[ArrayD<f64>; 7]> = None;
+    // Accumulators: [u, v, w, p, uu, uw, uv, pw, www].
+    let mut acc: Option<[ArrayD<f64>; 9]> = None;
     for f in files {
         let u = read_inst_field(&inst_path(f, 'u'), "u", nxc);
         let w = read_inst_field(&inst_path(f, 'w'), "w", nxc);
@@ -938,12 +1652,18 @@ fn accumulate_inst(files: &[String], with_uv: bool) -> InstEnsemble {
         } else {
             (empty(), empty())
         };
+        let (pw, www) = if with_power {
+            (&p * &w, &w * &w * &w)
+        } else {
+            (empty(), empty())
+        };
 
         match &mut acc {
-            None => acc = Some([u, v, w, p, uu, uw, uv]),
+            None => acc = Some([u, v, w, p, uu, uw, uv, pw, www]),
             Some(a) => {
                 a[0] += &u;  a[1] += &v;  a[2] += &w;  a[3] += &p;
-                a[4] += &uu; a[5] += &uw; a[6] += &uv;
+                a[4] += &uu; a[5] += &uw; a[6] += &uv; a[7] += &pw;
+                a[8] += &www;
             }
         }
         eprintln!("accumulated {f}");
@@ -954,8 +1674,8 @@ fn accumulate_inst(files: &[String], with_uv: bool) -> InstEnsemble {
     for arr in a.iter_mut() {
         *arr /= n;
     }
-    let [u, v, w, p, uu, uw, uv] = a;
-    InstEnsemble { u, v, w, p, uu, uw, uv, x, y, zc, nu }
+    let [u, v, w, p, uu, uw, uv, pw, www] = a;
+    InstEnsemble { u, v, w, p, uu, uw, uv, pw, www, x, y, zc, nu }
 }
 
 /// Fold the spanwise axis (axis 1) of a `(nz, ny, nx)` field onto `nphase`
@@ -1011,6 +1731,10 @@ struct FikInstArgs {
     /// outflow tail).
     #[arg(long, default_value_t = 0usize)]
     trim_end: usize,
+    /// `auto`: detect the trim from the residuals and the C_f mismatch along
+    /// x (overrides --trim-start/--trim-end; writes `<stem>_trim.dat`).
+    #[arg(long, value_name = "auto")]
+    trim: Option<String>,
     /// Treat streamwise direction as periodic for derivative stencils.
     #[arg(long, default_value_t = false)]
     periodic_x: bool,
@@ -1036,14 +1760,22 @@ fn run_fik_inst(args: FikInstArgs) {
     // spanwise flux terms vanish, so we fall through to the averaged core.
     let with_uv = (args.per_plane || args.planes.is_some()) && args.nphase > 1;
     let files = snapshot_templates(&args.files, &args.pattern, &args.range);
-    let ens = accumulate_inst(&files, with_uv);
+    let ens = accumulate_inst(&files, with_uv, false);
     let nu = ens.nu;
     let re_b = 1.0 / nu;
 
     // Trim a corrupted lead-in / tail from the streamwise range.
     let nx_full = ens.x.len();
-    let lo = args.trim_start;
-    let hi = nx_full - args.trim_end;
+    let (trim_start, trim_end) = if trim_auto(&args.trim) {
+        // the residual needs the full stress tensor: a second pass over the files
+        let t = accumulate_inst_tke(&files);
+        let f = [&t.u, &t.v, &t.w, &ens.p, &t.uu, &t.uv, &t.uw, &t.vv, &t.vw, &t.ww];
+        auto_trim(f, &ens.x, &ens.y, &ens.zc, nu, args.half_height, args.periodic_x, &args.output)
+    } else {
+        (args.trim_start, args.trim_end)
+    };
+    let lo = trim_start;
+    let hi = nx_full - trim_end;
     assert!(lo < hi, "trim_start ({lo}) must be < nx - trim_end ({hi})");
     let trim = |a: &ArrayD<f64>| a.slice_axis(Axis(2), (lo..hi).into()).to_owned();
 
@@ -1153,6 +1885,10 @@ struct RdInstArgs {
     /// Number of points to drop from the end of the x-range.
     #[arg(long, default_value_t = 0usize)]
     trim_end: usize,
+    /// `auto`: detect the trim from the residuals and the C_f mismatch along
+    /// x (overrides --trim-start/--trim-end; writes `<stem>_trim.dat`).
+    #[arg(long, value_name = "auto")]
+    trim: Option<String>,
     /// Treat streamwise direction as periodic for derivative stencils.
     #[arg(long, default_value_t = false)]
     periodic_x: bool,
@@ -1160,13 +1896,21 @@ struct RdInstArgs {
 
 fn run_rd_inst(args: RdInstArgs) {
     let files = snapshot_templates(&args.files, &args.pattern, &args.range);
-    let ens = accumulate_inst(&files, false);
+    let ens = accumulate_inst(&files, false, false);
     let nu = ens.nu;
     let re_b = 1.0 / nu;
 
     let nx_full = ens.x.len();
-    let lo = args.trim_start;
-    let hi = nx_full - args.trim_end;
+    let (trim_start, trim_end) = if trim_auto(&args.trim) {
+        // the residual needs the full stress tensor: a second pass over the files
+        let t = accumulate_inst_tke(&files);
+        let f = [&t.u, &t.v, &t.w, &ens.p, &t.uu, &t.uv, &t.uw, &t.vv, &t.vw, &t.ww];
+        auto_trim(f, &ens.x, &ens.y, &ens.zc, nu, args.half_height, args.periodic_x, &args.output)
+    } else {
+        (args.trim_start, args.trim_end)
+    };
+    let lo = trim_start;
+    let hi = nx_full - trim_end;
     assert!(lo < hi, "trim_start ({lo}) must be < nx - trim_end ({hi})");
     let trim = |a: &ArrayD<f64>| a.slice_axis(Axis(2), (lo..hi).into()).to_owned();
 
@@ -1207,10 +1951,13 @@ struct TkeArgs {
     #[arg(short, long, value_name = "FILE", default_value = "tke.dat")]
     output: String,
     /// Budget mode: full transport budget of the given component(s)
-    /// (uu, vv, ww, uv, uw, vw, or tke = half-trace), comma-separated.
+    /// (uu, vv, ww, uv, uw, vw, tke = half-trace, or mke = mean kinetic
+    /// energy ½ū_iū_i), comma-separated.
     /// Each file gets columns stress/prod/turb_trans/visc_trans/press_strain/
-    /// press_trans/visc_diss/conv/balance; several components write one file
-    /// `<stem>_<comp>.dat` each.  Without this flag: the plain stress profiles.
+    /// press_trans/visc_diss/conv/balance (no press_strain for mke, whose
+    /// prod is the production sink ⟨u_i'u_k'⟩∂ū_i/∂x_k); several components
+    /// write one file `<stem>_<comp>.dat` each.  Without this flag: the plain
+    /// stress profiles.
     #[arg(long, value_delimiter = ',')]
     component: Option<Vec<String>>,
     /// Physical x locations for wall-normal profiles, comma-separated; each is
@@ -1262,7 +2009,7 @@ fn run_tke(args: TkeArgs) {
                 "uu" => (0, 0), "vv" => (1, 1), "ww" => (2, 2),
                 "uv" => (0, 1), "uw" => (0, 2), "vw" => (1, 2),
                 other => panic!(
-                    "unknown component '{other}' (use uu/vv/ww/uv/uw/vw/tke)"
+                    "unknown component '{other}' (use uu/vv/ww/uv/uw/vw/tke/mke)"
                 ),
             };
             stress_budget(&mut loader, i, j, &x, &y, &zc, nu, args.periodic_x)
@@ -1272,14 +2019,23 @@ fn run_tke(args: TkeArgs) {
         let single = comps.len() == 1;
         let stem = args.output.strip_suffix(".dat").unwrap_or(&args.output).to_string();
         for comp in comps {
-            let b = if comp == "tke" {
+            let b = match comp.as_str() {
                 // k-budget = half the trace of the diagonal budgets
-                budget_of("uu").axpy(1.0, &budget_of("vv")).axpy(1.0, &budget_of("ww")).scale(0.5)
-            } else {
-                budget_of(comp)
+                "tke" => budget_of("uu")
+                    .axpy(1.0, &budget_of("vv"))
+                    .axpy(1.0, &budget_of("ww"))
+                    .scale(0.5),
+                "mke" => {
+                    let mut load = |name: &str| -> Result<ArrayD<f64>, hdf5::Error> {
+                        Ok(read_inst_field(&args.file, name, nxc))
+                    };
+                    mke_budget(&mut load, &x, &y, &zc, nu, args.periodic_x)
+                        .unwrap_or_else(|e| panic!("budget for 'mke' failed: {e}"))
+                }
+                _ => budget_of(comp),
             };
             let balance = b.balance();
-            let fields: Vec<(&str, &ArrayD<f64>)> = vec![
+            let mut fields: Vec<(&str, &ArrayD<f64>)> = vec![
                 (comp.as_str(), &b.stress),
                 ("prod", &b.prod),
                 ("turb_trans", &b.turb_trans),
@@ -1290,6 +2046,9 @@ fn run_tke(args: TkeArgs) {
                 ("conv", &b.conv),
                 ("balance", &balance),
             ];
+            if comp == "mke" {
+                fields.retain(|(name, _)| *name != "press_strain");
+            }
             let path = if single {
                 args.output.clone()
             } else {
@@ -1467,33 +2226,52 @@ fn write_profile_outputs(
     xrange: &Option<Vec<usize>>,
     output: &str,
 ) {
+    for (path, cols) in profile_tables(fields, x, zc, xloc, xrange, output) {
+        write_dat(Path::new(&path), "zc", ret, &cols)
+            .expect("failed to write .dat file");
+        eprintln!("wrote {path}");
+    }
+}
+
+/// Collapse named `(nz, nx)` fields to wall-normal profile tables (with a
+/// `zc` column), one `(path, columns)` pair per output file: one per `xloc`
+/// station (`<stem>_x<pos>.dat`, nearest grid point) or a single `output`
+/// averaged over `xrange` (default: the full streamwise extent).
+fn profile_tables(
+    fields: &[(&str, &ArrayD<f64>)],
+    x:  &Array1<f64>,
+    zc: &Array1<f64>,
+    xloc: &Option<Vec<f64>>,
+    xrange: &Option<Vec<usize>>,
+    output: &str,
+) -> Vec<(String, HashMap<String, Array1<f64>>)> {
     let nx = x.len();
-    let write = |profile: &dyn Fn(&ArrayD<f64>) -> Array1<f64>, path: &str| {
+    let table = |profile: &dyn Fn(&ArrayD<f64>) -> Array1<f64>| {
         let mut cols: HashMap<String, Array1<f64>> = HashMap::new();
         cols.insert("zc".into(), zc.clone());
         for (name, f) in fields {
             cols.insert((*name).into(), profile(f));
         }
-        write_dat(Path::new(path), "zc", ret, &cols)
-            .expect("failed to write .dat file");
-        eprintln!("wrote {path}");
+        cols
     };
 
     match xloc {
         Some(locs) => {
             let stem = output.strip_suffix(".dat").unwrap_or(output).to_string();
-            for &xq in locs {
-                let ix = (0..nx)
-                    .min_by(|&a, &b| {
-                        (x[a] - xq).abs().partial_cmp(&(x[b] - xq).abs()).unwrap()
-                    })
-                    .unwrap();
-                let station = |f: &ArrayD<f64>| -> Array1<f64> {
-                    f.slice(s![.., ix]).to_owned()
-                        .into_dimensionality::<Ix1>().unwrap()
-                };
-                write(&station, &format!("{stem}_x{:.2}.dat", x[ix]));
-            }
+            locs.iter()
+                .map(|&xq| {
+                    let ix = (0..nx)
+                        .min_by(|&a, &b| {
+                            (x[a] - xq).abs().partial_cmp(&(x[b] - xq).abs()).unwrap()
+                        })
+                        .unwrap();
+                    let station = |f: &ArrayD<f64>| -> Array1<f64> {
+                        f.slice(s![.., ix]).to_owned()
+                            .into_dimensionality::<Ix1>().unwrap()
+                    };
+                    (format!("{stem}_x{:.2}.dat", x[ix]), table(&station))
+                })
+                .collect()
         }
         None => {
             let (i0, i1) = match xrange {
@@ -1506,8 +2284,332 @@ fn write_profile_outputs(
                     .mean_axis(Axis(1)).expect("mean over x")
                     .into_dimensionality::<Ix1>().unwrap()
             };
-            write(&xmean, output);
+            vec![(output.to_string(), table(&xmean))]
+        }
+    }
+}
+
+// ─── momentum-budget sub-command ──────────────────────────────────────────────
+
+#[derive(Args)]
+struct MomentumBudgetArgs {
+    /// Input subavg HDF5 file (u, v, w, p and the total moments uu/uv/uw).
+    #[arg(short, long, value_name = "FILE")]
+    file: String,
+    /// Output .dat file (default: momentum.dat).  With --xloc, one file
+    /// `<output stem>_x<pos>.dat` per station instead.
+    #[arg(short, long, value_name = "FILE", default_value = "momentum.dat")]
+    output: String,
+    /// Physical x locations for wall-normal profiles, comma-separated; each is
+    /// snapped to the nearest grid point and written to its own file.
+    #[arg(long, value_delimiter = ',')]
+    xloc: Option<Vec<f64>>,
+    /// x-index range START END (0-based, half-open) to average the profiles
+    /// over when --xloc is not given; default: full streamwise extent.
+    #[arg(long, num_args = 2, value_name = "INT")]
+    xrange: Option<Vec<usize>>,
+    /// Treat streamwise direction as periodic for derivative stencils.
+    #[arg(long, default_value_t = false)]
+    periodic_x: bool,
+}
+
+fn run_momentum_budget(args: MomentumBudgetArgs) {
+    let mut h5 = H5File::new(&args.file).expect("failed to open HDF5 file");
+    h5.get_info().expect("failed to read DNS info");
+    h5.load_coords().expect("failed to load coordinates");
+    let nu = h5.info().nu.expect("'nu' not in HDF5 file");
+    let load_coord = |h5: &H5File, name: &str| -> Array1<f64> {
+        match h5.coord(name)
+            .unwrap_or_else(|| panic!("coord '{name}' not found"))
+            .read_data::<f64>().unwrap()
+        {
+            H5Data::Array(a) => a.into_dimensionality::<Ix1>().unwrap(),
+            H5Data::Scalar(_) => panic!("coord '{name}' is scalar"),
+        }
+    };
+    let x  = load_coord(&h5, "x");
+    let y  = load_coord(&h5, "y");
+    let zc = load_coord(&h5, "zc");
+    let nxc = x.len();
+    drop(h5);
+
+    let mut loader = |name: &str| -> Result<ArrayD<f64>, hdf5::Error> {
+        Ok(read_inst_field(&args.file, name, nxc))
+    };
+    let mb = momentum_budget(&mut loader, &x, &y, &zc, nu, args.periodic_x)
+        .expect("momentum_budget failed");
+    let residual = mb.residual();
+
+    // magnitude of the largest term, to judge the residual against
+    let terms = [
+        &mb.adv_x, &mb.adv_y, &mb.adv_z, &mb.rs_x, &mb.rs_y, &mb.rs_z,
+        &mb.dpdx, &mb.visc_x, &mb.visc_y, &mb.visc_z,
+    ];
+    let scale = terms.iter().flat_map(|t| t.iter()).fold(0.0_f64, |m, v| m.max(v.abs()));
+    let worst = residual.iter().fold(0.0_f64, |m, v| m.max(v.abs()));
+    eprintln!("max |residual| = {worst:.4e}  (max |term| = {scale:.4e})");
+
+    let fields: Vec<(&str, &ArrayD<f64>)> = vec![
+        ("adv_x", &mb.adv_x), ("adv_y", &mb.adv_y), ("adv_z", &mb.adv_z),
+        ("rs_x", &mb.rs_x), ("rs_y", &mb.rs_y), ("rs_z", &mb.rs_z),
+        ("dpdx", &mb.dpdx),
+        ("visc_x", &mb.visc_x), ("visc_y", &mb.visc_y), ("visc_z", &mb.visc_z),
+        ("res_x", &residual),
+    ];
+    write_profile_outputs(&fields, &x, &zc, 1.0 / nu, &args.xloc, &args.xrange, &args.output);
+}
+
+// ─── anisotropy sub-command ───────────────────────────────────────────────────
+
+#[derive(Args)]
+struct AnisotropyArgs {
+    /// Input subavg HDF5 file (raw: uu/vv/ww/uv/uw/vw are total second moments).
+    #[arg(short, long, value_name = "FILE")]
+    file: String,
+    /// Output stem: <stem>.h5 with the (nz, nx) maps and <stem>.dat with the
+    /// profiles (<stem>_x<pos>.dat per station with --xloc).
+    #[arg(short, long, default_value = "anisotropy")]
+    output: String,
+    /// Physical x locations for wall-normal profiles, comma-separated.
+    #[arg(long, value_delimiter = ',')]
+    xloc: Option<Vec<f64>>,
+    /// x-index range START END (0-based, half-open) over which the stresses
+    /// are averaged before forming the profiles when --xloc is not given;
+    /// default: full streamwise extent.
+    #[arg(long, num_args = 2, value_name = "INT")]
+    xrange: Option<Vec<usize>>,
+}
+
+/// Named anisotropy quantities in output order.
+fn anisotropy_columns(a: &AnisotropyFields) -> Vec<(String, &ArrayD<f64>)> {
+    let mut cols: Vec<(String, &ArrayD<f64>)> = Vec::new();
+    for (name, b) in ["b11", "b22", "b33", "b12", "b13", "b23"].iter().zip(&a.b) {
+        cols.push((name.to_string(), b));
+    }
+    cols.push(("inv2".into(), &a.ii));
+    cols.push(("inv3".into(), &a.iii));
+    cols.push(("lum_xi".into(), &a.xi));
+    cols.push(("lum_eta".into(), &a.eta));
+    for n in 0..3 {
+        cols.push((format!("lam{}", n + 1), &a.eigval[n]));
+        cols.push((format!("c{}c", n + 1), &a.weights[n]));
+        for (d, c) in ['x', 'y', 'z'].into_iter().enumerate() {
+            cols.push((format!("e{}{c}", n + 1), &a.eigvec[n][d]));
+        }
+    }
+    cols.push(("bary_x".into(), &a.xb));
+    cols.push(("bary_y".into(), &a.yb));
+    cols
+}
+
+fn run_anisotropy(args: AnisotropyArgs) {
+    let mut h5 = H5File::new(&args.file).expect("failed to open HDF5 file");
+    h5.get_info().expect("failed to read DNS info");
+    h5.load_coords().expect("failed to load coordinates");
+    let nu = h5.info().nu.expect("'nu' not in HDF5 file");
+    let load_coord = |h5: &H5File, name: &str| -> Array1<f64> {
+        match h5.coord(name)
+            .unwrap_or_else(|| panic!("coord '{name}' not found"))
+            .read_data::<f64>().unwrap()
+        {
+            H5Data::Array(a) => a.into_dimensionality::<Ix1>().unwrap(),
+            H5Data::Scalar(_) => panic!("coord '{name}' is scalar"),
+        }
+    };
+    let x  = load_coord(&h5, "x");
+    let zc = load_coord(&h5, "zc");
+    let nxc = x.len();
+    drop(h5);
+
+    let load = |name: &str| read_inst_field(&args.file, name, nxc);
+    let tf = tke_fields(
+        &load("u"), &load("v"), &load("w"),
+        &load("uu"), &load("vv"), &load("ww"),
+        &load("uv"), &load("uw"), &load("vw"),
+    )
+    .expect("tke_fields failed");
+    let stem = args.output.strip_suffix(".h5").unwrap_or(&args.output).to_string();
+
+    // ── (nz, nx) maps ─────────────────────────────────────────────────────────
+    let maps = anisotropy(&tf).expect("anisotropy failed");
+    let path = format!("{stem}.h5");
+    let out = hdf5::File::create(&path).expect("failed to create output HDF5");
+    for (name, a) in anisotropy_columns(&maps) {
+        write_h5(&out, &name, a);
+    }
+    write_h5(&out, "x", &x);
+    write_h5(&out, "zc", &zc);
+    write_h5(&out, "nu", &Array1::from_elem(1, nu));
+    eprintln!("wrote {path}");
+
+    // ── profiles: anisotropy of the station / x-averaged stresses ─────────────
+    let stresses: [(&str, &ArrayD<f64>); 6] = [
+        ("uu", &tf.uu), ("vv", &tf.vv), ("ww", &tf.ww),
+        ("uv", &tf.uv), ("uw", &tf.uw), ("vw", &tf.vw),
+    ];
+    for (path, cols) in profile_tables(
+        &stresses, &x, &zc, &args.xloc, &args.xrange, &format!("{stem}.dat"),
+    ) {
+        let col = |n: &str| cols[n].clone().into_dyn();
+        let (uu, vv, ww) = (col("uu"), col("vv"), col("ww"));
+        let tke = (&(&uu + &vv) + &ww) * 0.5;
+        let prof = TkeFields { uu, vv, ww, uv: col("uv"), uw: col("uw"), vw: col("vw"), tke };
+        let a = anisotropy(&prof).expect("anisotropy failed");
+        let mut table: HashMap<String, Array1<f64>> = HashMap::new();
+        table.insert("zc".into(), zc.clone());
+        for (name, f) in anisotropy_columns(&a) {
+            table.insert(name, f.clone().into_dimensionality::<Ix1>().unwrap());
+        }
+        write_dat(Path::new(&path), "zc", 1.0 / nu, &table).expect("failed to write .dat file");
+        eprintln!("wrote {path}");
+    }
+}
+
+// ─── vorticity sub-command ────────────────────────────────────────────────────
+
+#[derive(Args)]
+struct VorticityArgs {
+    /// Explicit snapshot templates; `{}` is replaced by the component
+    /// (u/v/w), e.g. `inst_400000_{}.h5`.  Combined with any snapshots
+    /// generated by --pattern/--range.
+    #[arg(short, long, num_args = 1.., value_name = "TEMPLATE")]
+    files: Vec<String>,
+    /// Filename pattern with a time placeholder `{t}` and component `{}`,
+    /// e.g. `inst_{t}_{}.h5`.  Expanded over --range into a snapshot series.
+    #[arg(long, value_name = "PATTERN")]
+    pattern: Option<String>,
+    /// Timestep range START END STEP (inclusive) used with --pattern.
+    #[arg(long, num_args = 3, value_names = ["START", "END", "STEP"])]
+    range: Option<Vec<usize>>,
+    /// Output stem: <stem>.h5 (or <stem>_<k>.h5 per snapshot when several are
+    /// given) with omega_x/y/z, enstrophy, helicity, strain; <stem>.dat with
+    /// the ensemble ω'_rms profiles.
+    #[arg(short, long, default_value = "vorticity")]
+    output: String,
+    /// Only write the ω'_rms profiles, not the instantaneous HDF5 fields.
+    #[arg(long, default_value_t = false)]
+    profiles_only: bool,
+    /// Normalize the profiles to wall units (ω⁺ = ων/u_τ², z⁺ added).
+    #[arg(short, long, default_value_t = false)]
+    normalize: bool,
+    /// Physical x locations for wall-normal profiles, comma-separated.
+    #[arg(long, value_delimiter = ',')]
+    xloc: Option<Vec<f64>>,
+    /// x-index range START END (0-based, half-open) to average the profiles
+    /// over when --xloc is not given; default: full streamwise extent.
+    #[arg(long, num_args = 2, value_name = "INT")]
+    xrange: Option<Vec<usize>>,
+    /// Treat streamwise direction as periodic for derivative stencils.
+    #[arg(long, default_value_t = false)]
+    periodic_x: bool,
+}
+
+fn run_vorticity(args: VorticityArgs) {
+    let files = snapshot_templates(&args.files, &args.pattern, &args.range);
+
+    let read_coord = |h5: &H5File, name: &str| -> Array1<f64> {
+        match h5.coord(name)
+            .unwrap_or_else(|| panic!("coord '{name}' not found"))
+            .read_data::<f64>().unwrap()
+        {
+            H5Data::Array(a) => a.into_dimensionality::<Ix1>().unwrap(),
+            H5Data::Scalar(_) => panic!("coord '{name}' is scalar"),
+        }
+    };
+    let (x, y, zc, nu) = {
+        let mut h5 = H5File::new(&inst_path(&files[0], 'u'))
+            .expect("failed to open first u file");
+        h5.get_info().expect("failed to read DNS info");
+        h5.load_coords().expect("failed to load coordinates");
+        let nu = h5.info().nu.expect("'nu' not in HDF5 file");
+        (read_coord(&h5, "x"), read_coord(&h5, "y"), read_coord(&h5, "zc"), nu)
+    };
+    let nxc = x.len();
+    let stem = args.output.strip_suffix(".dat").unwrap_or(&args.output).to_string();
+
+    // Accumulators of spanwise averages, shape (nz, nx):
+    // [ū, ω̄_x, ω̄_y, ω̄_z, ⟨ω_x²⟩, ⟨ω_y²⟩, ⟨ω_z²⟩].
+    let mut acc: Option<[ArrayD<f64>; 7]> = None;
+    for (k, f) in files.iter().enumerate() {
+        let u = read_inst_field(&inst_path(f, 'u'), "u", nxc);
+        let v = read_inst_field(&inst_path(f, 'v'), "v", nxc);
+        let w = read_inst_field(&inst_path(f, 'w'), "w", nxc);
+        let grad = velocity_gradient(&u, &v, &w, &x, &y, &zc, [false, true, args.periodic_x])
+            .expect("velocity_gradient failed");
+        let vf = vorticity_fields(&u, &v, &w, &grad).expect("vorticity_fields failed");
+        drop(grad);
+
+        if !args.profiles_only {
+            let path = if files.len() == 1 {
+                format!("{stem}.h5")
+            } else {
+                format!("{stem}_{k}.h5")
+            };
+            let out = hdf5::File::create(&path).expect("failed to create output HDF5");
+            write_h5(&out, "omega_x", &vf.omega_x);
+            write_h5(&out, "omega_y", &vf.omega_y);
+            write_h5(&out, "omega_z", &vf.omega_z);
+            write_h5(&out, "enstrophy", &vf.enstrophy);
+            write_h5(&out, "helicity", &vf.helicity);
+            write_h5(&out, "strain", &vf.strain);
+            write_h5(&out, "x", &x);
+            write_h5(&out, "y", &y);
+            write_h5(&out, "zc", &zc);
+            write_h5(&out, "nu", &Array1::from_elem(1, nu));
+            eprintln!("wrote {path}");
         }
+
+        let span = |a: &ArrayD<f64>| avg_axis(a, 1).expect("spanwise average");
+        let sq = |a: &ArrayD<f64>| span(&(a * a));
+        let snap = [
+            span(&u),
+            span(&vf.omega_x), span(&vf.omega_y), span(&vf.omega_z),
+            sq(&vf.omega_x), sq(&vf.omega_y), sq(&vf.omega_z),
+        ];
+        match &mut acc {
+            None => acc = Some(snap),
+            Some(a) => {
+                for (ai, si) in a.iter_mut().zip(snap.iter()) {
+                    *ai += si;
+                }
+            }
+        }
+        eprintln!("accumulated {f}");
+    }
+
+    // ── ensemble ω'_rms = √(⟨ω²⟩ − ω̄²) profiles ──────────────────────────────
+    let mut a = acc.unwrap();
+    let n = files.len() as f64;
+    for arr in a.iter_mut() {
+        *arr /= n;
+    }
+    let [u_mean, mx, my, mz, mxx, myy, mzz] = a;
+    let rms = |m2: &ArrayD<f64>, m: &ArrayD<f64>| (m2 - &(m * m)).mapv(|v| v.max(0.0).sqrt());
+    let (rx, ry, rz) = (rms(&mxx, &mx), rms(&myy, &my), rms(&mzz, &mz));
+    let fields: Vec<(&str, &ArrayD<f64>)> = vec![
+        ("u", &u_mean),
+        ("omx", &mx), ("omy", &my), ("omz", &mz),
+        ("omx_rms", &rx), ("omy_rms", &ry), ("omz_rms", &rz),
+    ];
+    let ret = 1.0 / nu;
+    for (path, cols) in profile_tables(
+        &fields, &x, &zc, &args.xloc, &args.xrange, &format!("{stem}.dat"),
+    ) {
+        let cols = if args.normalize {
+            let tau = wall_shear_stress(
+                &cols["u"].clone().into_dyn(),
+                &zc.clone().into_dyn(),
+                nu,
+            ).expect("τ_w computation failed");
+            Profiles::new("zc", nu, tau, cols)
+                .expect("Profiles::new failed")
+                .to_wall_units()
+                .expect("normalization failed")
+        } else {
+            cols
+        };
+        write_dat(Path::new(&path), "zc", ret, &cols).expect("failed to write .dat file");
+        eprintln!("wrote {path}");
     }
 }
 
@@ -1524,9 +2626,14 @@ struct VortexArgs {
     /// Snapshot file containing dataset `w` (wall-normal); default: same as --ufile.
     #[arg(short = 'w', long, value_name = "FILE")]
     wfile: Option<String>,
-    /// Output HDF5 file with q/lambda2/u fields (+ x/y/zc/nu).
+    /// Output HDF5 file with the selected fields and u (+ x/y/zc/nu).
     #[arg(short, long, value_name = "FILE", default_value = "vortex.h5")]
     output: String,
+    /// Fields to compute, comma-separated: q, lambda2, lambda_ci,
+    /// lambda_ci_signed (sign of ω_y: prograde > 0 at the lower wall), delta,
+    /// omega, liutex (magnitude + liutex_x/y/z components).
+    #[arg(long, value_delimiter = ',', default_value = "q,lambda2")]
+    fields: Vec<String>,
     /// Treat streamwise direction as periodic for derivative stencils.
     #[arg(long, default_value_t = false)]
     periodic_x: bool,
@@ -1583,96 +2690,1775 @@ fn run_vortex(args: VortexArgs) {
     let w = load_field(args.wfile.as_deref().unwrap_or(&args.ufile), "w");
     eprintln!("fields loaded: ({nz}, {ny}, {nxc})");
 
-    // ── Q and λ₂ ──────────────────────────────────────────────────────────────
-    let vf = vortex_criteria(&u, &v, &w, &x, &y, &zc, [false, true, args.periodic_x])
-        .expect("vortex_criteria failed");
+    let which: Vec<Criterion> = args
+        .fields
+        .iter()
+        .map(|n| Criterion::from_name(n).unwrap_or_else(|e| panic!("{e}")))
+        .collect();
+
+    // ── velocity gradient → selected criteria ────────────────────────────────
+    let grad = velocity_gradient(&u, &v, &w, &x, &y, &zc, [false, true, args.periodic_x])
+        .expect("velocity_gradient failed");
     drop(v);
     drop(w);
-    eprintln!("Q and λ₂ computed");
+    let vf = vortex_fields(&grad, &which).expect("vortex_fields failed");
+    drop(grad);
+    eprintln!("computed {}", args.fields.join(", "));
 
     // ── isosurface-level guidance: tail quantiles ─────────────────────────────
-    let quantiles = |field: &ArrayD<f64>, fractions: &[f64], lower_tail: bool| -> Vec<f64> {
-        let mut sample: Vec<f64> = field.as_slice().unwrap().iter().step_by(16).copied().collect();
-        sample.sort_by(|a, b| a.partial_cmp(b).unwrap());
-        fractions
-            .iter()
-            .map(|&f| {
-                let frac = if lower_tail { f } else { 1.0 - f };
-                sample[((sample.len() - 1) as f64 * frac) as usize]
-            })
-            .collect()
-    };
     let fr = [0.001, 0.005, 0.01, 0.02, 0.05];
-    let l2q = quantiles(&vf.lambda2, &fr, true);
-    let qq = quantiles(&vf.q, &fr, false);
     eprintln!("suggested isosurface levels (tail quantiles of the sampled field):");
-    for (i, f) in fr.iter().enumerate() {
-        eprintln!(
-            "  {:>5.1}%:  lambda2 = {:>12.4e}    q = {:>12.4e}",
-            f * 100.0,
-            l2q[i],
-            qq[i]
-        );
+    for (name, field) in &vf {
+        // λ₂ marks vortices by its negative tail, the others by the positive one
+        let q = tail_quantiles(field, &fr, name == "lambda2");
+        let levels: Vec<String> = fr
+            .iter()
+            .zip(&q)
+            .map(|(f, l)| format!("{:.1}%: {l:.4e}", f * 100.0))
+            .collect();
+        eprintln!("  {name:>16}  {}", levels.join("  "));
     }
 
-    // ── write HDF5 (q, lambda2, u + coords/metadata) ──────────────────────────
+    // ── write HDF5 (selected fields, u + coords/metadata) ─────────────────────
     let out = hdf5::File::create(&args.output).expect("failed to create output HDF5");
-    let write3d = |name: &str, a: &ArrayD<f64>| {
-        out.new_dataset_builder()
-            .with_data(a.view())
-            .create(name)
-            .unwrap_or_else(|e| panic!("write '{name}': {e}"));
-    };
-    write3d("q", &vf.q);
-    write3d("lambda2", &vf.lambda2);
-    write3d("u", &u);
-    let write1d = |name: &str, a: &Array1<f64>| {
-        out.new_dataset_builder()
-            .with_data(a.view())
-            .create(name)
-            .unwrap_or_else(|e| panic!("write '{name}': {e}"));
-    };
-    write1d("x", &x);
-    write1d("y", &y);
-    write1d("zc", &zc);
-    write1d("nu", &Array1::from_elem(1, nu));
+    for (name, field) in &vf {
+        write_h5(&out, name, field);
+    }
+    write_h5(&out, "u", &u);
+    write_h5(&out, "x", &x);
+    write_h5(&out, "y", &y);
+    write_h5(&out, "zc", &zc);
+    write_h5(&out, "nu", &Array1::from_elem(1, nu));
     drop(out);
     eprintln!("wrote {}", args.output);
     eprintln!(
         "to view in Paraview, generate an XDMF for the region of interest, e.g.:\n  \
-         datapostproc-rust hdfview -f {} -v q lambda2 u",
-        args.output
+         datapostproc-rust hdfview -f {} -v {} u",
+        args.output,
+        vf.iter().map(|(n, _)| n.as_str()).collect::<Vec<_>>().join(" ")
     );
 }
 
-// ─── Helpers ──────────────────────────────────────────────────────────────────
-
-fn dire_to_axis(dire: &str) -> usize {
-    match dire.to_lowercase().as_str() {
-        "z" => 0,
-        "y" => 1,
-        "x" => 2,
-        d   => panic!("Unknown direction '{}' – expected x, y, or z", d),
-    }
-}
+// ─── structures sub-command ───────────────────────────────────────────────────
 
-fn build_block(args: &BlockArgs) -> Option<Block> {
-    if args.blockx.is_none() && args.blocky.is_none() && args.blockz.is_none() {
-        return None;
-    }
-    let bv = |v: &Vec<usize>| BlockValue::new([v[0], v[1], v[2], v[3]]).expect("invalid block");
-    let bz = args.blockz.as_ref().map(bv);
-    let by = args.blocky.as_ref().map(bv);
-    let bx = args.blockx.as_ref().map(bv);
-    Some(Block::new(vec![bz, by, bx]))
+#[derive(Args)]
+struct StructuresArgs {
+    /// HDF5 file holding the field (e.g. `vortex` output or an instantaneous
+    /// snapshot) together with x, y, zc, nu and u.
+    #[arg(short, long)]
+    file: String,
+    /// Dataset to threshold (q, lambda2, lambda_ci, u, …).
+    #[arg(long, default_value = "q")]
+    var: String,
+    /// Second dataset: threshold the product of the fluctuations of --var and
+    /// this one (e.g. `--var u --times w` for u'w').
+    #[arg(long)]
+    times: Option<String>,
+    /// File holding --times (default: --file).
+    #[arg(long)]
+    times_file: Option<String>,
+    /// Subtract the spanwise mean from --var (u' streaks); implied by --times.
+    #[arg(long, default_value_t = false)]
+    fluct: bool,
+    /// Threshold rule: `above` (f > level), `below` (f < level) or `abs`
+    /// (|f| > level).
+    #[arg(long, default_value = "above")]
+    rule: String,
+    /// Threshold level.
+    #[arg(long, allow_hyphen_values = true)]
+    level: f64,
+    /// Scale the level by the local spanwise rms at (z, x) (u'_rms·w'_rms for
+    /// a --times product), e.g. `--rule below --level -1.5 --relative`.
+    #[arg(long, default_value_t = false)]
+    relative: bool,
+    /// Objects with z_min⁺ ≤ this, in the local wall units of the station
+    /// nearest their centroid, are classified as wall-attached.
+    #[arg(long, default_value_t = 20.0)]
+    attach_plus: f64,
+    /// Discard objects with fewer grid points.
+    #[arg(long, default_value_t = 8)]
+    min_cells: usize,
+    /// Number of bins of the size/height PDFs.
+    #[arg(long, default_value_t = 30)]
+    bins: usize,
+    /// Output stem: <stem>_objects.dat, <stem>_pdf.dat, <stem>_labels.h5.
+    #[arg(short, long, default_value = "structures")]
+    output: String,
 }
 
-/// Default [start, stride, count] = [0, 1, full_extent].
-fn view_block(arg: &Option<Vec<usize>>, full: usize) -> [usize; 3] {
-    match arg {
-        Some(v) => [v[0], v[1], v[2]],
-        None    => [0, 1, full],
+fn run_structures(args: StructuresArgs) {
+    assert!(args.bins >= 1, "--bins must be at least 1");
+    let mut h5 = H5File::new(&args.file).expect("failed to open HDF5 file");
+    h5.get_info().expect("failed to read DNS info");
+    h5.load_coords().expect("failed to load coordinates");
+    let read_coord = |h5: &H5File, name: &str| -> Array1<f64> {
+        match h5.coord(name)
+            .unwrap_or_else(|| panic!("coord '{name}' not found"))
+            .read_data::<f64>().unwrap()
+        {
+            H5Data::Array(a) => a.into_dimensionality::<Ix1>().unwrap(),
+            H5Data::Scalar(_) => panic!("coord '{name}' is scalar"),
+        }
+    };
+    let x = read_coord(&h5, "x");
+    let y = read_coord(&h5, "y");
+    let zc = read_coord(&h5, "zc");
+    let nu = h5.info().nu.expect("'nu' not in HDF5 file");
+    drop(h5);
+    let nxc = x.len();
+
+    let rule = match args.rule.as_str() {
+        "above" => Threshold::Above(args.level),
+        "below" => Threshold::Below(args.level),
+        "abs" => Threshold::AbsAbove(args.level),
+        other => panic!("unknown --rule '{other}' (expected above, below or abs)"),
+    };
+
+    // ── field to threshold and its local reference scale ─────────────────────
+    let fluct = |f: ArrayD<f64>| -> ArrayD<f64> {
+        let mean = f.mean_axis(Axis(1)).expect("spanwise mean").insert_axis(Axis(1));
+        f - &mean
+    };
+    let a = read_inst_field(&args.file, &args.var, nxc);
+    let (field, scale) = match &args.times {
+        Some(name) => {
+            let bfile = args.times_file.as_deref().unwrap_or(&args.file);
+            let b = read_inst_field(bfile, name, nxc);
+            let scale = args.relative.then(|| {
+                spanwise_rms(&a).expect("spanwise rms") * spanwise_rms(&b).expect("spanwise rms")
+            });
+            (fluct(a) * fluct(b), scale)
+        }
+        None => {
+            let f = if args.fluct { fluct(a) } else { a };
+            let scale = args.relative.then(|| spanwise_rms(&f).expect("spanwise rms"));
+            (f, scale)
+        }
+    };
+    let mask = threshold_mask(&field, rule, scale.as_ref().map(|s| s.view()))
+        .expect("threshold_mask failed");
+    drop(field);
+
+    // ── local viscous length δ_ν(x) for the attached classification ─────────
+    // (NaN where τ_w ≤ 0: no wall units, never attached); the domain-mean
+    // δ_ν only scales the PDF axis
+    let u = read_inst_field(&args.file, "u", nxc);
+    let u_span = u.mean_axis(Axis(1)).unwrap().into_dimensionality::<ndarray::Ix2>().unwrap();
+    let tau_x = local_tau(&u_span, &zc, nu).expect("τ_w(x) computation failed");
+    let lv_x = tau_x.mapv(|t| if t > 0.0 { nu / t.sqrt() } else { f64::NAN });
+    let u_prof = u_span.mean_axis(Axis(1)).unwrap().into_dyn();
+    let tau = wall_shear_stress(&u_prof, &zc.clone().into_dyn(), nu)
+        .expect("τ_w computation failed");
+    let lv = nu / friction_velocity(tau);
+    drop(u);
+
+    let (labels, objs) = label_structures(&mask, &x, &y, &zc, &(&lv_x * args.attach_plus), args.min_cells)
+        .expect("label_structures failed");
+    let nattached = objs.iter().filter(|o| o.attached).count();
+    let vtotal: f64 = objs.iter().map(|o| o.volume).sum();
+    let domain = (x[nxc - 1] - x[0]) * (y[1] - y[0]) * y.len() as f64 * (zc[zc.len() - 1] - zc[0]);
+    eprintln!(
+        "{} objects ({nattached} attached, {} detached), volume fraction {:.4}, δ_ν = {lv:.4e}",
+        objs.len(),
+        objs.len() - nattached,
+        vtotal / domain,
+    );
+
+    let stem = args.output.strip_suffix(".dat").unwrap_or(&args.output).to_string();
+    let ret = 1.0 / nu;
+
+    // ── per-object table ──────────────────────────────────────────────────────
+    let col = |f: &dyn Fn(&Structure) -> f64| -> Array1<f64> { objs.iter().map(f).collect() };
+    let mut cols: HashMap<String, Array1<f64>> = HashMap::new();
+    cols.insert("label".into(), col(&|o| o.label as f64));
+    cols.insert("cells".into(), col(&|o| o.cells as f64));
+    cols.insert("volume".into(), col(&|o| o.volume));
+    cols.insert("attached".into(), col(&|o| if o.attached { 1.0 } else { 0.0 }));
+    for (d, c) in ['x', 'y', 'z'].into_iter().enumerate() {
+        cols.insert(format!("{c}cen"), col(&|o| o.centroid[d]));
+        cols.insert(format!("{c}min"), col(&|o| o.min[d]));
+        cols.insert(format!("{c}max"), col(&|o| o.max[d]));
+        cols.insert(format!("l{c}"), col(&|o| o.extent()[d]));
+    }
+    cols.insert("zminplus".into(), col(&|o| o.min[2] / lv_x[o.ix]));
+    cols.insert("zmaxplus".into(), col(&|o| o.max[2] / lv_x[o.ix]));
+    write_dat(Path::new(&format!("{stem}_objects.dat")), "object", ret, &cols)
+        .expect("failed to write .dat file");
+
+    // ── size / height PDFs ────────────────────────────────────────────────────
+    if !objs.is_empty() {
+        let nb = args.bins;
+        let zmax: Vec<f64> = objs.iter().map(|o| o.max[2]).collect();
+        let zatt: Vec<f64> = objs.iter().filter(|o| o.attached).map(|o| o.max[2]).collect();
+        let zdet: Vec<f64> = objs.iter().filter(|o| !o.attached).map(|o| o.max[2]).collect();
+        let vol: Vec<f64> = objs.iter().map(|o| o.volume).collect();
+        let (z0, z1) = (zc[0], zc[zc.len() - 1]);
+        let zedges = Array1::linspace(z0, z1, nb + 1);
+        // volumes span decades: logarithmic bins
+        let vmin = vol.iter().cloned().fold(f64::INFINITY, f64::min);
+        let vmax = vol.iter().cloned().fold(0.0, f64::max);
+        let vedges = Array1::logspace(10.0, vmin.log10(), (vmax * (1.0 + 1e-12)).log10(), nb + 1);
+        let centre = |e: &Array1<f64>| -> Array1<f64> {
+            Array1::from_shape_fn(nb, |b| 0.5 * (e[b] + e[b + 1]))
+        };
+        // sub-population PDFs are normalised by the total count so that
+        // attached + detached = all
+        let frac = |part: &[f64]| part.len() as f64 / zmax.len() as f64;
+        let hist = |v: &[f64], e: &Array1<f64>| histogram_pdf(v, e).expect("histogram_pdf failed");
+        let mut pdf: HashMap<String, Array1<f64>> = HashMap::new();
+        pdf.insert("zbin".into(), centre(&zedges));
+        pdf.insert("zbinplus".into(), centre(&zedges) / lv);
+        pdf.insert("pdf_zmax".into(), hist(&zmax, &zedges));
+        pdf.insert("pdf_zmax_att".into(), hist(&zatt, &zedges) * frac(&zatt));
+        pdf.insert("pdf_zmax_det".into(), hist(&zdet, &zedges) * frac(&zdet));
+        pdf.insert("vbin".into(), centre(&vedges));
+        pdf.insert("pdf_volume".into(), hist(&vol, &vedges));
+        write_dat(Path::new(&format!("{stem}_pdf.dat")), "zmax", ret, &pdf)
+            .expect("failed to write .dat file");
+    }
+
+    // ── label field ───────────────────────────────────────────────────────────
+    let path = format!("{stem}_labels.h5");
+    let out = hdf5::File::create(&path).expect("failed to create output HDF5");
+    write_h5(&out, "labels", &labels.mapv(|l| l as f64));
+    write_h5(&out, "x", &x);
+    write_h5(&out, "y", &y);
+    write_h5(&out, "zc", &zc);
+    write_h5(&out, "nu", &Array1::from_elem(1, nu));
+    eprintln!("wrote {path}");
+}
+
+// ─── isosurface sub-command ───────────────────────────────────────────────────
+
+#[derive(Args)]
+struct IsosurfaceArgs {
+    /// HDF5 file holding the field and x, y, zc (e.g. `vortex` output).
+    #[arg(short, long)]
+    file: String,
+    /// Dataset to contour.
+    #[arg(long, default_value = "q")]
+    var: String,
+    /// Isosurface level.
+    #[arg(long, allow_hyphen_values = true, conflicts_with = "quantile")]
+    level: Option<f64>,
+    /// Pick the level as a tail quantile of the field instead, e.g. 0.01 for
+    /// the top 1 % (bottom 1 % for lambda2), as printed by `vortex`.
+    #[arg(long)]
+    quantile: Option<f64>,
+    /// Colour the surface by this dataset (e.g. u).
+    #[arg(long)]
+    color: Option<String>,
+    /// File holding --color (default: --file).
+    #[arg(long)]
+    color_file: Option<String>,
+    /// Subtract the spanwise mean from the colour field (u' instead of u).
+    #[arg(long, default_value_t = false)]
+    color_fluct: bool,
+    /// Output mesh; the format follows the extension (.vtk, .ply, .stl)
+    /// unless --format is given.
+    #[arg(short, long, default_value = "isosurface.vtk")]
+    output: String,
+    /// Mesh format: vtk, ply or stl.
+    #[arg(long)]
+    format: Option<String>,
+    /// Do not close the surface across the periodic spanwise boundary.
+    #[arg(long, default_value_t = false)]
+    open_y: bool,
+}
+
+fn run_isosurface(args: IsosurfaceArgs) {
+    if let Some(q) = args.quantile {
+        assert!(q > 0.0 && q < 1.0, "--quantile {q} must lie in (0, 1)");
+    }
+    let mut h5 = H5File::new(&args.file).expect("failed to open HDF5 file");
+    h5.load_coords().expect("failed to load coordinates");
+    let read_coord = |h5: &H5File, name: &str| -> Array1<f64> {
+        match h5.coord(name)
+            .unwrap_or_else(|| panic!("coord '{name}' not found"))
+            .read_data::<f64>().unwrap()
+        {
+            H5Data::Array(a) => a.into_dimensionality::<Ix1>().unwrap(),
+            H5Data::Scalar(_) => panic!("coord '{name}' is scalar"),
+        }
+    };
+    let x = read_coord(&h5, "x");
+    let y = read_coord(&h5, "y");
+    let zc = read_coord(&h5, "zc");
+    drop(h5);
+    let nxc = x.len();
+
+    let format = match &args.format {
+        Some(f) => MeshFormat::from_name(f).unwrap_or_else(|| panic!("unknown --format '{f}'")),
+        None => MeshFormat::from_path(Path::new(&args.output))
+            .unwrap_or_else(|| panic!("cannot infer mesh format from '{}'; use --format", args.output)),
+    };
+    if format == MeshFormat::Stl && args.color.is_some() {
+        eprintln!("warning: STL carries no vertex data, --color is ignored");
+    }
+
+    let field = read_inst_field(&args.file, &args.var, nxc);
+    let level = match (args.level, args.quantile) {
+        (Some(l), _) => l,
+        (None, Some(q)) => {
+            let l = tail_quantiles(&field, &[q], args.var == "lambda2")[0];
+            assert!(l.is_finite(), "'{}' has no finite values to take a quantile of", args.var);
+            l
+        }
+        (None, None) => panic!("give --level or --quantile"),
+    };
+    let color = args.color.as_ref().map(|name| {
+        let c = read_inst_field(args.color_file.as_deref().unwrap_or(&args.file), name, nxc);
+        if args.color_fluct {
+            let mean = c.mean_axis(Axis(1)).expect("spanwise mean").insert_axis(Axis(1));
+            c - &mean
+        } else {
+            c
+        }
+    });
+
+    // λ₂ vortices are the region *below* the (negative) level: contour −λ₂ so
+    // the normals still point out of the vortex cores
+    let (field, level) = if args.var == "lambda2" { (-field, -level) } else { (field, level) };
+    let mesh = isosurface(&field, level, &x, &y, &zc, color.as_ref(), !args.open_y)
+        .expect("isosurface failed");
+    eprintln!(
+        "{} = {level:.4e}: {} vertices, {} triangles, area {:.4e}",
+        args.var,
+        mesh.vertices.len(),
+        mesh.triangles.len(),
+        mesh.area()
+    );
+    let name = args.color.as_deref().unwrap_or(&args.var);
+    write_mesh(Path::new(&args.output), &mesh, format, name).expect("failed to write mesh");
+    eprintln!("wrote {}", args.output);
+}
+
+// ─── qr sub-command ───────────────────────────────────────────────────────────
+
+#[derive(Args)]
+struct QrArgs {
+    /// Explicit snapshot templates; `{}` is replaced by the component
+    /// (u/v/w), e.g. `inst_400000_{}.h5`.  Combined with any snapshots
+    /// generated by --pattern/--range.
+    #[arg(short, long, num_args = 1.., value_name = "TEMPLATE")]
+    files: Vec<String>,
+    /// Filename pattern with a time placeholder `{t}` and component `{}`,
+    /// e.g. `inst_{t}_{}.h5`.  Expanded over --range into a snapshot series.
+    #[arg(long, value_name = "PATTERN")]
+    pattern: Option<String>,
+    /// Timestep range START END STEP (inclusive) used with --pattern.
+    #[arg(long, num_args = 3, value_names = ["START", "END", "STEP"])]
+    range: Option<Vec<usize>>,
+    /// Wall-normal band edges (physical z, comma-separated); consecutive
+    /// edges delimit one band.  Default: the whole height as one band.
+    #[arg(long, value_delimiter = ',')]
+    zbands: Option<Vec<f64>>,
+    /// Streamwise region edges (physical x, comma-separated), e.g. the start
+    /// and end of the blowing strip and the domain end.  Default: whole domain.
+    #[arg(long, value_delimiter = ',')]
+    xregions: Option<Vec<f64>>,
+    /// Number of bins per axis.
+    #[arg(long, default_value_t = 100)]
+    bins: usize,
+    /// Half-width of the R* = R/⟨Q_W⟩^{3/2} axis.
+    #[arg(long, default_value_t = 3.0)]
+    rmax: f64,
+    /// Half-width of the Q* = Q/⟨Q_W⟩ axis.
+    #[arg(long, default_value_t = 5.0)]
+    qmax: f64,
+    /// Output HDF5 file.
+    #[arg(short, long, default_value = "qr.h5")]
+    output: String,
+    /// Treat streamwise direction as periodic for derivative stencils.
+    #[arg(long, default_value_t = false)]
+    periodic_x: bool,
+}
+
+fn run_qr(args: QrArgs) {
+    let files = snapshot_templates(&args.files, &args.pattern, &args.range);
+
+    let read_coord = |h5: &H5File, name: &str| -> Array1<f64> {
+        match h5.coord(name)
+            .unwrap_or_else(|| panic!("coord '{name}' not found"))
+            .read_data::<f64>().unwrap()
+        {
+            H5Data::Array(a) => a.into_dimensionality::<Ix1>().unwrap(),
+            H5Data::Scalar(_) => panic!("coord '{name}' is scalar"),
+        }
+    };
+    let (x, y, zc, nu) = {
+        let mut h5 = H5File::new(&inst_path(&files[0], 'u'))
+            .expect("failed to open first u file");
+        h5.get_info().expect("failed to read DNS info");
+        h5.load_coords().expect("failed to load coordinates");
+        let nu = h5.info().nu.expect("'nu' not in HDF5 file");
+        (read_coord(&h5, "x"), read_coord(&h5, "y"), read_coord(&h5, "zc"), nu)
+    };
+    let nxc = x.len();
+
+    // physical edges → half-open index ranges of the points inside
+    let ranges = |edges: &Option<Vec<f64>>, c: &Array1<f64>, what: &str| -> Vec<(usize, usize)> {
+        let Some(e) = edges else { return vec![(0, c.len())] };
+        assert!(e.len() >= 2, "--{what} needs at least two edges");
+        e.windows(2)
+            .map(|w| {
+                let i0 = c.iter().position(|&v| v >= w[0]).unwrap_or(c.len());
+                let i1 = c.iter().position(|&v| v >= w[1]).unwrap_or(c.len());
+                assert!(i0 < i1, "--{what} [{}, {}) contains no grid points", w[0], w[1]);
+                (i0, i1)
+            })
+            .collect()
+    };
+    let bands = ranges(&args.zbands, &zc, "zbands");
+    let regions = ranges(&args.xregions, &x, "xregions");
+    let mut acc = QrJointPdf::new(&bands, &regions, args.bins, args.bins, args.rmax, args.qmax)
+        .expect("QrJointPdf::new failed");
+
+    let invariants = |f: &String| {
+        let u = read_inst_field(&inst_path(f, 'u'), "u", nxc);
+        let v = read_inst_field(&inst_path(f, 'v'), "v", nxc);
+        let w = read_inst_field(&inst_path(f, 'w'), "w", nxc);
+        let grad = velocity_gradient(&u, &v, &w, &x, &y, &zc, [false, true, args.periodic_x])
+            .expect("velocity_gradient failed");
+        gradient_invariants(&grad)
+    };
+    // pass 1: ensemble ⟨Q_W⟩; pass 2: bin every snapshot on that scale
+    for f in &files {
+        acc.normalise(&invariants(f)).expect("QrJointPdf::normalise failed");
+        eprintln!("normalised {f}");
+    }
+    for f in &files {
+        acc.accumulate(&invariants(f)).expect("QrJointPdf::accumulate failed");
+        eprintln!("accumulated {f}");
+    }
+
+    let pdf = acc.pdf().expect("pdf failed");
+    let qw = acc.qw_mean().expect("qw_mean failed");
+    let (rb, qb) = acc.bins();
+    let edges = |r: &[(usize, usize)], c: &Array1<f64>| -> ndarray::Array2<f64> {
+        ndarray::Array2::from_shape_fn((r.len(), 2), |(n, e)| {
+            if e == 0 { c[r[n].0] } else { c[r[n].1 - 1] }
+        })
+    };
+
+    eprintln!("⟨Q_W⟩ per region (rows) and band (columns):");
+    for (g, row) in qw.outer_iter().enumerate() {
+        let vals: Vec<String> = row.iter().map(|v| format!("{v:.4e}")).collect();
+        eprintln!("  x ∈ [{:.3}, {:.3}]  {}", x[regions[g].0], x[regions[g].1 - 1], vals.join("  "));
+    }
+
+    let out = hdf5::File::create(&args.output).expect("failed to create output HDF5");
+    write_h5(&out, "pdf", &pdf);
+    write_h5(&out, "r_star", &rb);
+    write_h5(&out, "q_star", &qb);
+    write_h5(&out, "qw_mean", &qw);
+    write_h5(&out, "zband", &edges(&bands, &zc));
+    write_h5(&out, "xregion", &edges(&regions, &x));
+    write_h5(&out, "samples", &Array1::from_elem(1, acc.samples() as f64));
+    write_h5(&out, "nu", &Array1::from_elem(1, nu));
+    eprintln!("wrote {}", args.output);
+}
+
+// ─── tke-spectral sub-command ─────────────────────────────────────────────────
+
+#[derive(Args)]
+struct TkeSpectralArgs {
+    /// Explicit snapshot templates; `{}` is replaced by the component
+    /// (u/v/w/p), e.g. `inst_400000_{}.h5`.  Combined with any snapshots
+    /// generated by --pattern/--range.
+    #[arg(short, long, num_args = 1.., value_name = "TEMPLATE")]
+    files: Vec<String>,
+    /// Filename pattern with a time placeholder `{t}` and component `{}`,
+    /// e.g. `inst_{t}_{}.h5`.  Expanded over --range into a snapshot series.
+    #[arg(long, value_name = "PATTERN")]
+    pattern: Option<String>,
+    /// Timestep range START END STEP (inclusive) used with --pattern.
+    #[arg(long, num_args = 3, value_names = ["START", "END", "STEP"])]
+    range: Option<Vec<usize>>,
+    /// Streamwise windows as pairs of physical x (comma-separated),
+    /// e.g. `2,4,10,12` for [2, 4) and [10, 12).  Default: whole domain.
+    #[arg(long, value_delimiter = ',')]
+    xwindows: Option<Vec<f64>>,
+    /// Output HDF5 file.
+    #[arg(short, long, default_value = "tke_spectral.h5")]
+    output: String,
+    /// Treat streamwise direction as periodic for derivative stencils.
+    #[arg(long, default_value_t = false)]
+    periodic_x: bool,
+}
+
+fn run_tke_spectral(args: TkeSpectralArgs) {
+    let files = snapshot_templates(&args.files, &args.pattern, &args.range);
+
+    let read_coord = |h5: &H5File, name: &str| -> Array1<f64> {
+        match h5.coord(name)
+            .unwrap_or_else(|| panic!("coord '{name}' not found"))
+            .read_data::<f64>().unwrap()
+        {
+            H5Data::Array(a) => a.into_dimensionality::<Ix1>().unwrap(),
+            H5Data::Scalar(_) => panic!("coord '{name}' is scalar"),
+        }
+    };
+    let (x, y, zc, nu) = {
+        let mut h5 = H5File::new(&inst_path(&files[0], 'u'))
+            .expect("failed to open first u file");
+        h5.get_info().expect("failed to read DNS info");
+        h5.load_coords().expect("failed to load coordinates");
+        let nu = h5.info().nu.expect("'nu' not in HDF5 file");
+        (read_coord(&h5, "x"), read_coord(&h5, "y"), read_coord(&h5, "zc"), nu)
+    };
+    let nxc = x.len();
+    let windows = x_windows(&args.xwindows, &x);
+    let comps = ['u', 'v', 'w', 'p'];
+    let read = |f: &str, c: char| read_inst_field(&inst_path(f, c), &c.to_string(), nxc);
+
+    // pass 1: span + ensemble means (the fluctuations of pass 2 need them)
+    let mut mean: Option<[ArrayD<f64>; 4]> = None;
+    for f in &files {
+        let m = comps.map(|c| avg_axis(&read(f, c), 1).expect("spanwise average failed"));
+        match &mut mean {
+            None => mean = Some(m),
+            Some(acc) => {
+                for (a, b) in acc.iter_mut().zip(&m) {
+                    *a += b;
+                }
+            }
+        }
+        eprintln!("mean: accumulated {f}");
+    }
+    let mean = mean.unwrap().map(|a| a / files.len() as f64);
+
+    // pass 2: spectra of the fluctuations
+    let mut sb = SpectralBudget::new(mean.each_ref(), &x, &y, &zc, nu, &windows, args.periodic_x)
+        .expect("SpectralBudget::new failed");
+    for f in &files {
+        let [u, v, w, p] = comps.map(|c| read(f, c));
+        sb.accumulate(&u, &v, &w, &p).expect("SpectralBudget::accumulate failed");
+        eprintln!("spectra: accumulated {f}");
+    }
+
+    let k = sb.wavenumbers();
+    let dens = sb.densities().expect("densities failed");
+    let phys = sb.physical().expect("physical terms failed");
+
+    eprintln!("TKE terms, max over z: |Σ_k Δk (·) − physical| / max|physical|");
+    let out = hdf5::File::create(&args.output).expect("failed to create output HDF5");
+    for ((name, d), (_, p)) in dens.named().into_iter().zip(phys.named()) {
+        let sum = d.sum_axis(Axis(3)) * k[1];
+        let scale = p.iter().fold(0.0f64, |a, v| a.max(v.abs())).max(f64::MIN_POSITIVE);
+        let err = sum.iter().zip(p.iter()).fold(0.0f64, |a, (s, q)| a.max((s - q).abs()));
+        eprintln!("  {name:<9} {:.3e}", err / scale);
+        write_h5(&out, name, d);
+        write_h5(&out, &format!("{name}_phys"), p);
+    }
+    // Σ_k T matches the divergence form only up to the discretization error
+    let turb = sb.turbulent_transport().expect("turbulent transport failed");
+    let transfer = dens.transfer.sum_axis(Axis(3)) * k[1];
+    eprintln!("transfer vs turb_trans = −∂⟨u_α'u_α'u_j'⟩/∂x_j: max |Σ_k Δk T − turb_trans| / max|turb_trans|");
+    for (a, name) in ["uu", "vv", "ww", "k"].iter().enumerate() {
+        let (t, q) = (transfer.index_axis(Axis(1), a), turb.index_axis(Axis(1), a));
+        let scale = q.iter().fold(0.0f64, |m, v| m.max(v.abs())).max(f64::MIN_POSITIVE);
+        let gap = t.iter().zip(q.iter()).fold(0.0f64, |m, (s, r)| m.max((s - r).abs()));
+        eprintln!("  {name:<9} {:.3e}", gap / scale);
+    }
+    write_h5(&out, "turb_trans", &turb);
+    let lambda = k.mapv(|kk| if kk > 0.0 { 2.0 * std::f64::consts::PI / kk } else { f64::INFINITY });
+    write_h5(&out, "ky", &k);
+    write_h5(&out, "lambda_y", &lambda);
+    write_h5(&out, "zc", &zc);
+    write_h5(&out, "xwindow", &window_bounds(&windows, &x));
+    write_h5(&out, "samples", &Array1::from_elem(1, sb.samples() as f64));
+    write_h5(&out, "nu", &Array1::from_elem(1, nu));
+    eprintln!("wrote {}", args.output);
+}
+
+// ─── fik-spectral sub-command ─────────────────────────────────────────────────
+
+#[derive(Args)]
+struct FikSpectralArgs {
+    /// Explicit snapshot templates; `{}` is replaced by the component
+    /// (u/w), e.g. `inst_400000_{}.h5`.  Combined with any snapshots
+    /// generated by --pattern/--range.
+    #[arg(short, long, num_args = 1.., value_name = "TEMPLATE")]
+    files: Vec<String>,
+    /// Filename pattern with a time placeholder `{t}` and component `{}`,
+    /// e.g. `inst_{t}_{}.h5`.  Expanded over --range into a snapshot series.
+    #[arg(long, value_name = "PATTERN")]
+    pattern: Option<String>,
+    /// Timestep range START END STEP (inclusive) used with --pattern.
+    #[arg(long, num_args = 3, value_names = ["START", "END", "STEP"])]
+    range: Option<Vec<usize>>,
+    /// Streamwise windows as pairs of physical x (comma-separated),
+    /// e.g. `2,4,10,12` for [2, 4) and [10, 12).  Default: whole domain.
+    #[arg(long, value_delimiter = ',')]
+    xwindows: Option<Vec<f64>>,
+    /// Half-channel height h (default: 1.0).
+    #[arg(long, default_value_t = 1.0)]
+    half_height: f64,
+    /// Output stem: writes <stem>_x<x0>-<x1>.dat per window.
+    #[arg(short, long, default_value = "fik_spectral")]
+    output: String,
+}
+
+fn run_fik_spectral(args: FikSpectralArgs) {
+    let files = snapshot_templates(&args.files, &args.pattern, &args.range);
+
+    let read_coord = |h5: &H5File, name: &str| -> Array1<f64> {
+        match h5.coord(name)
+            .unwrap_or_else(|| panic!("coord '{name}' not found"))
+            .read_data::<f64>().unwrap()
+        {
+            H5Data::Array(a) => a.into_dimensionality::<Ix1>().unwrap(),
+            H5Data::Scalar(_) => panic!("coord '{name}' is scalar"),
+        }
+    };
+    let (x, y, zc, nu) = {
+        let mut h5 = H5File::new(&inst_path(&files[0], 'u'))
+            .expect("failed to open first u file");
+        h5.get_info().expect("failed to read DNS info");
+        h5.load_coords().expect("failed to load coordinates");
+        let nu = h5.info().nu.expect("'nu' not in HDF5 file");
+        (read_coord(&h5, "x"), read_coord(&h5, "y"), read_coord(&h5, "zc"), nu)
+    };
+    let (nxc, ny, nz) = (x.len(), y.len(), zc.len());
+    let re_b = 1.0 / nu;
+    let ly = (y[1] - y[0]) * ny as f64;
+    let windows = x_windows(&args.xwindows, &x);
+
+    let mut cs = SpanwiseCospectrum::new(nz, ny, nxc, ly, &windows)
+        .expect("SpanwiseCospectrum::new failed");
+    for f in &files {
+        let u = read_inst_field(&inst_path(f, 'u'), "u", nxc);
+        let w = read_inst_field(&inst_path(f, 'w'), "w", nxc);
+        cs.accumulate(&u, &w).expect("accumulate failed");
+        eprintln!("accumulated {f}");
+    }
+    let (k, co) = cs.cospectrum().expect("cospectrum failed");
+    let dk = k[1];
+
+    // reference: the fik-inst core on the same span + ensemble statistics
+    // (cf_turb_y depends on u'w' only; ny = 1 stands in for the span average)
+    let (ubar, wbar) = cs.means().expect("means failed");
+    let span = |a: &ndarray::Array2<f64>| a.clone().insert_axis(Axis(1)).into_dyn();
+    let (u3, w3) = (span(&ubar), span(&wbar));
+    let uw3 = span(&(&cs.covariance().expect("covariance failed") + &(&ubar * &wbar)));
+    let fik = fik_decomposition(
+        &u3, &u3, &w3, &ArrayD::zeros(u3.raw_dim()), &(&u3 * &u3), &uw3,
+        &x, &zc, re_b, args.half_height, false,
+    )
+    .expect("fik_decomposition failed");
+
+    let stem = args.output.strip_suffix(".dat").unwrap_or(&args.output);
+    for (iw, &(i0, i1)) in windows.iter().enumerate() {
+        let modes = fik_turb_y_modes(&(co.index_axis(Axis(0), iw).to_owned() * dk).into_dyn(), &zc, args.half_height)
+            .expect("fik_turb_y_modes failed");
+        let reference = fik.cf_turb_y.slice(s![i0..i1]).mean().unwrap();
+
+        let uprof = ubar.slice(s![.., i0..i1]).mean_axis(Axis(1)).unwrap();
+        let tau = wall_shear_stress(&uprof.into_dyn(), &zc.clone().into_dyn(), nu)
+            .expect("wall shear stress");
+        let utau = friction_velocity(tau);
+        let lambda = k.mapv(|kk| if kk > 0.0 { 2.0 * std::f64::consts::PI / kk } else { f64::INFINITY });
+
+        let mut cum = 0.0;
+        let cf_cum = modes.mapv(|c| { cum += c; cum });
+        let mut cols: HashMap<String, Array1<f64>> = HashMap::new();
+        cols.insert("yk".into(), k.clone());
+        cols.insert("ylambda".into(), lambda.clone());
+        cols.insert("ylambda_plus".into(), lambda.mapv(|l| l * utau / nu));
+        cols.insert("cf_turb_y".into(), modes.clone());
+        // premultiplied: k dC/dk = per-mode contribution × m, per unit ln λ
+        cols.insert("cf_premul".into(), Array1::from_iter(modes.iter().enumerate().map(|(m, c)| m as f64 * c)));
+        // wavelengths ≥ λ_m (k ≤ k_m)
+        cols.insert("cf_cum".into(), cf_cum);
+
+        let path = format!("{stem}_x{:.2}-{:.2}.dat", x[i0], x[i1 - 1]);
+        write_dat(Path::new(&path), "yk", utau / nu, &cols).expect("failed to write .dat file");
+        eprintln!(
+            "x ∈ [{:.3}, {:.3}]: Σ_λ cf_turb_y = {:.6e}, fik-inst cf_turb_y = {reference:.6e}  (Re_τ = {:.1})",
+            x[i0], x[i1 - 1], modes.sum(), utau * args.half_height / nu
+        );
+        eprintln!("wrote {path}");
+    }
+}
+
+// ─── diagnostics sub-command ──────────────────────────────────────────────────
+
+#[derive(Args)]
+struct DiagnosticsArgs {
+    /// Input subavg HDF5 file (raw: uw is the total second moment).
+    #[arg(short, long, value_name = "FILE")]
+    file: String,
+    /// Output .dat file (<stem>_x<pos>.dat per station with --xloc).
+    #[arg(short, long, default_value = "diagnostics.dat")]
+    output: String,
+    /// Physical x locations for wall-normal profiles, comma-separated.
+    #[arg(long, value_delimiter = ',')]
+    xloc: Option<Vec<f64>>,
+    /// x-index range START END (0-based, half-open) over which ū and u'w' are
+    /// averaged when --xloc is not given; default: full streamwise extent.
+    #[arg(long, num_args = 2, value_name = "INT")]
+    xrange: Option<Vec<usize>>,
+    /// Mask ν_t, l_m and the stress fraction (NaN) where |∂ū/∂z| is below
+    /// this fraction of its maximum (near the centreline).
+    #[arg(long, default_value_t = 0.02)]
+    mask: f64,
+}
+
+fn run_diagnostics(args: DiagnosticsArgs) {
+    let mut h5 = H5File::new(&args.file).expect("failed to open HDF5 file");
+    h5.get_info().expect("failed to read DNS info");
+    h5.load_coords().expect("failed to load coordinates");
+    let nu = h5.info().nu.expect("'nu' not in HDF5 file");
+    let load_coord = |h5: &H5File, name: &str| -> Array1<f64> {
+        match h5.coord(name)
+            .unwrap_or_else(|| panic!("coord '{name}' not found"))
+            .read_data::<f64>().unwrap()
+        {
+            H5Data::Array(a) => a.into_dimensionality::<Ix1>().unwrap(),
+            H5Data::Scalar(_) => panic!("coord '{name}' is scalar"),
+        }
+    };
+    let x  = load_coord(&h5, "x");
+    let zc = load_coord(&h5, "zc");
+    let nxc = x.len();
+    drop(h5);
+
+    let load = |name: &str| avg_axis(&read_inst_field(&args.file, name, nxc), 1)
+        .expect("spanwise average failed");
+    let u = load("u");
+    let uw = &load("uw") - &(&u * &load("w"));
+
+    // profiles of the station / x-averaged ū and u'w', then the ratios
+    let fields: [(&str, &ArrayD<f64>); 2] = [("u", &u), ("uw", &uw)];
+    for (path, mut cols) in profile_tables(&fields, &x, &zc, &args.xloc, &args.xrange, &args.output) {
+        let d = shear_diagnostics(&cols["u"], &cols["uw"], &zc, nu, args.mask)
+            .expect("shear_diagnostics failed");
+        cols.insert("dudz".into(), d.dudz);
+        cols.insert("nut".into(), d.nu_t.mapv(|v| v / nu));
+        cols.insert("lmix".into(), d.lmix.clone());
+        cols.insert("lmix_plus".into(), d.lmix.mapv(|v| v * d.utau / nu));
+        cols.insert("indicator".into(), d.indicator);
+        cols.insert("rs_frac".into(), d.rs_fraction);
+        write_dat(Path::new(&path), "zc", 1.0 / nu, &cols).expect("failed to write .dat file");
+        eprintln!("wrote {path}  (u_tau = {:.5e})", d.utau);
+    }
+}
+
+// ─── scaling sub-command ──────────────────────────────────────────────────────
+
+#[derive(Args)]
+struct ScalingArgs {
+    /// Input subavg HDF5 file.
+    #[arg(short, long, value_name = "FILE")]
+    file: String,
+    /// Variables to normalize (u is always read for the scales).
+    #[arg(short, long, num_args = 1.., value_name = "VAR")]
+    variables: Vec<String>,
+    /// Output .dat file (<stem>_x<pos>.dat per station with --xloc).
+    #[arg(short, long, default_value = "scaling.dat")]
+    output: String,
+    /// Physical x locations for wall-normal profiles, comma-separated.
+    #[arg(long, value_delimiter = ',')]
+    xloc: Option<Vec<f64>>,
+    /// Inner scaling: local (u_τ(x)), reference (--tau-ref) or upstream
+    /// (u_τ at --x-ref).
+    #[arg(long, default_value = "local")]
+    inner: String,
+    /// Wall shear stress of the reference case for --inner reference.
+    #[arg(long)]
+    tau_ref: Option<f64>,
+    /// Physical x of the station whose u_τ is used for --inner upstream.
+    #[arg(long)]
+    x_ref: Option<f64>,
+    /// Outer scaling instead of wall units: h, delta99 or bulk.
+    #[arg(long)]
+    outer: Option<String>,
+    /// Half-channel height for --outer h and bulk.
+    #[arg(long, default_value_t = 1.0)]
+    half_height: f64,
+    /// Also write the normalized (nz, nx) maps and the per-station scales
+    /// to this HDF5 file.
+    #[arg(long, value_name = "FILE")]
+    maps: Option<String>,
+}
+
+fn run_scaling(args: ScalingArgs) {
+    let mut h5 = H5File::new(&args.file).expect("failed to open HDF5 file");
+    h5.get_info().expect("failed to read DNS info");
+    h5.load_coords().expect("failed to load coordinates");
+    let nu = h5.info().nu.expect("'nu' not in HDF5 file");
+    let load_coord = |h5: &H5File, name: &str| -> Array1<f64> {
+        match h5.coord(name)
+            .unwrap_or_else(|| panic!("coord '{name}' not found"))
+            .read_data::<f64>().unwrap()
+        {
+            H5Data::Array(a) => a.into_dimensionality::<Ix1>().unwrap(),
+            H5Data::Scalar(_) => panic!("coord '{name}' is scalar"),
+        }
+    };
+    let x  = load_coord(&h5, "x");
+    let zc = load_coord(&h5, "zc");
+    let nxc = x.len();
+    drop(h5);
+
+    // ── span-averaged (nz, nx) fields ─────────────────────────────────────────
+    let load = |name: &str| avg_axis(&read_inst_field(&args.file, name, nxc), 1)
+        .expect("spanwise average failed")
+        .into_dimensionality::<ndarray::Ix2>()
+        .unwrap();
+    let units = UnitRegistry::new();
+    for var in &args.variables {
+        units.dimension(var).unwrap_or_else(|e| panic!("{e}"));
+    }
+    let u = load("u");
+    let mut fields: HashMap<String, ndarray::Array2<f64>> = HashMap::new();
+    for var in &args.variables {
+        let f = if var == "u" { u.clone() } else { load(var) };
+        fields.insert(var.clone(), f);
+    }
+    fields.insert("u".into(), u.clone());
+
+    // ── per-station scales ────────────────────────────────────────────────────
+    let tau = local_tau(&u, &zc, nu).expect("local τ_w failed");
+    let nearest = |xq: f64| (0..nxc)
+        .min_by(|&a, &b| (x[a] - xq).abs().partial_cmp(&(x[b] - xq).abs()).unwrap())
+        .unwrap();
+    let inner = match args.inner.as_str() {
+        "local" => InnerScaling::Local,
+        "reference" => InnerScaling::Reference(args.tau_ref.expect("--inner reference needs --tau-ref")),
+        "upstream" => InnerScaling::Upstream(nearest(args.x_ref.expect("--inner upstream needs --x-ref"))),
+        s => panic!("unknown --inner '{s}' (expected local, reference or upstream)"),
+    };
+    let utau = inner.utau(&tau).expect("u_τ failed");
+    let unscaled: Vec<usize> = (0..nxc).filter(|&ix| utau[ix].is_nan()).collect();
+    if let (Some(&a), Some(&b)) = (unscaled.first(), unscaled.last()) {
+        eprintln!(
+            "warning: τ_w ≤ 0 at {} station(s) in x ∈ [{:.4}, {:.4}]; their wall units are NaN",
+            unscaled.len(), x[a], x[b]
+        );
+    }
+    let outer = args.outer.as_deref().map(|o| match o {
+        "h" => OuterScaling::HalfHeight(args.half_height),
+        "delta99" => OuterScaling::Delta99,
+        "bulk" => OuterScaling::Bulk(args.half_height),
+        s => panic!("unknown --outer '{s}' (expected h, delta99 or bulk)"),
+    });
+
+    let (scaled, coord_key, scales) = match outer {
+        Some(o) => {
+            let (len, vel) = o.scales(&u, &zc).expect("outer scales failed");
+            let f = fields_to_outer_units(&fields, &units, "z", &zc, &len, &vel).expect("outer units failed");
+            (f, "zouter", vec![("length", len), ("velocity", vel)])
+        }
+        None => {
+            let f = fields_to_wall_units(&fields, &units, "z", &zc, nu, &utau).expect("wall units failed");
+            (f, "zplus", vec![("tau", tau.clone()), ("utau", utau.clone())])
+        }
+    };
+
+    // ── profiles: one per station, or x-averaged over the domain ──────────────
+    let dyn_fields: Vec<(String, ArrayD<f64>)> = scaled.iter()
+        .map(|(k, v)| (k.clone(), v.clone().into_dyn()))
+        .collect();
+    let refs: Vec<(&str, &ArrayD<f64>)> = dyn_fields.iter().map(|(k, v)| (k.as_str(), v)).collect();
+    let stations: Vec<Option<usize>> = match &args.xloc {
+        Some(locs) => locs.iter().map(|&xq| Some(nearest(xq))).collect(),
+        None => vec![None],
+    };
+    let tables = profile_tables(&refs, &x, &zc, &args.xloc, &None, &args.output);
+    for ((path, cols), ix) in tables.into_iter().zip(stations) {
+        let ret = match (outer, ix) {
+            (None, Some(ix)) => utau[ix] / nu,
+            _ => 1.0 / nu,
+        };
+        write_dat(Path::new(&path), coord_key, ret, &cols).expect("failed to write .dat file");
+        eprintln!("wrote {path}");
+    }
+
+    if let Some(maps) = &args.maps {
+        let out = hdf5::File::create(maps).expect("failed to create maps file");
+        for (name, f) in &scaled {
+            write_h5(&out, name, f);
+        }
+        for (name, a) in &scales {
+            write_h5(&out, name, a);
+        }
+        write_h5(&out, "x", &x);
+        write_h5(&out, "zc", &zc);
+        eprintln!("wrote {maps}");
+    }
+}
+
+// ─── compare-control sub-command ──────────────────────────────────────────────
+
+#[derive(Args)]
+struct CompareControlArgs {
+    /// Controlled case: one subavg file, or snapshot templates with --inst.
+    #[arg(short, long, num_args = 1.., value_name = "FILE", required = true)]
+    control: Vec<String>,
+    /// Uncontrolled reference case on the same grid, given like --control.
+    #[arg(short, long, num_args = 1.., value_name = "FILE", required = true)]
+    reference: Vec<String>,
+    /// Read both cases as instantaneous snapshot ensembles (`{}` in a
+    /// template is replaced by the component, as in fik-inst).
+    #[arg(long, default_value_t = false)]
+    inst: bool,
+    /// Output .dat file (default: compare_control.dat).
+    #[arg(short, long, value_name = "FILE", default_value = "compare_control.dat")]
+    output: String,
+    /// Physical x range X0 X1 of the control region for the integrated
+    /// measures; default: the full (trimmed) streamwise extent.
+    #[arg(long, num_args = 2, value_names = ["X0", "X1"])]
+    region: Option<Vec<f64>>,
+    /// Half-channel height h (default: 1.0).
+    #[arg(long, default_value_t = 1.0)]
+    half_height: f64,
+    /// Number of points to drop from the start of the x-range.
+    #[arg(long, default_value_t = 0usize)]
+    trim_start: usize,
+    /// Number of points to drop from the end of the x-range.
+    #[arg(long, default_value_t = 0usize)]
+    trim_end: usize,
+    /// Treat streamwise direction as periodic for derivative stencils.
+    #[arg(long, default_value_t = false)]
+    periodic_x: bool,
+}
+
+/// Mean fields of one case from a subavg file (stored total moments) or an
+/// instantaneous ensemble; `v`/`uv` are left empty.
+fn load_case(files: &[String], inst: bool) -> InstEnsemble {
+    if inst {
+        return accumulate_inst(files, false, true);
+    }
+    assert!(files.len() == 1, "a subavg case is a single file (use --inst for snapshot ensembles)");
+    let path = &files[0];
+    let mut h5 = H5File::new(path).expect("failed to open HDF5 file");
+    h5.get_info().expect("failed to read DNS info");
+    h5.load_coords().expect("failed to load coordinates");
+    let nu = h5.info().nu.expect("'nu' not in HDF5 file");
+    let load_coord = |h5: &H5File, name: &str| -> Array1<f64> {
+        match h5.coord(name)
+            .unwrap_or_else(|| panic!("coord '{name}' not found"))
+            .read_data::<f64>().unwrap()
+        {
+            H5Data::Array(a) => a.into_dimensionality::<Ix1>().unwrap(),
+            H5Data::Scalar(_) => panic!("coord '{name}' is scalar"),
+        }
+    };
+    let (x, y, zc) = (load_coord(&h5, "x"), load_coord(&h5, "y"), load_coord(&h5, "zc"));
+    drop(h5);
+    let nxc = x.len();
+    let load = |name: &str| read_inst_field(path, name, nxc);
+    let empty = || ArrayD::<f64>::zeros(ndarray::IxDyn(&[0]));
+    InstEnsemble {
+        u: load("u"), v: empty(), w: load("w"), p: load("p"),
+        uu: load("uu"), uw: load("uw"), uv: empty(),
+        pw: load("pw"), www: load("www"),
+        x, y, zc, nu,
+    }
+}
+
+fn run_compare_control(args: CompareControlArgs) {
+    let ctrl = load_case(&args.control, args.inst);
+    let refc = load_case(&args.reference, args.inst);
+    assert!(
+        ctrl.x.len() == refc.x.len() && ctrl.zc.len() == refc.zc.len()
+            && ctrl.x.iter().zip(refc.x.iter()).all(|(a, b)| (a - b).abs() < 1e-9),
+        "control and reference cases are not on the same grid"
+    );
+    assert!((ctrl.nu - refc.nu).abs() <= 1e-12 * refc.nu, "control and reference differ in nu");
+    let nu = refc.nu;
+    let re_b = 1.0 / nu;
+    let zc = refc.zc.clone();
+
+    let nx_full = refc.x.len();
+    let lo = args.trim_start;
+    let hi = nx_full - args.trim_end;
+    assert!(lo < hi, "trim_start ({lo}) must be < nx - trim_end ({hi})");
+    let x = refc.x.slice(s![lo..hi]).to_owned();
+    let region = x_windows(&args.region, &x)[0];
+
+    // ── per case: direct C_f, FIK, RD, span-averaged wall fields ──────────────
+    let analyse = |e: &InstEnsemble| {
+        let trim = |a: &ArrayD<f64>| a.slice_axis(Axis(2), (lo..hi).into()).to_owned();
+        let (u, w, p, uu, uw) = (trim(&e.u), trim(&e.w), trim(&e.p), trim(&e.uu), trim(&e.uw));
+        let (pw, www) = (trim(&e.pw), trim(&e.www));
+        // Direct C_f from viscous sublayer: 2ν·u(iz=1)/zc[1], as in fik/rd.
+        let iz1 = 1usize;
+        let cf = u.slice(s![iz1, .., ..]).mean_axis(Axis(0)).expect("mean over y")
+            .mapv(|uv| 2.0 * nu * uv / zc[iz1]);
+        let fik = fik_decomposition(
+            &u, &u, &w, &p, &uu, &uw, &x, &zc, re_b, args.half_height, args.periodic_x,
+        ).expect("fik_decomposition failed");
+        let rd = rd_decomposition(
+            &u, &w, &p, &uu, &uw, &x, &zc, re_b, args.half_height, 1.0, args.periodic_x,
+        ).expect("rd_decomposition failed");
+        let span = |a: &ArrayD<f64>| avg_axis(a, 1).expect("spanwise average failed")
+            .into_dimensionality::<ndarray::Ix2>().unwrap();
+        let p_in = wall_power_input(&span(&pw), &span(&www), &zc);
+        (cf, fik, rd, p_in)
+    };
+    let (cf, fik, rd, p_in) = analyse(&ctrl);
+    let (cf_ref, fik_ref, rd_ref, p_in_ref) = analyse(&refc);
+
+    let fik_terms = |f: &FikDecomposition| vec![
+        ("lam", f.cf_laminar.clone()), ("center", f.cf_center.clone()),
+        ("turb_x", f.cf_turb_x.clone()), ("turb_y", f.cf_turb_y.clone()),
+        ("conv_x", f.cf_conv_x.clone()), ("conv_y", f.cf_conv_y.clone()),
+        ("diff_x", f.cf_diff_x.clone()), ("source", f.cf_source.clone()),
+        ("total", f.cf_total()),
+    ];
+    let rd_terms = |r: &RdDecomposition| vec![
+        ("diss", r.cf_diss.clone()), ("prod", r.cf_prod.clone()),
+        ("growth", r.cf_growth()), ("center", r.cf_center.clone()),
+        ("total", r.cf_total()),
+    ];
+
+    let mut cols: HashMap<String, Array1<f64>> = HashMap::new();
+    cols.insert("x".into(), x.clone());
+    cols.insert("cf".into(), cf.clone());
+    cols.insert("cf_ref".into(), cf_ref.clone());
+    cols.insert("dr".into(), drag_reduction(&cf, &cf_ref).expect("drag reduction failed"));
+    cols.insert("p_in".into(), &p_in - &p_in_ref);
+
+    eprintln!("ΔC_f attribution, mean over x ∈ [{:.4}, {:.4}]:", x[region.0], x[region.1 - 1]);
+    for (method, terms, terms_ref) in [
+        ("fik", fik_terms(&fik), fik_terms(&fik_ref)),
+        ("rd", rd_terms(&rd), rd_terms(&rd_ref)),
+    ] {
+        for ((name, a), (_, b)) in terms.into_iter().zip(terms_ref) {
+            let d = &a - &b;
+            eprintln!("  {method:>3} {name:<7} ΔC_f = {:+.5e}", region_mean(&d, &x, region));
+            cols.insert(format!("dcf_{method}_{name}"), d);
+        }
+    }
+
+    // the reference's own wall flux (ideally zero) is not charged to the control
+    let eff = control_efficiency(&x, &cf, &cf_ref, &cols["p_in"], region)
+        .expect("control efficiency failed");
+    eprintln!("integrated DR        = {:.4}", eff.dr);
+    eprintln!("friction power       = {:.5e} (reference {:.5e})", eff.power_friction, eff.power_friction_ref);
+    eprintln!("power input          = {:.5e}", eff.power_input);
+    eprintln!("net energy saving S  = {:.4}", eff.saving);
+    eprintln!("gain G               = {:.4}", eff.gain);
+
+    write_dat(Path::new(&args.output), "x", re_b, &cols).expect("failed to write .dat file");
+    eprintln!("wrote {}", args.output);
+}
+
+// ─── control-region sub-command ───────────────────────────────────────────────
+
+#[derive(Args)]
+struct ControlRegionArgs {
+    /// Subavg files or snapshot templates (`{}` → component); w is averaged
+    /// over all of them, plus any generated by --pattern/--range.
+    #[arg(short, long, num_args = 1.., value_name = "FILE")]
+    files: Vec<String>,
+    /// Filename pattern with a time placeholder `{t}`, e.g. `inst_{t}_{}.h5`.
+    #[arg(long, value_name = "PATTERN")]
+    pattern: Option<String>,
+    /// Timestep range START END STEP (inclusive) used with --pattern.
+    #[arg(long, num_args = 3, value_names = ["START", "END", "STEP"])]
+    range: Option<Vec<usize>>,
+    /// Wall points with |w̄| above this fraction of its maximum are active.
+    #[arg(long, default_value_t = 0.1)]
+    threshold: f64,
+    /// Output .dat file with the streamwise wall-velocity profile; its
+    /// `x_act` column is x relative to the x_zero origin when the files
+    /// carry one, else to the detected leading edge.
+    #[arg(short, long, default_value = "control_region.dat")]
+    output: String,
+}
+
+fn run_control_region(args: ControlRegionArgs) {
+    let files = snapshot_templates(&args.files, &args.pattern, &args.range);
+    let first = inst_path(&files[0], 'w');
+    let mut h5 = H5File::new(&first).expect("failed to open HDF5 file");
+    h5.get_info().expect("failed to read DNS info");
+    h5.load_coords().expect("failed to load coordinates");
+    let load_coord = |h5: &H5File, name: &str| -> Option<Array1<f64>> {
+        match h5.coord(name)?.read_data::<f64>().unwrap() {
+            H5Data::Array(a) => Some(a.into_dimensionality::<Ix1>().unwrap()),
+            H5Data::Scalar(v) => Some(Array1::from_elem(1, v)),
+        }
+    };
+    let x  = load_coord(&h5, "x").expect("coord 'x' not found");
+    let y  = load_coord(&h5, "y").expect("coord 'y' not found");
+    let zc = load_coord(&h5, "zc").expect("coord 'zc' not found");
+    let x_zero = load_coord(&h5, "x_zero");
+    let nu = h5.info().nu.expect("'nu' not in HDF5 file");
+    drop(h5);
+    let nxc = x.len();
+
+    // ── wall plane of the (ensemble-)mean w ───────────────────────────────────
+    let mut ww = ndarray::Array2::<f64>::zeros((y.len(), nxc));
+    for f in &files {
+        ww += &wall_plane(&read_inst_field(&inst_path(f, 'w'), "w", nxc), &zc);
+    }
+    ww /= files.len() as f64;
+    let g = detect_control(&ww, &x, &y, args.threshold).expect("control detection failed");
+
+    let ly = y.len() as f64 * (y[1] - y[0]);
+    eprintln!("control region  x ∈ [{:.4}, {:.4}]  (index {}..{})", g.x_start, g.x_end, g.ix.0, g.ix.1);
+    match g.period_y {
+        Some(p) => eprintln!("spanwise slits  period {p:.4} ({} per span), duty cycle {:.3}", (ly / p).round(), g.duty),
+        None => eprintln!("spanwise uniform control"),
+    }
+    eprintln!("amplitude       w̄_w = {:+.5e} ({})", g.amplitude, if g.amplitude > 0.0 { "blowing" } else { "suction" });
+    eprintln!("momentum coeff. C_μ = {:.5e}", g.momentum_coefficient);
+    // x relative to the actuator: the origin of the stored x_zero coordinate
+    // when the case has one, else the detected leading edge
+    let origin = match &x_zero {
+        Some(xz) => {
+            // x_zero holds x relative to the case's own origin
+            let origin = if xz.len() == nxc { x[0] - xz[0] } else { xz[0] };
+            eprintln!("x_zero origin   {origin:.4} (detected leading edge at x_act = {:+.4})", g.x_start - origin);
+            origin
+        }
+        None => g.x_start,
+    };
+    let (x0, x1, xl) = (x[0], x[nxc - 1], x[g.ix.1.min(nxc - 1)]);
+    eprintln!("suggested       --region {} {}", g.x_start, xl);
+    // upstream / active / downstream, dropping any that hold no grid point
+    // (a region starting at x[0] or reaching the outflow)
+    let suggested: Vec<String> = [(x0, g.x_start), (g.x_start, xl), (xl, x1)]
+        .into_iter()
+        .filter(|&(a, b)| x.iter().any(|&v| v >= a && v < b))
+        .map(|(a, b)| format!("{a},{b}"))
+        .collect();
+    if !suggested.is_empty() {
+        eprintln!("                --xwindows {}", suggested.join(","));
+    }
+
+    let mut cols: HashMap<String, Array1<f64>> = HashMap::new();
+    cols.insert("x".into(), x.clone());
+    cols.insert("x_act".into(), x.mapv(|v| v - origin));
+    cols.insert("w_wall".into(), ww.mean_axis(Axis(0)).expect("mean over y"));
+    cols.insert(
+        "active".into(),
+        g.mask.mapv(|a| if a { 1.0 } else { 0.0 }).mean_axis(Axis(0)).expect("mean over y"),
+    );
+    write_dat(Path::new(&args.output), "x", 1.0 / nu, &cols).expect("failed to write .dat file");
+    eprintln!("wrote {}", args.output);
+}
+
+// ─── closure sub-command ──────────────────────────────────────────────────────
+
+#[derive(Args)]
+struct ClosureArgs {
+    /// One subavg file, or snapshot templates with --inst.
+    #[arg(short, long, num_args = 1.., value_name = "FILE", required = true)]
+    files: Vec<String>,
+    /// Read the input as an instantaneous snapshot ensemble.
+    #[arg(long, default_value_t = false)]
+    inst: bool,
+    /// Summary table, one row per (method, setting, region).
+    #[arg(short, long, default_value = "closure.dat")]
+    output: String,
+    /// Half-channel heights to sweep, comma-separated.
+    #[arg(long, value_delimiter = ',', default_value = "1.0")]
+    half_heights: Vec<f64>,
+    /// Trims START:END to sweep, comma-separated, e.g. `0:0,5:5,10:10`.
+    #[arg(long, value_delimiter = ',', default_value = "0:0")]
+    trims: Vec<String>,
+    /// periodic_x settings to sweep, e.g. `false,true`.
+    #[arg(long, value_delimiter = ',', default_value = "false")]
+    periodic_x: Vec<bool>,
+    /// Wall-gradient stencils for the direct C_f: cell1 (fik/rd default),
+    /// cell0, quad; comma-separated.
+    #[arg(long, value_delimiter = ',', default_value = "cell1")]
+    stencils: Vec<String>,
+    /// Physical x regions as pairs X0 X1 (comma-separated); default: the
+    /// full trimmed extent.
+    #[arg(long, value_delimiter = ',')]
+    xwindows: Option<Vec<f64>>,
+    /// Reference velocity of the RD energy frame.
+    #[arg(long, default_value_t = 1.0)]
+    u_ref: f64,
+}
+
+fn run_closure(args: ClosureArgs) {
+    let ens = load_case(&args.files, args.inst);
+    let nu = ens.nu;
+    let re_b = 1.0 / nu;
+    let zc = &ens.zc;
+    let nx_full = ens.x.len();
+    let trims: Vec<(usize, usize)> = args.trims.iter()
+        .map(|t| {
+            let (a, b) = t.split_once(':').unwrap_or_else(|| panic!("--trims entry '{t}' is not START:END"));
+            (a.parse().expect("trim START"), b.parse().expect("trim END"))
+        })
+        .collect();
+    let stencils: Vec<WallStencil> = args.stencils.iter()
+        .map(|n| WallStencil::from_name(n).unwrap_or_else(|| panic!("unknown stencil '{n}' (cell1, cell0, quad)")))
+        .collect();
+    const FIK_TERMS: [&str; 8] = ["lam", "center", "turb_x", "turb_y", "conv_x", "conv_y", "diff_x", "source"];
+    const RD_TERMS: [&str; 8] = ["diss", "prod", "conv_x", "conv_y", "turb_x", "diff_x", "source", "center"];
+
+    let names = [
+        "case", "method", "h", "trim0", "trim1", "periodic", "stencil",
+        "x0", "x1", "l2", "linf", "mean", "dominant", "align",
+    ];
+    let mut rows: Vec<[f64; 14]> = Vec::new();
+    eprintln!(
+        "{:>6} {:>6} {:>11} {:>5} {:>7} {:>24} {:>10} {:>10} {:>11}  dominant",
+        "method", "h", "trim", "per", "stencil", "region", "L2", "Linf", "mean",
+    );
+    for &h in &args.half_heights {
+        for &(t0, t1) in &trims {
+            assert!(t0 + t1 < nx_full, "trim {t0}:{t1} leaves no points (nx = {nx_full})");
+            let (lo, hi) = (t0, nx_full - t1);
+            let trim = |a: &ArrayD<f64>| a.slice_axis(Axis(2), (lo..hi).into()).to_owned();
+            let (u, w, p, uu, uw) = (trim(&ens.u), trim(&ens.w), trim(&ens.p), trim(&ens.uu), trim(&ens.uw));
+            let x = ens.x.slice(s![lo..hi]).to_owned();
+            let us = avg_axis(&u, 1).expect("spanwise average failed");
+            let windows = x_windows(&args.xwindows, &x);
+            for &per in &args.periodic_x {
+                let fik = fik_decomposition(&u, &u, &w, &p, &uu, &uw, &x, zc, re_b, h, per)
+                    .expect("fik_decomposition failed");
+                let rd = rd_decomposition(&u, &w, &p, &uu, &uw, &x, zc, re_b, h, args.u_ref, per)
+                    .expect("rd_decomposition failed");
+                let fik_terms = [
+                    &fik.cf_laminar, &fik.cf_center, &fik.cf_turb_x, &fik.cf_turb_y,
+                    &fik.cf_conv_x, &fik.cf_conv_y, &fik.cf_diff_x, &fik.cf_source,
+                ].map(|t| t.clone());
+                let rd_terms = [
+                    &rd.cf_diss, &rd.cf_prod, &rd.cf_conv_x, &rd.cf_conv_y,
+                    &rd.cf_turb_x, &rd.cf_diff_x, &rd.cf_source, &rd.cf_center,
+                ].map(|t| t.clone());
+                for (si, st) in stencils.iter().enumerate() {
+                    let cf_direct = st.cf_direct(&us, zc, nu).expect("direct C_f failed");
+                    for (mi, (method, total, terms, tnames)) in [
+                        ("fik", fik.cf_total(), &fik_terms, &FIK_TERMS),
+                        ("rd", rd.cf_total(), &rd_terms, &RD_TERMS),
+                    ].into_iter().enumerate() {
+                        for &(i0, i1) in &windows {
+                            let m = closure_metrics(&total, &cf_direct, terms, (i0, i1))
+                                .expect("closure metrics failed");
+                            eprintln!(
+                                "{method:>6} {h:>6.3} {t0:>5}:{t1:<5} {per:>5} {:>7} [{:>9.4}, {:>9.4}] {:>10.3e} {:>10.3e} {:>+11.3e}  {} ({:.2})",
+                                st.name(), x[i0], x[i1 - 1], m.l2, m.linf, m.mean, m.dominant.map_or("-", |k| tnames[k]), m.alignment,
+                            );
+                            rows.push([
+                                rows.len() as f64, mi as f64, h, t0 as f64, t1 as f64,
+                                if per { 1.0 } else { 0.0 }, si as f64, x[i0], x[i1 - 1],
+                                m.l2, m.linf, m.mean, m.dominant.map_or(f64::NAN, |k| k as f64), m.alignment,
+                            ]);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    // method 0 = fik, 1 = rd; stencil and dominant index the --stencils list
+    // and the term lists above (dominant NaN: residual constant over x)
+    let mut cols: HashMap<String, Array1<f64>> = HashMap::new();
+    for (c, name) in names.iter().enumerate() {
+        cols.insert((*name).into(), rows.iter().map(|r| r[c]).collect());
+    }
+    write_dat(Path::new(&args.output), "case", re_b, &cols).expect("failed to write .dat file");
+    eprintln!("wrote {}", args.output);
+}
+
+// ─── check sub-command ────────────────────────────────────────────────────────
+
+#[derive(Args)]
+struct CheckArgs {
+    /// Subavg files, or snapshot templates with --inst; each is checked on
+    /// its own.
+    #[arg(short, long, num_args = 1.., value_name = "FILE", required = true)]
+    files: Vec<String>,
+    /// Read the input as instantaneous snapshots (`{}` in a template is
+    /// replaced by the component, as in fik-inst).  The momentum residual of
+    /// a snapshot is the N-S residual, i.e. the local ∂u/∂t; it is written and
+    /// reported but not checked against --res-tol.
+    #[arg(long, default_value_t = false)]
+    inst: bool,
+    /// Output HDF5 file of residual fields (<stem>_<k>.h5 per input when
+    /// several are given); profiles go to <stem>_x.dat and <stem>_z.dat.
+    #[arg(short, long, default_value = "check.h5")]
+    output: String,
+    /// Tolerance on the RMS of ∇·u.
+    #[arg(long, default_value_t = 1e-2)]
+    div_tol: f64,
+    /// Tolerance on the L2 norm of the RANS momentum residual (subavg input).
+    #[arg(long, default_value_t = 1e-2)]
+    res_tol: f64,
+    /// Tolerance on the relative spread of the bulk flow rate Q(x).
+    #[arg(long, default_value_t = 1e-3)]
+    q_tol: f64,
+    /// Momentum residual with the solver's staggered stencil (needs --inst and
+    /// the `zd` dual grid) instead of the collocated one; points it does not
+    /// reach are NaN in the output.
+    #[arg(long, default_value_t = false)]
+    fortran: bool,
+    /// Half-channel height h; Q(x) integrates over the walls at 0 and 2h.
+    #[arg(long, default_value_t = 1.0)]
+    half_height: f64,
+    /// Treat streamwise direction as periodic for derivative stencils.
+    #[arg(long, default_value_t = false)]
+    periodic_x: bool,
+}
+
+fn run_check(args: CheckArgs) {
+    // the staggered stencil is the instantaneous operator, without the
+    // Reynolds-stress divergence of a subavg file
+    assert!(!args.fortran || args.inst, "--fortran needs --inst");
+    let stem = args.output.strip_suffix(".h5").unwrap_or(&args.output);
+    let mut failed = 0usize;
+    for (k, f) in args.files.iter().enumerate() {
+        let base = if args.files.len() == 1 { stem.to_string() } else { format!("{stem}_{k}") };
+        let path = |c: char| if args.inst { inst_path(f, c) } else { f.clone() };
+
+        let mut h5 = H5File::new(&path('u')).expect("failed to open HDF5 file");
+        h5.get_info().expect("failed to read DNS info");
+        h5.load_coords().expect("failed to load coordinates");
+        let nu = h5.info().nu.expect("'nu' not in HDF5 file");
+        let load_coord = |h5: &H5File, name: &str| -> Array1<f64> {
+            match h5.coord(name)
+                .unwrap_or_else(|| panic!("coord '{name}' not found"))
+                .read_data::<f64>().unwrap()
+            {
+                H5Data::Array(a) => a.into_dimensionality::<Ix1>().unwrap(),
+                H5Data::Scalar(_) => panic!("coord '{name}' is scalar"),
+            }
+        };
+        let (x, y, zc) = (load_coord(&h5, "x"), load_coord(&h5, "y"), load_coord(&h5, "zc"));
+        let zd = args.fortran.then(|| load_coord(&h5, "zd"));
+        drop(h5);
+        let (nz, nxc) = (zc.len(), x.len());
+        let load = |c: char, name: &str| read_inst_field(&path(c), name, nxc);
+        let (u, v, w, p) = (load('u', "u"), load('v', "v"), load('w', "w"), load('p', "p"));
+        let periodic = [false, true, args.periodic_x];
+
+        let div = divergence(&u, &v, &w, &x, &y, &zc, periodic).expect("divergence failed");
+        let (rx, ry, rz, res_l2) = if let Some(zd) = &zd {
+            // staggered fields keep the outflow column: (nz, ny, nx + 1)
+            let raw = |c: char| read_inst_field(&path(c), &c.to_string(), nxc + 1);
+            let (fx, fy, fz) = ns_residual_fortran_stencil(&raw('u'), &raw('v'), &raw('w'), &raw('p'), &x, &y, &zc, zd, nu)
+                .expect("ns_residual_fortran_stencil failed");
+            let (fx, fy, fz) = (fx.into_dyn(), fy.into_dyn(), fz.into_dyn());
+            let l2 = ns_residual_l2(&fx, &fy, &fz).expect("residual L2 failed");
+            // interior iz = 1..nz-2, ix = 1..nx on the full grid
+            let embed = |r: &ArrayD<f64>| {
+                let mut a = ArrayD::from_elem(u.shape(), f64::NAN);
+                a.slice_mut(s![1..nz - 2, .., 1..nxc]).assign(r);
+                a
+            };
+            (embed(&fx), embed(&fy), embed(&fz), l2)
+        } else {
+            let (rx, ry, rz) = if args.inst {
+                ns_momentum_residual(&u, &v, &w, &p, &x, &y, &zc, nu, periodic)
+                    .expect("ns_momentum_residual failed")
+            } else {
+                let m = |name: &str| read_inst_field(f, name, nxc);
+                rans_momentum_residual(
+                    &u, &v, &w, &p,
+                    &m("uu"), &m("uv"), &m("uw"), &m("vv"), &m("vw"), &m("ww"),
+                    &x, &y, &zc, nu, periodic,
+                ).expect("rans_momentum_residual failed")
+            };
+            let l2 = ns_residual_l2(&rx, &ry, &rz).expect("residual L2 failed");
+            (rx, ry, rz, l2)
+        };
+        let res = (&(&rx * &rx + &ry * &ry) + &(&rz * &rz)).mapv(f64::sqrt);
+        let q = bulk_flow_rate(&u, &y, &zc, args.half_height).expect("bulk_flow_rate failed");
+
+        let mut checks = vec![Check { name: "div_rms", value: rms(&div), tol: args.div_tol }];
+        if !args.inst {
+            checks.push(Check { name: "res_l2", value: res_l2, tol: args.res_tol });
+        }
+        checks.push(Check { name: "q_spread", value: relative_spread(&q), tol: args.q_tol });
+        let max_div = div.iter().fold(0.0f64, |a, v| a.max(v.abs()));
+        eprintln!("{f}  (max |div| = {max_div:.4e}, <Q> = {:.6e})", q.mean().unwrap());
+        for c in &checks {
+            let verdict = if c.passed() { "PASS" } else { "FAIL" };
+            eprintln!("  {:<9} {:>12.4e}  tol {:>9.2e}  {verdict}", c.name, c.value, c.tol);
+        }
+        if args.inst {
+            eprintln!("  {:<9} {:>12.4e}  (local ∂u/∂t, not checked)", "res_l2", res_l2);
+        }
+        failed += checks.iter().filter(|c| !c.passed()).count();
+
+        let h5path = format!("{base}.h5");
+        let out = hdf5::File::create(&h5path).expect("failed to create output HDF5");
+        for (name, a) in [("div", &div), ("res_x", &rx), ("res_y", &ry), ("res_z", &rz), ("res", &res)] {
+            write_h5(&out, name, a);
+        }
+        write_h5(&out, "x", &x);
+        write_h5(&out, "y", &y);
+        write_h5(&out, "zc", &zc);
+        write_h5(&out, "nu", &Array1::from_elem(1, nu));
+        drop(out);
+
+        let mut cols: HashMap<String, Array1<f64>> = HashMap::new();
+        cols.insert("x".into(), x.clone());
+        cols.insert("q".into(), q);
+        cols.insert("div".into(), profile_rms(&div, 2));
+        cols.insert("res".into(), profile_rms(&res, 2));
+        write_dat(Path::new(&format!("{base}_x.dat")), "x", 1.0 / nu, &cols).expect("failed to write .dat file");
+        let mut cols: HashMap<String, Array1<f64>> = HashMap::new();
+        cols.insert("zc".into(), zc.clone());
+        cols.insert("div".into(), profile_rms(&div, 0));
+        cols.insert("res".into(), profile_rms(&res, 0));
+        write_dat(Path::new(&format!("{base}_z.dat")), "zc", 1.0 / nu, &cols).expect("failed to write .dat file");
+        eprintln!("wrote {h5path}, {base}_x.dat, {base}_z.dat");
+    }
+    if failed > 0 {
+        eprintln!("check: {failed} measure(s) out of tolerance");
+        std::process::exit(1);
+    }
+    eprintln!("check: all measures within tolerance");
+}
+
+// ─── validate sub-command ─────────────────────────────────────────────────────
+
+#[derive(Args)]
+struct ValidateArgs {
+    /// Files or snapshot templates (`{}` is replaced by each variable name,
+    /// as in fik-inst); a template without `{}` holds every variable.
+    #[arg(short, long, num_args = 1.., value_name = "TEMPLATE")]
+    files: Vec<String>,
+    /// Filename pattern with a time placeholder `{t}` (and component `{}`).
+    #[arg(long, value_name = "PATTERN")]
+    pattern: Option<String>,
+    /// Timestep range START END STEP (inclusive) used with --pattern.
+    #[arg(long, num_args = 3, value_names = ["START", "END", "STEP"])]
+    range: Option<Vec<usize>>,
+    /// Variables to scan, comma-separated; coordinates and ν are read from
+    /// the first one's file.
+    #[arg(short, long, value_delimiter = ',', default_value = "u,v,w,p")]
+    variables: Vec<String>,
+    /// Report, one tab-separated line per (file, check): file, check,
+    /// ok/FAIL, detail.
+    #[arg(short, long, default_value = "validate.tsv")]
+    output: String,
+    /// Also write the templates that pass every check, one per line, e.g.
+    /// for `-f $(cat good.txt)` in the ensemble commands.
+    #[arg(long, value_name = "FILE")]
+    good: Option<String>,
+    /// Relative tolerance of the spanwise spacing, the ghost copies and ν.
+    #[arg(long, default_value_t = 1e-8)]
+    tol: f64,
+    /// Also require the streamwise ghost columns to be periodic copies.
+    #[arg(long, default_value_t = false)]
+    periodic_x: bool,
+}
+
+/// Checks of one snapshot template as (check, ok, detail); `nu_ref` is the
+/// ν of the first readable snapshot of the series.
+fn validate_snapshot(template: &str, args: &ValidateArgs, nu_ref: &mut Option<f64>) -> Vec<(String, bool, String)> {
+    let mut rows: Vec<(String, bool, String)> = Vec::new();
+    let read_coord = |h5: &H5File, name: &str| -> Result<Array1<f64>, hdf5::Error> {
+        match h5.coord(name).ok_or_else(|| format!("coord '{name}' not found"))?.read_data::<f64>()? {
+            H5Data::Array(a) => a.into_dimensionality::<Ix1>().map_err(|e| format!("coord '{name}': {e}").into()),
+            H5Data::Scalar(_) => Err(format!("coord '{name}' is scalar").into()),
+        }
+    };
+    let header = H5File::new(&template.replace("{}", &args.variables[0])).and_then(|mut h5| {
+        h5.get_info()?;
+        h5.load_coords()?;
+        let nu = h5.info().nu.ok_or_else(|| hdf5::Error::from("'nu' not in HDF5 file"))?;
+        Ok((read_coord(&h5, "x")?, read_coord(&h5, "y")?, read_coord(&h5, "zc")?, nu))
+    });
+    let (x, y, zc, nu) = match header {
+        Ok(h) => h,
+        Err(e) => {
+            rows.push(("header".into(), false, e.to_string()));
+            return rows;
+        }
+    };
+    for (name, c) in [("x", &x), ("y", &y), ("zc", &zc)] {
+        rows.push((format!("{name}_increasing"), is_increasing(c), format!("n={}", c.len())));
+    }
+    let dev = spacing_deviation(&y);
+    rows.push(("y_uniform".into(), dev <= args.tol, format!("{dev:.3e}")));
+    let nu0 = *nu_ref.get_or_insert(nu);
+    rows.push(("nu".into(), ((nu - nu0) / nu0).abs() <= args.tol, format!("{nu:.6e}")));
+
+    let (nz, ny, nx) = (zc.len(), y.len(), x.len());
+    for var in &args.variables {
+        let path = template.replace("{}", var);
+        let field = H5File::new(&path).and_then(|mut h5| {
+            h5.add_dataset(var, None)?;
+            match h5.dataset(var).unwrap().read_data::<f64>()? {
+                H5Data::Array(a) => Ok(a),
+                H5Data::Scalar(_) => Err(format!("'{var}' is scalar").into()),
+            }
+        });
+        let a = match field {
+            Ok(a) => a,
+            Err(e) => {
+                rows.push((format!("{var}_read"), false, e.to_string()));
+                continue;
+            }
+        };
+        let sh = a.shape();
+        let fits = |n: usize, m: usize| (n..=n + 2).contains(&m);
+        let shape_ok = sh.len() == 3 && sh[0] == nz && fits(ny, sh[1]) && fits(nx, sh[2]);
+        rows.push((format!("{var}_shape"), shape_ok, format!("{sh:?} vs ({nz}, {ny}, {nx})")));
+        if !shape_ok {
+            continue;
+        }
+        let (nan, inf) = count_nonfinite(&a);
+        rows.push((format!("{var}_finite"), nan + inf == 0, format!("nan={nan} inf={inf}")));
+        let mut axes = vec![(1, ny, "y")];
+        if args.periodic_x {
+            axes.push((2, nx, "x"));
+        }
+        for (axis, n, c) in axes {
+            if let Some(m) = ghost_mismatch(&a, axis, n).expect("shape already checked") {
+                rows.push((format!("{var}_ghost_{c}"), m <= args.tol, format!("{m:.3e}")));
+            }
+        }
+    }
+    rows
+}
+
+fn run_validate(args: ValidateArgs) {
+    let templates = snapshot_templates(&args.files, &args.pattern, &args.range);
+    assert!(!args.variables.is_empty(), "--variables is empty");
+    let mut report = String::from("file\tcheck\tstatus\tdetail\n");
+    let mut good: Vec<&String> = Vec::new();
+    let mut nu_ref = None;
+    for t in &templates {
+        let rows = validate_snapshot(t, &args, &mut nu_ref);
+        for (check, ok, detail) in &rows {
+            report.push_str(&format!("{t}\t{check}\t{}\t{detail}\n", if *ok { "ok" } else { "FAIL" }));
+        }
+        let failed: Vec<&str> = rows.iter().filter(|r| !r.1).map(|r| r.0.as_str()).collect();
+        if failed.is_empty() {
+            good.push(t);
+        } else {
+            eprintln!("{t}: FAIL {}", failed.join(", "));
+        }
+    }
+    std::fs::write(&args.output, report).expect("failed to write report");
+    eprintln!("wrote {}", args.output);
+    if let Some(path) = &args.good {
+        let list: String = good.iter().map(|t| format!("{t}\n")).collect();
+        std::fs::write(path, list).expect("failed to write good-file list");
+        eprintln!("wrote {path}");
+    }
+    eprintln!("validate: {} of {} snapshots good", good.len(), templates.len());
+    if good.len() < templates.len() {
+        std::process::exit(1);
+    }
+}
+
+// ─── grid sub-command ─────────────────────────────────────────────────────────
+
+#[derive(Args)]
+struct GridArgs {
+    /// A subavg file or snapshot templates (`{}` is replaced by the
+    /// component); ū is averaged over all of them.
+    #[arg(short, long, num_args = 1.., value_name = "FILE", required = true)]
+    files: Vec<String>,
+    /// Output .dat file along x; the z table goes to <stem>_z.dat.
+    #[arg(short, long, default_value = "grid.dat")]
+    output: String,
+    /// Half-channel height h: the centreline Δz⁺ is taken at z = h.
+    #[arg(long, default_value_t = 1.0)]
+    half_height: f64,
+    /// Largest acceptable Δx⁺.
+    #[arg(long, default_value_t = 15.0)]
+    dx_max: f64,
+    /// Largest acceptable Δy⁺.
+    #[arg(long, default_value_t = 8.0)]
+    dy_max: f64,
+    /// Largest acceptable z⁺ of the first off-wall point.
+    #[arg(long, default_value_t = 1.0)]
+    z1_max: f64,
+    /// Fewest acceptable points below z⁺ = 10.
+    #[arg(long, default_value_t = 5)]
+    n10_min: usize,
+}
+
+fn run_grid(args: GridArgs) {
+    let mut h5 = H5File::new(&inst_path(&args.files[0], 'u')).expect("failed to open HDF5 file");
+    h5.get_info().expect("failed to read DNS info");
+    h5.load_coords().expect("failed to load coordinates");
+    let nu = h5.info().nu.expect("'nu' not in HDF5 file");
+    let load_coord = |h5: &H5File, name: &str| -> Array1<f64> {
+        match h5.coord(name)
+            .unwrap_or_else(|| panic!("coord '{name}' not found"))
+            .read_data::<f64>().unwrap()
+        {
+            H5Data::Array(a) => a.into_dimensionality::<Ix1>().unwrap(),
+            H5Data::Scalar(_) => panic!("coord '{name}' is scalar"),
+        }
+    };
+    let (x, y, zc) = (load_coord(&h5, "x"), load_coord(&h5, "y"), load_coord(&h5, "zc"));
+    let zd = h5.coord("zd").is_some().then(|| load_coord(&h5, "zd"));
+    drop(h5);
+    let nxc = x.len();
+
+    let mut u = ndarray::Array2::<f64>::zeros((zc.len(), nxc));
+    for f in &args.files {
+        let us = avg_axis(&read_inst_field(&inst_path(f, 'u'), "u", nxc), 1).expect("spanwise average failed");
+        u += &us.into_dimensionality::<ndarray::Ix2>().unwrap();
+    }
+    u /= args.files.len() as f64;
+
+    let g = wall_unit_grid(&u, &x, &y, &zc, zd.as_ref(), nu, args.half_height).expect("wall_unit_grid failed");
+    let (sx, sz) = (stretching(&x), stretching(&zc));
+    let max_ratio = |r: &Array1<f64>| r.iter().fold(1.0f64, |m, &v| m.max(v).max(1.0 / v));
+    let mut sorted = g.utau.to_vec();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    let ut_med = sorted[sorted.len() / 2];
+
+    // bit 1: Δx⁺, 2: Δy⁺, 4: z₁⁺, 8: points below z⁺ = 10
+    let flag = Array1::from_shape_fn(nxc, |i| {
+        [
+            g.dx[i] > args.dx_max,
+            g.dy[i] > args.dy_max,
+            g.z1[i] > args.z1_max,
+            g.n10[i] < args.n10_min as f64,
+        ].iter().enumerate().fold(0.0, |b, (k, &hit)| if hit { b + (1 << k) as f64 } else { b })
+    });
+
+    let range = |a: &Array1<f64>| a.iter().fold((f64::INFINITY, f64::NEG_INFINITY), |(l, h), &v| (l.min(v), h.max(v)));
+    eprintln!(
+        "grid ({}, {}, {}): median u_tau = {ut_med:.5e}, max stretching x {:.4}, z {:.4}{}",
+        zc.len(), y.len(), nxc, max_ratio(&sx), max_ratio(&sz),
+        if zd.is_some() { "" } else { "  (no zd: first point from zc)" },
+    );
+    for (name, a, lim) in [
+        ("dx+", &g.dx, format!("≤ {}", args.dx_max)),
+        ("dy+", &g.dy, format!("≤ {}", args.dy_max)),
+        ("dz+ wall", &g.dz_wall, String::new()),
+        ("dz+ centre", &g.dz_center, String::new()),
+        ("z1+", &g.z1, format!("≤ {}", args.z1_max)),
+        ("n(z+≤10)", &g.n10, format!("≥ {}", args.n10_min)),
+    ] {
+        let (lo, hi) = range(a);
+        eprintln!("  {name:<10} {lo:>10.4} .. {hi:<10.4} {lim}");
+    }
+    // contiguous under-resolved x-ranges
+    let mut i = 0;
+    while i < nxc {
+        if flag[i] == 0.0 {
+            i += 1;
+            continue;
+        }
+        let i0 = i;
+        let mut bits = 0u32;
+        while i < nxc && flag[i] != 0.0 {
+            bits |= flag[i] as u32;
+            i += 1;
+        }
+        let reasons: Vec<&str> = ["dx+", "dy+", "z1+", "n10"].iter().enumerate()
+            .filter(|(k, _)| bits & (1 << k) != 0).map(|(_, r)| *r).collect();
+        let peak = g.utau.slice(s![i0..i]).fold(0.0f64, |m, &v| m.max(v));
+        eprintln!(
+            "  under-resolved x ∈ [{:.4}, {:.4}]: {}  (max u_tau / median = {:.3})",
+            x[i0], x[i - 1], reasons.join(", "), peak / ut_med,
+        );
+    }
+
+    let mut cols: HashMap<String, Array1<f64>> = HashMap::new();
+    cols.insert("x".into(), x.clone());
+    cols.insert("utau".into(), g.utau.clone());
+    cols.insert("dxplus".into(), g.dx);
+    cols.insert("dyplus".into(), g.dy);
+    cols.insert("dzplus_wall".into(), g.dz_wall);
+    cols.insert("dzplus_center".into(), g.dz_center);
+    cols.insert("z1plus".into(), g.z1);
+    cols.insert("n10".into(), g.n10);
+    cols.insert("stretch".into(), sx);
+    cols.insert("flag".into(), flag);
+    write_dat(Path::new(&args.output), "x", 1.0 / nu, &cols).expect("failed to write .dat file");
+
+    let stem = args.output.strip_suffix(".dat").unwrap_or(&args.output);
+    let zpath = format!("{stem}_z.dat");
+    let mut cols: HashMap<String, Array1<f64>> = HashMap::new();
+    cols.insert("zc".into(), zc.clone());
+    cols.insert("dz".into(), ndarray::Array1::from_shape_fn(zc.len(), |k| if k == 0 { zc[0] } else { zc[k] - zc[k - 1] }));
+    cols.insert("stretch".into(), sz);
+    cols.insert("zplus".into(), zc.mapv(|z| z * ut_med / nu));
+    write_dat(Path::new(&zpath), "zc", 1.0 / nu, &cols).expect("failed to write .dat file");
+    eprintln!("wrote {}, {zpath}", args.output);
+}
+
+// ─── Helpers ──────────────────────────────────────────────────────────────────
+
+/// Parse `--xwindows` pairs of physical x into half-open index ranges of the
+/// grid points inside each window; `None` selects the whole domain.
+fn x_windows(pairs: &Option<Vec<f64>>, x: &Array1<f64>) -> Vec<(usize, usize)> {
+    let nx = x.len();
+    let Some(e) = pairs else { return vec![(0, nx)] };
+    assert!(!e.is_empty() && e.len() % 2 == 0, "--xwindows needs pairs of x positions");
+    e.chunks(2)
+        .map(|w| {
+            let i0 = x.iter().position(|&v| v >= w[0]).unwrap_or(nx);
+            let i1 = x.iter().position(|&v| v >= w[1]).unwrap_or(nx);
+            assert!(i0 < i1, "--xwindows [{}, {}) contains no grid points", w[0], w[1]);
+            (i0, i1)
+        })
+        .collect()
+}
+
+/// First and last x of each window, shape (nwin, 2).
+fn window_bounds(windows: &[(usize, usize)], x: &Array1<f64>) -> ndarray::Array2<f64> {
+    ndarray::Array2::from_shape_fn((windows.len(), 2), |(n, e)| {
+        if e == 0 { x[windows[n].0] } else { x[windows[n].1 - 1] }
+    })
+}
+
+fn dire_to_axis(dire: &str) -> usize {
+    match dire.to_lowercase().as_str() {
+        "z" => 0,
+        "y" => 1,
+        "x" => 2,
+        d   => panic!("Unknown direction '{}' – expected x, y, or z", d),
+    }
+}
+
+fn build_block(args: &BlockArgs) -> Option<Block> {
+    if args.blockx.is_none() && args.blocky.is_none() && args.blockz.is_none() {
+        return None;
+    }
+    let bv = |v: &Vec<usize>| BlockValue::new([v[0], v[1], v[2], v[3]]).expect("invalid block");
+    let bz = args.blockz.as_ref().map(bv);
+    let by = args.blocky.as_ref().map(bv);
+    let bx = args.blockx.as_ref().map(bv);
+    Some(Block::new(vec![bz, by, bx]))
+}
+
+/// Default [start, stride, count] = [0, 1, full_extent].
+fn view_block(arg: &Option<Vec<usize>>, full: usize) -> [usize; 3] {
+    match arg {
+        Some(v) => [v[0], v[1], v[2]],
+        None    => [0, 1, full],
+    }
+}
+
+/// Quantiles of a field estimated from every 16th finite point: the
+/// `fractions` of the upper tail, or of the lower tail when `lower_tail`
+/// (used to suggest isosurface levels).  NaN when no sample is finite.
+fn tail_quantiles(field: &ArrayD<f64>, fractions: &[f64], lower_tail: bool) -> Vec<f64> {
+    let mut sample: Vec<f64> = field.iter().step_by(16).copied().filter(|v| v.is_finite()).collect();
+    if sample.is_empty() {
+        return vec![f64::NAN; fractions.len()];
     }
+    sample.sort_by(f64::total_cmp);
+    fractions
+        .iter()
+        .map(|&f| {
+            let frac = if lower_tail { f } else { 1.0 - f };
+            sample[((sample.len() - 1) as f64 * frac) as usize]
+        })
+        .collect()
+}
+
+/// Write one array as a dataset of an output HDF5 file.
+fn write_h5<D: ndarray::Dimension>(out: &hdf5::File, name: &str, a: &ndarray::Array<f64, D>) {
+    out.new_dataset_builder()
+        .with_data(a.view())
+        .create(name)
+        .unwrap_or_else(|e| panic!("write '{name}': {e}"));
 }
 
 fn find_coord(h5: &H5File, axis: char) -> Option<String> {
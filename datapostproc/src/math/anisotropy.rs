@@ -0,0 +1,248 @@
+//! Reynolds-stress anisotropy: invariants, principal axes, Lumley-triangle
+//! and barycentric-map coordinates.
+//!
+//! Coordinate convention (matches the rest of this crate): indices 1/2/3 are
+//! x/y/z, i.e. u (streamwise), v (spanwise), w (wall-normal).
+//!
+//! From the Reynolds stresses of [`TkeFields`]:
+//!
+//!   b_ij = ⟨u_i'u_j'⟩ / (2k) − δ_ij/3,
+//!   II = −½ b_ij b_ji,   III = ⅓ b_ij b_jk b_ki,
+//!
+//! Lumley-triangle coordinates (Pope 2000, §11.3)
+//!
+//!   η = (−II/3)^{1/2},   ξ = (III/2)^{1/3},
+//!
+//! and, with the eigenvalues λ₁ ≥ λ₂ ≥ λ₃ of b, the barycentric weights
+//! (Banerjee et al. 2007)
+//!
+//!   C₁c = λ₁ − λ₂,   C₂c = 2(λ₂ − λ₃),   C₃c = 3λ₃ + 1,   ΣC = 1,
+//!
+//! placed in the triangle with one-component (1, 0), two-component (0, 0)
+//! and isotropic (½, √3/2) corners:
+//!
+//!   x_B = C₁c + ½ C₃c,   y_B = (√3/2) C₃c.
+//!
+//! Points with k ≤ 0 (no turbulence) get NaN.
+
+use hdf5::Error;
+use ndarray::ArrayD;
+
+use super::tke::TkeFields;
+
+/// Anisotropy fields, each of the input stress shape.
+pub struct AnisotropyFields {
+    /// b_ij for (ij) = 11, 22, 33, 12, 13, 23.
+    pub b: [ArrayD<f64>; 6],
+    /// Second and third invariants II, III.
+    pub ii: ArrayD<f64>,
+    pub iii: ArrayD<f64>,
+    /// Lumley-triangle coordinates.
+    pub xi: ArrayD<f64>,
+    pub eta: ArrayD<f64>,
+    /// Eigenvalues λ₁ ≥ λ₂ ≥ λ₃.
+    pub eigval: [ArrayD<f64>; 3],
+    /// eigvec[n][c]: component c (x, y, z) of the unit eigenvector of λ_n,
+    /// sign fixed so that its largest component is positive.
+    pub eigvec: [[ArrayD<f64>; 3]; 3],
+    /// Barycentric weights C₁c, C₂c, C₃c.
+    pub weights: [ArrayD<f64>; 3],
+    /// Barycentric-map coordinates x_B, y_B.
+    pub xb: ArrayD<f64>,
+    pub yb: ArrayD<f64>,
+}
+
+/// Anisotropy tensor, invariants, principal axes and map coordinates at
+/// every point of the stresses in `tf`.
+pub fn anisotropy(tf: &TkeFields) -> Result<AnisotropyFields, Error> {
+    let stresses = [&tf.uu, &tf.vv, &tf.ww, &tf.uv, &tf.uw, &tf.vw];
+    let shape = tf.uu.raw_dim();
+    if stresses.iter().any(|s| s.raw_dim() != shape) {
+        return Err("anisotropy: Reynolds stresses differ in shape".into());
+    }
+    let zeros = || ArrayD::<f64>::zeros(shape.clone());
+    let mut out = AnisotropyFields {
+        b: std::array::from_fn(|_| zeros()),
+        ii: zeros(),
+        iii: zeros(),
+        xi: zeros(),
+        eta: zeros(),
+        eigval: std::array::from_fn(|_| zeros()),
+        eigvec: std::array::from_fn(|_| std::array::from_fn(|_| zeros())),
+        weights: std::array::from_fn(|_| zeros()),
+        xb: zeros(),
+        yb: zeros(),
+    };
+
+    let h = 3f64.sqrt() / 2.0;
+    for (idx, &uu) in tf.uu.indexed_iter() {
+        let (vv, ww) = (tf.vv[&idx], tf.ww[&idx]);
+        let (uv, uw, vw) = (tf.uv[&idx], tf.uw[&idx], tf.vw[&idx]);
+        let k2 = uu + vv + ww;
+        if k2.is_nan() || k2 <= 0.0 {
+            for a in out.b.iter_mut().chain(out.eigval.iter_mut()).chain(out.weights.iter_mut()) {
+                a[&idx] = f64::NAN;
+            }
+            for a in out.eigvec.iter_mut().flatten() {
+                a[&idx] = f64::NAN;
+            }
+            for a in [&mut out.ii, &mut out.iii, &mut out.xi, &mut out.eta, &mut out.xb, &mut out.yb] {
+                a[&idx] = f64::NAN;
+            }
+            continue;
+        }
+        let b = [
+            [uu / k2 - 1.0 / 3.0, uv / k2, uw / k2],
+            [uv / k2, vv / k2 - 1.0 / 3.0, vw / k2],
+            [uw / k2, vw / k2, ww / k2 - 1.0 / 3.0],
+        ];
+        for (c, &(i, j)) in [(0, 0), (1, 1), (2, 2), (0, 1), (0, 2), (1, 2)].iter().enumerate() {
+            out.b[c][&idx] = b[i][j];
+        }
+
+        let (lam, vec) = symmetric_eigen(b);
+        // invariants from the eigenvalues (b symmetric)
+        let ii = -0.5 * lam.iter().map(|l| l * l).sum::<f64>();
+        let iii = lam.iter().map(|l| l * l * l).sum::<f64>() / 3.0;
+        out.ii[&idx] = ii;
+        out.iii[&idx] = iii;
+        out.eta[&idx] = (-ii / 3.0).max(0.0).sqrt();
+        out.xi[&idx] = (iii / 2.0).cbrt();
+
+        let c = [lam[0] - lam[1], 2.0 * (lam[1] - lam[2]), 3.0 * lam[2] + 1.0];
+        for n in 0..3 {
+            out.eigval[n][&idx] = lam[n];
+            out.weights[n][&idx] = c[n];
+            for (d, &e) in vec[n].iter().enumerate() {
+                out.eigvec[n][d][&idx] = e;
+            }
+        }
+        out.xb[&idx] = c[0] + 0.5 * c[2];
+        out.yb[&idx] = h * c[2];
+    }
+    Ok(out)
+}
+
+/// Eigen-decomposition of a symmetric 3×3 matrix by cyclic Jacobi rotations.
+/// Returns the eigenvalues in descending order and the matching unit
+/// eigenvectors (`vec[n]` belongs to `val[n]`), each with its largest
+/// component positive.
+pub fn symmetric_eigen(a: [[f64; 3]; 3]) -> ([f64; 3], [[f64; 3]; 3]) {
+    let mut m = a;
+    // columns of v are the eigenvectors
+    let mut v = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+    let scale = m.iter().flatten().map(|x| x.abs()).fold(0.0, f64::max);
+
+    for _sweep in 0..50 {
+        let off = m[0][1].abs() + m[0][2].abs() + m[1][2].abs();
+        if off <= 1e-15 * scale || off == 0.0 {
+            break;
+        }
+        for (p, q) in [(0, 1), (0, 2), (1, 2)] {
+            if m[p][q].abs() <= 1e-300 {
+                continue;
+            }
+            let theta = (m[q][q] - m[p][p]) / (2.0 * m[p][q]);
+            let t = theta.signum() / (theta.abs() + (theta * theta + 1.0).sqrt());
+            let c = 1.0 / (t * t + 1.0).sqrt();
+            let s = t * c;
+            // m ← Jᵀ m J with the rotation in the (p, q) plane
+            let r = 3 - p - q;
+            let (mpp, mqq, mpq) = (m[p][p], m[q][q], m[p][q]);
+            let (mrp, mrq) = (m[r][p], m[r][q]);
+            m[p][p] = mpp - t * mpq;
+            m[q][q] = mqq + t * mpq;
+            m[p][q] = 0.0;
+            m[q][p] = 0.0;
+            m[r][p] = c * mrp - s * mrq;
+            m[p][r] = m[r][p];
+            m[r][q] = s * mrp + c * mrq;
+            m[q][r] = m[r][q];
+            for row in v.iter_mut() {
+                let (vp, vq) = (row[p], row[q]);
+                row[p] = c * vp - s * vq;
+                row[q] = s * vp + c * vq;
+            }
+        }
+    }
+
+    let mut order = [0, 1, 2];
+    order.sort_by(|&i, &j| m[j][j].partial_cmp(&m[i][i]).unwrap_or(std::cmp::Ordering::Equal));
+    let val = order.map(|n| m[n][n]);
+    let vec = order.map(|n| {
+        let e = [v[0][n], v[1][n], v[2][n]];
+        let big = e.iter().cloned().fold(0.0, |a: f64, b| if b.abs() > a.abs() { b } else { a });
+        if big < 0.0 { e.map(|x| -x) } else { e }
+    });
+    (val, vec)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::Array1;
+
+    fn stresses(rows: &[[f64; 6]]) -> TkeFields {
+        let col = |c: usize| Array1::from_iter(rows.iter().map(|r| r[c])).into_dyn();
+        let (uu, vv, ww) = (col(0), col(1), col(2));
+        let tke = (&(&uu + &vv) + &ww) * 0.5;
+        TkeFields { uu, vv, ww, uv: col(3), uw: col(4), vw: col(5), tke }
+    }
+
+    #[test]
+    fn limiting_states_on_the_maps() {
+        let tf = stresses(&[
+            [1.0, 1.0, 1.0, 0.0, 0.0, 0.0], // isotropic
+            [2.0, 0.0, 0.0, 0.0, 0.0, 0.0], // one-component
+            [1.0, 1.0, 0.0, 0.0, 0.0, 0.0], // two-component axisymmetric
+            [0.0, 0.0, 0.0, 0.0, 0.0, 0.0], // laminar
+        ]);
+        let a = anisotropy(&tf).unwrap();
+        let h = 3f64.sqrt() / 2.0;
+        let close = |p: f64, q: f64| (p - q).abs() < 1e-12;
+
+        assert!(close(a.xb[[0]], 0.5) && close(a.yb[[0]], h));
+        assert!(close(a.eta[[0]], 0.0) && close(a.xi[[0]], 0.0));
+
+        assert!(close(a.xb[[1]], 1.0) && close(a.yb[[1]], 0.0));
+        // 1C: b = diag(2/3, −1/3, −1/3) → η = ξ = 1/3
+        assert!(close(a.eta[[1]], 1.0 / 3.0) && close(a.xi[[1]], 1.0 / 3.0));
+        assert!(close(a.eigvec[0][0][[1]], 1.0));
+
+        assert!(close(a.xb[[2]], 0.0) && close(a.yb[[2]], 0.0));
+        // 2C axisymmetric: b = diag(1/6, 1/6, −1/3) → ξ = −1/6, η = 1/6
+        assert!(close(a.xi[[2]], -1.0 / 6.0) && close(a.eta[[2]], 1.0 / 6.0));
+
+        assert!(a.xb[[3]].is_nan() && a.eigval[0][[3]].is_nan());
+        for n in 0..3 {
+            let s: f64 = a.weights.iter().map(|w| w[[n]]).sum();
+            assert!(close(s, 1.0));
+        }
+    }
+
+    #[test]
+    fn jacobi_matches_rotated_diagonal() {
+        // R diag(3, 1, −2) Rᵀ with R a rotation about (1, 1, 1)/√3 by 40°
+        let (th, n) = (40f64.to_radians(), 1.0 / 3f64.sqrt());
+        let (c, s) = (th.cos(), th.sin());
+        let r = [
+            [c + n * n * (1.0 - c), n * n * (1.0 - c) - n * s, n * n * (1.0 - c) + n * s],
+            [n * n * (1.0 - c) + n * s, c + n * n * (1.0 - c), n * n * (1.0 - c) - n * s],
+            [n * n * (1.0 - c) - n * s, n * n * (1.0 - c) + n * s, c + n * n * (1.0 - c)],
+        ];
+        let d = [3.0, 1.0, -2.0];
+        let a: [[f64; 3]; 3] = std::array::from_fn(|i| {
+            std::array::from_fn(|j| (0..3).map(|k| r[i][k] * d[k] * r[j][k]).sum())
+        });
+        let (val, vec) = symmetric_eigen(a);
+        for n in 0..3 {
+            assert!((val[n] - d[n]).abs() < 1e-12, "{val:?}");
+            // A e = λ e
+            let e = vec[n];
+            for i in 0..3 {
+                let ae: f64 = (0..3).map(|j| a[i][j] * e[j]).sum();
+                assert!((ae - val[n] * e[i]).abs() < 1e-12);
+            }
+        }
+    }
+}
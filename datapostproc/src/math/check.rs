@@ -0,0 +1,134 @@
+//! Conservation checks of a stored flow field: continuity, momentum and
+//! mass flux.
+//!
+//! In a channel the bulk flow rate through every cross-section,
+//!
+//!   Q(x) = ∫∫ u dy dz,
+//!
+//! is the same; its relative spread (max Q − min Q) / |⟨Q⟩| measures lost or
+//! gained mass along x.  The y integral is a periodic sum, the z integral a
+//! trapezoid over [0, zc.., 2h] closed by no-slip segments to both walls when
+//! the first or last point is off the wall.
+//!
+//! The divergence and momentum residual fields come from [`crate::math::ns`];
+//! the profiles here reduce them to their x and z distributions, and
+//! [`Check`] compares a scalar against its tolerance.
+
+use hdf5::Error;
+use ndarray::{Array1, ArrayD, Axis};
+
+/// Bulk flow rate Q(x) of a (nz, ny, nx) streamwise velocity in a channel
+/// of half-height `h`.
+pub fn bulk_flow_rate(u: &ArrayD<f64>, y: &Array1<f64>, zc: &Array1<f64>, h: f64) -> Result<Array1<f64>, Error> {
+    if u.ndim() != 3 || u.shape()[0] != zc.len() || u.shape()[1] != y.len() {
+        return Err(format!(
+            "bulk_flow_rate: u shape {:?} does not match (nz = {}, ny = {}, nx)",
+            u.shape(), zc.len(), y.len()
+        ).into());
+    }
+    if y.len() < 2 {
+        return Err("bulk_flow_rate: need at least 2 spanwise points".into());
+    }
+    let nz = zc.len();
+    if zc[nz - 1] > 2.0 * h {
+        return Err(format!("bulk_flow_rate: zc extends beyond 2h = {}", 2.0 * h).into());
+    }
+    // periodic y: every point carries the uniform spacing
+    let dy = y[1] - y[0];
+    let plane = u.sum_axis(Axis(1)) * dy;
+    let (bottom, top) = (plane.index_axis(Axis(0), 0), plane.index_axis(Axis(0), nz - 1));
+    let mut q = &bottom * (0.5 * zc[0]) + &top * (0.5 * (2.0 * h - zc[nz - 1]));
+    for k in 1..nz {
+        let dz = zc[k] - zc[k - 1];
+        q += &((&plane.index_axis(Axis(0), k) + &plane.index_axis(Axis(0), k - 1)) * (0.5 * dz));
+    }
+    Ok(q.into_dimensionality().expect("Q(x) is 1-D"))
+}
+
+/// Relative spread (max − min) / |mean| of a profile.
+pub fn relative_spread(q: &Array1<f64>) -> f64 {
+    let (lo, hi) = q.iter().fold((f64::INFINITY, f64::NEG_INFINITY), |(l, h), &v| (l.min(v), h.max(v)));
+    (hi - lo) / q.mean().unwrap_or(f64::NAN).abs()
+}
+
+/// RMS over all axes but `axis`, ignoring NaN: the z (`axis` 0) or x
+/// (`axis` 2) distribution of a (nz, ny, nx) field.
+pub fn profile_rms(a: &ArrayD<f64>, axis: usize) -> Array1<f64> {
+    Array1::from_shape_fn(a.shape()[axis], |k| rms(&a.index_axis(Axis(axis), k).to_owned()))
+}
+
+/// RMS over the whole field, ignoring NaN (points a stencil does not reach).
+pub fn rms(a: &ArrayD<f64>) -> f64 {
+    let (s, n) = a.iter().filter(|v| !v.is_nan()).fold((0.0, 0usize), |(s, n), v| (s + v * v, n + 1));
+    (s / n as f64).sqrt()
+}
+
+/// One conservation measure and its tolerance.
+#[derive(Clone, Debug)]
+pub struct Check {
+    pub name: &'static str,
+    pub value: f64,
+    pub tol: f64,
+}
+
+impl Check {
+    /// `value ≤ tol`; a NaN value fails.
+    pub fn passed(&self) -> bool {
+        self.value <= self.tol
+    }
+}
+
+// ─── Tests ────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::Array3;
+
+    #[test]
+    fn flow_rate_of_linear_profile() {
+        // u = z on z ∈ [0, 2] sampled without the wall point: ∫ z dz = 2 per
+        // unit span, span 4 × 0.5 = 2, times the streamwise scale (1 + i)
+        let zc = Array1::linspace(0.25, 2.0, 8);
+        let y = Array1::from_shape_fn(4, |j| 0.5 * j as f64);
+        let u = Array3::from_shape_fn((8, 4, 3), |(k, _, i)| zc[k] * (1.0 + i as f64)).into_dyn();
+        let q = bulk_flow_rate(&u, &y, &zc, 1.0).unwrap();
+        for (i, &v) in q.iter().enumerate() {
+            assert!((v - 4.0 * (1.0 + i as f64)).abs() < 1e-12, "{i}: {v}");
+        }
+        assert!((relative_spread(&q) - 1.0).abs() < 1e-12);
+        assert!(bulk_flow_rate(&u, &y.slice(ndarray::s![..3]).to_owned(), &zc, 1.0).is_err());
+        assert!(bulk_flow_rate(&u, &y, &zc, 0.9).is_err());
+    }
+
+    #[test]
+    fn flow_rate_closes_both_walls() {
+        // u = z(2 − z) at cell centres off both walls: ∫₀² u dz = 4/3 per unit
+        // span, less the trapezoid error O(Δz²) of the parabola
+        let nz = 400;
+        let dz = 2.0 / nz as f64;
+        let zc = Array1::from_shape_fn(nz, |k| (k as f64 + 0.5) * dz);
+        let y = Array1::from_shape_fn(4, |j| 0.5 * j as f64);
+        let u = Array3::from_shape_fn((nz, 4, 2), |(k, _, _)| zc[k] * (2.0 - zc[k])).into_dyn();
+        let q = bulk_flow_rate(&u, &y, &zc, 1.0).unwrap();
+        let exact = 2.0 * 4.0 / 3.0;
+        assert!(q.iter().all(|&v| (v - exact).abs() < 2.0 * dz * dz), "{q}");
+        // dropping the upper segment would lose ≈ Δz²/4 of each unit span
+        let top = u.index_axis(Axis(0), nz - 1).sum_axis(Axis(0)) * 0.5;
+        let open = &q - &(top * (0.5 * 0.5 * dz));
+        assert!(open.iter().all(|&v| (v - exact).abs() > 0.2 * dz * dz));
+    }
+
+    #[test]
+    fn profiles_and_tolerances() {
+        let a = Array3::from_shape_fn((3, 2, 4), |(k, j, _)| if j == 0 { k as f64 } else { -(k as f64) }).into_dyn();
+        let z = profile_rms(&a, 0);
+        assert!(z.iter().enumerate().all(|(k, &v)| (v - k as f64).abs() < 1e-12));
+        assert_eq!(profile_rms(&a, 2).len(), 4);
+        let mut b = a.clone();
+        b[[0, 0, 0]] = f64::NAN;
+        assert!((rms(&b) - rms(&a) * (24.0f64 / 23.0).sqrt()).abs() < 1e-12);
+        assert!(Check { name: "q", value: 1e-4, tol: 1e-3 }.passed());
+        assert!(!Check { name: "q", value: f64::NAN, tol: 1e-3 }.passed());
+    }
+}
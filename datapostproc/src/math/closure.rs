@@ -0,0 +1,181 @@
+//! Closure of a skin-friction decomposition against the directly measured
+//! C_f, for sensitivity sweeps over the numerical choices.
+//!
+//! Over an x-index region, with r(x) = C_f,total(x) − C_f,direct(x):
+//!
+//!   L2    = ‖r‖₂ / ‖C_f,direct‖₂
+//!   L∞    = max|r| / ⟨|C_f,direct|⟩
+//!   mean  = ⟨r⟩ / ⟨C_f,direct⟩                 (signed bias)
+//!
+//! The bias ⟨r⟩ cannot be attributed to a term: an offset is equally
+//! aligned with every x-constant term (e.g. the FIK laminar 6/Re_b), exact
+//! or not.  The x-varying part r − ⟨r⟩ is attributed to the term whose own
+//! x variation correlates best with it, i.e. the largest
+//! |corr(r, t_k)| = |Σ r̃ t̃_k| / (‖r̃‖ ‖t̃_k‖) with ã = a − ⟨a⟩ over the
+//! region; x-constant terms take no part.  A mismatch that follows one term
+//! (e.g. a streamwise-derivative term near the inflow) points at that term's
+//! discretization rather than at C_f,direct.
+//!
+//! C_f,direct = 2ν ∂ū/∂z|_wall depends on the wall-gradient stencil, see
+//! [`WallStencil`].
+
+use hdf5::Error;
+use ndarray::{s, Array1, ArrayD};
+
+/// Wall-gradient estimate of the direct C_f from the span-averaged (nz, nx) ū.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WallStencil {
+    /// 2ν ū₁/z₁ at the second cell (the `fik`/`rd` convention).
+    SecondCell,
+    /// 2ν ū₀/z₀ at the first cell.
+    FirstCell,
+    /// Second-order one-sided fit through the wall (ū = 0) and the first two
+    /// cells: ∂ū/∂z|₀ = (ū₀ z₁² − ū₁ z₀²) / (z₀ z₁ (z₁ − z₀)).
+    Quadratic,
+}
+
+impl WallStencil {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "cell1" => Some(Self::SecondCell),
+            "cell0" => Some(Self::FirstCell),
+            "quad" => Some(Self::Quadratic),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::SecondCell => "cell1",
+            Self::FirstCell => "cell0",
+            Self::Quadratic => "quad",
+        }
+    }
+
+    /// C_f,direct(x) from the span-averaged (nz, nx) ū and the wall distances
+    /// `zc` (no wall point).
+    pub fn cf_direct(&self, u: &ArrayD<f64>, zc: &Array1<f64>, nu: f64) -> Result<Array1<f64>, Error> {
+        if u.ndim() != 2 || u.shape()[0] != zc.len() || zc.len() < 2 {
+            return Err(format!("cf_direct: ū shape {:?} is not (nz ≥ 2, nx) with nz = {}", u.shape(), zc.len()).into());
+        }
+        let (u0, u1) = (u.slice(s![0, ..]), u.slice(s![1, ..]));
+        let (z0, z1) = (zc[0], zc[1]);
+        let dudz = match self {
+            Self::SecondCell => u1.mapv(|v| v / z1),
+            Self::FirstCell => u0.mapv(|v| v / z0),
+            Self::Quadratic => (&u0 * (z1 * z1) - &u1 * (z0 * z0)) / (z0 * z1 * (z1 - z0)),
+        };
+        Ok(dudz * (2.0 * nu))
+    }
+}
+
+/// Closure metrics of one decomposition over one region.
+#[derive(Clone, Copy, Debug)]
+pub struct ClosureMetrics {
+    pub l2: f64,
+    pub linf: f64,
+    pub mean: f64,
+    /// Index of the term whose x variation correlates best with that of the
+    /// residual, and |corr|; `None` when the residual or every term is
+    /// constant over the region.
+    pub dominant: Option<usize>,
+    pub alignment: f64,
+}
+
+/// Metrics of `cf_total` against `cf_direct` over `i0..i1`, with the
+/// decomposition's individual `terms` for the residual attribution.
+pub fn closure_metrics(
+    cf_total: &Array1<f64>,
+    cf_direct: &Array1<f64>,
+    terms: &[Array1<f64>],
+    (i0, i1): (usize, usize),
+) -> Result<ClosureMetrics, Error> {
+    let n = cf_direct.len();
+    if cf_total.len() != n || terms.iter().any(|t| t.len() != n) {
+        return Err("closure_metrics: profiles differ in length".into());
+    }
+    if i0 >= i1 || i1 > n {
+        return Err(format!("closure_metrics: empty region {i0}..{i1} (nx = {n})").into());
+    }
+    let r = cf_total.slice(s![i0..i1]).to_owned() - cf_direct.slice(s![i0..i1]);
+    let d = cf_direct.slice(s![i0..i1]);
+    let m = (i1 - i0) as f64;
+    let norm = |a: &Array1<f64>| a.iter().map(|v| v * v).sum::<f64>().sqrt();
+    let rn = norm(&r);
+    // x-varying parts; a part below round-off of the whole is constant
+    let centred = |a: &Array1<f64>| {
+        let c = a - a.mean().unwrap();
+        if norm(&c) <= 1e-9 * norm(a) { None } else { Some(c) }
+    };
+
+    let (mut dominant, mut alignment) = (None, 0.0);
+    if let Some(rc) = centred(&r) {
+        let rcn = norm(&rc);
+        for (k, t) in terms.iter().enumerate() {
+            let Some(tc) = centred(&t.slice(s![i0..i1]).to_owned()) else { continue };
+            let c = (rc.iter().zip(tc.iter()).map(|(a, b)| a * b).sum::<f64>() / (rcn * norm(&tc))).abs();
+            if c > alignment {
+                (dominant, alignment) = (Some(k), c);
+            }
+        }
+    }
+    Ok(ClosureMetrics {
+        l2: rn / norm(&d.to_owned()),
+        linf: r.iter().fold(0.0f64, |a, v| a.max(v.abs())) / (d.iter().map(|v| v.abs()).sum::<f64>() / m),
+        mean: r.sum() / d.sum(),
+        dominant,
+        alignment,
+    })
+}
+
+// ─── Tests ────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::Array2;
+
+    #[test]
+    fn stencils_on_quadratic_profile() {
+        // ū = a z − b z²: exact ∂ū/∂z|₀ = a only for the quadratic fit
+        let (a, b, nu) = (3.0, 20.0, 1e-3);
+        let zc = Array1::from_vec(vec![0.01, 0.025, 0.05]);
+        let u = Array2::from_shape_fn((3, 4), |(k, _)| a * zc[k] - b * zc[k] * zc[k]).into_dyn();
+        let q = WallStencil::Quadratic.cf_direct(&u, &zc, nu).unwrap();
+        assert!(q.iter().all(|&c| (c - 2.0 * nu * a).abs() < 1e-12));
+        let c0 = WallStencil::FirstCell.cf_direct(&u, &zc, nu).unwrap();
+        let c1 = WallStencil::SecondCell.cf_direct(&u, &zc, nu).unwrap();
+        assert!((c0[0] - 2.0 * nu * (a - b * 0.01)).abs() < 1e-12);
+        assert!(c1[0] < c0[0] && c0[0] < q[0]);
+        assert_eq!(WallStencil::from_name("quad"), Some(WallStencil::Quadratic));
+    }
+
+    #[test]
+    fn metrics_and_dominant_term() {
+        let n = 40;
+        let x = Array1::linspace(0.0, 1.0, n);
+        let direct = Array1::from_elem(n, 8e-3);
+        let smooth = Array1::from_elem(n, 8e-3);
+        // residual follows a decaying inflow term
+        let inflow = x.mapv(|v: f64| 1e-3 * (-10.0 * v).exp());
+        let total = &smooth + &inflow;
+        let terms = [smooth.clone(), x.mapv(|v: f64| 1e-4 * v), inflow.clone()];
+        let m = closure_metrics(&total, &direct, &terms, (0, n)).unwrap();
+        assert_eq!(m.dominant, Some(2));
+        assert!((m.alignment - 1.0).abs() < 1e-12);
+        assert!((m.linf - 1e-3 / 8e-3).abs() < 1e-12);
+        assert!((m.mean - inflow.sum() / (8e-3 * n as f64)).abs() < 1e-12);
+        // downstream the inflow term has decayed
+        let far = closure_metrics(&total, &direct, &terms, (30, n)).unwrap();
+        assert!(far.l2 < 1e-4 && m.l2 > far.l2);
+        assert!(closure_metrics(&total, &direct, &terms, (5, 5)).is_err());
+        // a pure offset is not blamed on the constant term...
+        let offset = &total + 5e-4;
+        let m = closure_metrics(&offset, &direct, &terms[..1], (0, n)).unwrap();
+        assert_eq!(m.dominant, None);
+        // ...nor does it hide the varying term under it
+        let m = closure_metrics(&offset, &direct, &terms, (0, n)).unwrap();
+        assert_eq!(m.dominant, Some(2));
+        assert!((m.alignment - 1.0).abs() < 1e-12 && (m.mean - (inflow.sum() / n as f64 + 5e-4) / 8e-3).abs() < 1e-12);
+    }
+}
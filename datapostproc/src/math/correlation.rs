@@ -0,0 +1,355 @@
+//! Two-point correlations in the streamwise / wall-normal (x–z) plane.
+//!
+//! Coordinate convention (matches the rest of this crate):
+//!   Array shape (nz, ny, nx): axis 0 = wall-normal z, axis 1 = spanwise y,
+//!   axis 2 = streamwise x.
+//!
+//! For a reference point (x_r, z_r) the correlation coefficient over the whole
+//! x–z plane is
+//!
+//!   R(x, z) = ⟨u'(x_r, y, z_r) u'(x, y, z)⟩ / (σ(x_r, z_r) σ(x, z))
+//!
+//! with ⟨·⟩ the average over the spanwise direction and the snapshots (the
+//! spanwise-homogeneous directions of a spatially developing flow), and
+//! u' = u − ū(x, z) the fluctuation about the span + ensemble mean.  The
+//! moments are accumulated in a single pass as totals and the means removed at
+//! the end (⟨ab⟩ − ā b̄), like the `*-inst` sub-commands.
+//!
+//! At the reference point the spanwise correlation R(Δy) is accumulated as
+//! well (periodic shift along y), so integral length scales are available in
+//! all three directions.
+
+use hdf5::Error;
+use ndarray::{Array1, Array2, ArrayD, ArrayView1, ArrayView2, Axis};
+
+/// Accumulator for x–z two-point correlations about a set of reference
+/// points: feed it snapshots with [`accumulate`], then read out the
+/// correlation maps with [`correlation`].
+///
+/// [`accumulate`]: PlaneCorrelation::accumulate
+/// [`correlation`]: PlaneCorrelation::correlation
+pub struct PlaneCorrelation {
+    nz: usize,
+    ny: usize,
+    nx: usize,
+    /// Reference points as grid indices (iz, ix).
+    refs: Vec<(usize, usize)>,
+    /// Σ u and Σ u² over y and snapshots, shape (nz, nx).
+    sum: Array2<f64>,
+    sum2: Array2<f64>,
+    /// Per reference: Σ u_ref(y) u(z, y, x), shape (nz, nx).
+    cross: Vec<Array2<f64>>,
+    /// Per reference: Σ u_ref(y) u_ref(y + j Δy), j = 0..ny.
+    span: Vec<Array1<f64>>,
+    /// Number of y-lines accumulated (ny × snapshots).
+    lines: usize,
+}
+
+impl PlaneCorrelation {
+    /// `refs` — reference points as (iz, ix) grid indices.
+    pub fn new(nz: usize, ny: usize, nx: usize, refs: &[(usize, usize)]) -> Result<Self, Error> {
+        if refs.is_empty() {
+            return Err("no reference points given".into());
+        }
+        for &(iz, ix) in refs {
+            if iz >= nz || ix >= nx {
+                return Err(format!(
+                    "reference point (iz={iz}, ix={ix}) outside the ({nz}, {nx}) plane"
+                )
+                .into());
+            }
+        }
+        Ok(Self {
+            nz,
+            ny,
+            nx,
+            refs: refs.to_vec(),
+            sum: Array2::zeros((nz, nx)),
+            sum2: Array2::zeros((nz, nx)),
+            cross: vec![Array2::zeros((nz, nx)); refs.len()],
+            span: vec![Array1::zeros(ny); refs.len()],
+            lines: 0,
+        })
+    }
+
+    /// Add one snapshot, shape `(nz, ny, nx)`.
+    pub fn accumulate(&mut self, u: &ArrayD<f64>) -> Result<(), Error> {
+        let s = u.shape();
+        if s != [self.nz, self.ny, self.nx] {
+            return Err(format!(
+                "snapshot shape {s:?} incompatible with ({}, {}, {})",
+                self.nz, self.ny, self.nx
+            )
+            .into());
+        }
+        let u3 = u.view().into_dimensionality::<ndarray::Ix3>().unwrap();
+        self.sum += &u3.sum_axis(Axis(1));
+        self.sum2 += &u3.mapv(|v| v * v).sum_axis(Axis(1));
+
+        for (k, &(iz, ix)) in self.refs.iter().enumerate() {
+            let uref = u3.slice(ndarray::s![iz, .., ix]);
+            for (j, &ur) in uref.iter().enumerate() {
+                self.cross[k].scaled_add(ur, &u3.index_axis(Axis(1), j));
+            }
+            for shift in 0..self.ny {
+                let mut acc = 0.0;
+                for j in 0..self.ny {
+                    acc += uref[j] * uref[(j + shift) % self.ny];
+                }
+                self.span[k][shift] += acc;
+            }
+        }
+        self.lines += self.ny;
+        Ok(())
+    }
+
+    /// Number of y-lines accumulated so far.
+    pub fn samples(&self) -> usize {
+        self.lines
+    }
+
+    /// Reference points as (iz, ix) grid indices.
+    pub fn refs(&self) -> &[(usize, usize)] {
+        &self.refs
+    }
+
+    /// Correlation coefficient map R(x, z), shape `(nz, nx)`, about reference
+    /// point `k`; R = 1 at the reference point.  Points with zero variance
+    /// are set to 0.
+    pub fn correlation(&self, k: usize) -> Result<Array2<f64>, Error> {
+        self.check(k)?;
+        let n = self.lines as f64;
+        let (iz, ix) = self.refs[k];
+        let mean = &self.sum / n;
+        let var = &self.sum2 / n - &mean.mapv(|m| m * m);
+        let var_ref = var[[iz, ix]];
+        if var_ref <= 0.0 {
+            return Err(format!("zero variance at reference point {k}").into());
+        }
+        let mut r = &self.cross[k] / n - &(&mean * mean[[iz, ix]]);
+        for (rv, &v) in r.iter_mut().zip(var.iter()) {
+            *rv = if v > 0.0 { *rv / (var_ref * v).sqrt() } else { 0.0 };
+        }
+        Ok(r)
+    }
+
+    /// Spanwise correlation R(Δy_j) at reference point `k` for
+    /// `j = 0..=ny/2`; R(0) = 1.
+    pub fn spanwise_correlation(&self, k: usize) -> Result<Array1<f64>, Error> {
+        self.check(k)?;
+        let n = self.lines as f64;
+        let (iz, ix) = self.refs[k];
+        let mean = self.sum[[iz, ix]] / n;
+        let cov = self.span[k].mapv(|s| s / n - mean * mean);
+        if cov[0] <= 0.0 {
+            return Err(format!("zero variance at reference point {k}").into());
+        }
+        Ok(cov.slice(ndarray::s![..self.ny / 2 + 1]).mapv(|c| c / cov[0]))
+    }
+
+    fn check(&self, k: usize) -> Result<(), Error> {
+        if self.lines == 0 {
+            return Err("no snapshots accumulated".into());
+        }
+        if k >= self.refs.len() {
+            return Err(format!("reference index {k} out of range").into());
+        }
+        Ok(())
+    }
+}
+
+/// Integral length scale ∫₀^{s₀} R(s) ds, integrated with the trapezoidal rule
+/// from `sep[0]` (the reference, R = 1) up to the first zero crossing s₀
+/// (linearly interpolated), or to the end of the line if R stays positive.
+/// `sep` holds the (non-negative, increasing) separations.
+pub fn integral_scale(sep: ArrayView1<f64>, r: ArrayView1<f64>) -> f64 {
+    let mut l = 0.0;
+    for i in 1..sep.len().min(r.len()) {
+        let ds = sep[i] - sep[i - 1];
+        if r[i] <= 0.0 {
+            // partial trapezoid up to the interpolated zero crossing
+            let frac = r[i - 1] / (r[i - 1] - r[i]);
+            l += 0.5 * r[i - 1] * frac * ds;
+            break;
+        }
+        l += 0.5 * (r[i - 1] + r[i]) * ds;
+    }
+    l
+}
+
+/// Integral scales and structure inclination extracted from one correlation
+/// map.
+#[derive(Debug, Clone, Copy)]
+pub struct CorrelationScales {
+    /// Streamwise integral scale (mean of the downstream and upstream ones).
+    pub lx: f64,
+    /// Spanwise integral scale.
+    pub ly: f64,
+    /// Wall-normal integral scale (mean of the upward and wall-ward ones).
+    pub lz: f64,
+    /// Inclination of the R ≥ level region to the wall, in degrees
+    /// (positive = leaning downstream away from the wall).
+    pub angle: f64,
+}
+
+/// Integral length scales along the lines through the reference point
+/// `(iz, ix)` of `r` (shape `(nz, nx)`), the spanwise scale from `ry`
+/// (separations `j Δy`), and the inclination angle of the region R ≥ `level`
+/// connected to the reference point.
+pub fn correlation_scales(
+    r: ArrayView2<f64>,
+    ry: ArrayView1<f64>,
+    (iz, ix): (usize, usize),
+    x: &Array1<f64>,
+    z: &Array1<f64>,
+    dy: f64,
+    level: f64,
+) -> CorrelationScales {
+    let one_sided = |coord: &Array1<f64>, line: ArrayView1<f64>, i0: usize| -> f64 {
+        let n = coord.len();
+        let fwd_s = Array1::from_iter((i0..n).map(|i| coord[i] - coord[i0]));
+        let fwd_r = line.slice(ndarray::s![i0..]).to_owned();
+        let bwd_s = Array1::from_iter((0..=i0).rev().map(|i| coord[i0] - coord[i]));
+        let bwd_r = Array1::from_iter((0..=i0).rev().map(|i| line[i]));
+        let mut sum = 0.0;
+        let mut sides = 0.0;
+        for (s, rr) in [(fwd_s, fwd_r), (bwd_s, bwd_r)] {
+            if s.len() > 1 {
+                sum += integral_scale(s.view(), rr.view());
+                sides += 1.0;
+            }
+        }
+        if sides > 0.0 { sum / sides } else { 0.0 }
+    };
+    let sy = Array1::from_iter((0..ry.len()).map(|j| j as f64 * dy));
+    CorrelationScales {
+        lx: one_sided(x, r.row(iz), ix),
+        ly: integral_scale(sy.view(), ry),
+        lz: one_sided(z, r.column(ix), iz),
+        angle: inclination_angle(r, (iz, ix), x, z, level),
+    }
+}
+
+/// Inclination angle (degrees, from +x towards +z) of the principal axis of
+/// the region R ≥ `level` that is connected to the reference point, using
+/// area-weighted second moments in physical (x, z) coordinates.
+pub fn inclination_angle(
+    r: ArrayView2<f64>,
+    (iz, ix): (usize, usize),
+    x: &Array1<f64>,
+    z: &Array1<f64>,
+    level: f64,
+) -> f64 {
+    let (nz, nx) = r.dim();
+    // cell widths for the area weights (half-distance to the neighbours)
+    let width = |c: &Array1<f64>, i: usize| -> f64 {
+        let lo = if i > 0 { c[i - 1] } else { c[i] };
+        let hi = if i + 1 < c.len() { c[i + 1] } else { c[i] };
+        0.5 * (hi - lo)
+    };
+
+    // flood fill (4-connectivity) from the reference point
+    let mut inside = Array2::from_elem((nz, nx), false);
+    let mut stack = vec![(iz, ix)];
+    while let Some((k, i)) = stack.pop() {
+        if inside[[k, i]] || r[[k, i]] < level {
+            continue;
+        }
+        inside[[k, i]] = true;
+        if k > 0 { stack.push((k - 1, i)); }
+        if k + 1 < nz { stack.push((k + 1, i)); }
+        if i > 0 { stack.push((k, i - 1)); }
+        if i + 1 < nx { stack.push((k, i + 1)); }
+    }
+
+    let (mut a, mut mx, mut mz) = (0.0, 0.0, 0.0);
+    for ((k, i), _) in inside.indexed_iter().filter(|(_, b)| **b) {
+        let da = width(x, i) * width(z, k);
+        a += da;
+        mx += da * x[i];
+        mz += da * z[k];
+    }
+    if a <= 0.0 {
+        return 0.0;
+    }
+    let (cx, cz) = (mx / a, mz / a);
+    let (mut ixx, mut izz, mut ixz) = (0.0, 0.0, 0.0);
+    for ((k, i), _) in inside.indexed_iter().filter(|(_, b)| **b) {
+        let da = width(x, i) * width(z, k);
+        let (dx, dz) = (x[i] - cx, z[k] - cz);
+        ixx += da * dx * dx;
+        izz += da * dz * dz;
+        ixz += da * dx * dz;
+    }
+    (0.5 * (2.0 * ixz).atan2(ixx - izz)).to_degrees()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::Array3;
+
+    const PI: f64 = std::f64::consts::PI;
+
+    /// u = cos(2πj/N) cos θ + sin(2πj/N) sin θ with θ = k_x x + k_z z: the
+    /// spanwise factors are uncorrelated, so R(x, z) = cos(θ − θ_ref) and
+    /// R(Δy) = cos(2π Δj / N).
+    #[test]
+    fn phase_field_correlations() {
+        let (nz, ny, nx) = (9, 16, 12);
+        let (kx, kz) = (0.7, 1.9);
+        let x = Array1::from_iter((0..nx).map(|i| 0.3 * i as f64));
+        let z = Array1::from_iter((0..nz).map(|k| 0.05 + 0.1 * (k as f64).powf(1.2)));
+        let theta = |k: usize, i: usize| kx * x[i] + kz * z[k];
+        let u = Array3::from_shape_fn((nz, ny, nx), |(k, j, i)| {
+            let phi = 2.0 * PI * j as f64 / ny as f64;
+            phi.cos() * theta(k, i).cos() + phi.sin() * theta(k, i).sin()
+        })
+        .into_dyn();
+
+        let refs = [(4, 5), (0, 0)];
+        let mut pc = PlaneCorrelation::new(nz, ny, nx, &refs).unwrap();
+        pc.accumulate(&u).unwrap();
+        assert_eq!(pc.samples(), ny);
+
+        for (n, &(kr, ir)) in refs.iter().enumerate() {
+            let r = pc.correlation(n).unwrap();
+            for ((k, i), &v) in r.indexed_iter() {
+                let expected = (theta(k, i) - theta(kr, ir)).cos();
+                assert!((v - expected).abs() < 1e-12, "({k},{i}): {v} vs {expected}");
+            }
+            let ry = pc.spanwise_correlation(n).unwrap();
+            for (j, &v) in ry.iter().enumerate() {
+                let expected = (2.0 * PI * j as f64 / ny as f64).cos();
+                assert!((v - expected).abs() < 1e-12, "Δj = {j}: {v}");
+            }
+        }
+    }
+
+    /// ∫ cos(ks) ds up to the first zero is 1/k; R = exp(−s/L) → L.
+    #[test]
+    fn integral_scale_of_model_correlations() {
+        let s = Array1::linspace(0.0, 20.0, 20001);
+        let k = 3.0;
+        let l = integral_scale(s.view(), s.mapv(|v| (k * v).cos()).view());
+        assert!((l - 1.0 / k).abs() < 1e-6, "cos: {l}");
+        let l = integral_scale(s.view(), s.mapv(|v| (-v / 0.8).exp()).view());
+        assert!((l - 0.8).abs() < 1e-5, "exp: {l}");
+    }
+
+    /// A Gaussian correlation ellipse rotated by α has its principal axis at α.
+    #[test]
+    fn inclination_of_rotated_ellipse() {
+        let (nz, nx) = (121, 241);
+        let x = Array1::from_iter((0..nx).map(|i| -3.0 + 0.025 * i as f64));
+        let z = Array1::from_iter((0..nz).map(|k| -1.5 + 0.025 * k as f64));
+        let alpha = 12.0_f64.to_radians();
+        let r = Array2::from_shape_fn((nz, nx), |(k, i)| {
+            let xi = x[i] * alpha.cos() + z[k] * alpha.sin();
+            let eta = -x[i] * alpha.sin() + z[k] * alpha.cos();
+            (-(xi * xi) / 1.0 - (eta * eta) / 0.04).exp()
+        });
+        let angle = inclination_angle(r.view(), (60, 120), &x, &z, 0.3);
+        assert!((angle - 12.0).abs() < 0.2, "angle = {angle}");
+    }
+}
@@ -0,0 +1,375 @@
+//! Interchangeable derivative schemes behind `ns::deriv1` / `ns::deriv2`.
+//!
+//! Every budget, decomposition and vortex criterion differentiates through
+//! `ns::deriv1`/`deriv2`, which dispatch on the process-wide [`Scheme`]
+//! ([`set_scheme`], the `--scheme` CLI option):
+//!
+//!   second    3-point Lagrange stencils on the non-uniform grid (default).
+//!   fourth    5-point explicit Lagrange stencils on the non-uniform grid,
+//!             shifted one-sided near a non-periodic boundary; weights from
+//!             Fornberg's recursion.  f' is 4th order, f'' 3rd (4th on a
+//!             uniform grid).
+//!   pade      4th-order compact (Padé) schemes
+//!               ¼ f'ᵢ₋₁ + f'ᵢ + ¼ f'ᵢ₊₁ = 3/2 (fᵢ₊₁ − fᵢ₋₁) / 2h
+//!               1/10 f''ᵢ₋₁ + f''ᵢ + 1/10 f''ᵢ₊₁ = 6/5 (fᵢ₊₁ − 2fᵢ + fᵢ₋₁) / h²
+//!             with 3rd-order boundary closures, solved per line as a
+//!             (cyclic) tridiagonal system.  A non-uniform coordinate is
+//!             mapped to a uniform index ξ:  f_x = f_ξ / x_ξ,
+//!             f_xx = (f_ξξ − x_ξξ f_x) / x_ξ².
+//!   spectral  FFT derivatives (ik)ⁿ along periodic axes, which must be
+//!             uniform (e.g. y); non-periodic axes fall back to `pade`.
+//!
+//! Axes with fewer than 5 points use the `second` stencils whatever the
+//! scheme.
+
+use std::sync::atomic::{AtomicU8, Ordering};
+
+use hdf5::Error;
+use ndarray::{Array1, ArrayD, Axis, Zip};
+use rustfft::{num_complex::Complex, FftPlanner};
+
+use super::ns::{deriv1_central, deriv2_central};
+
+/// Finite-difference scheme of the derivative operators.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Scheme {
+    Second = 0,
+    Fourth = 1,
+    Pade = 2,
+    Spectral = 3,
+}
+
+static CURRENT: AtomicU8 = AtomicU8::new(Scheme::Second as u8);
+
+/// Select the scheme used by `ns::deriv1`/`deriv2` for the whole process.
+pub fn set_scheme(scheme: Scheme) {
+    CURRENT.store(scheme as u8, Ordering::Relaxed);
+}
+
+/// The scheme currently used by `ns::deriv1`/`deriv2`.
+pub fn scheme() -> Scheme {
+    match CURRENT.load(Ordering::Relaxed) {
+        1 => Scheme::Fourth,
+        2 => Scheme::Pade,
+        3 => Scheme::Spectral,
+        _ => Scheme::Second,
+    }
+}
+
+impl Scheme {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "second" => Some(Self::Second),
+            "fourth" => Some(Self::Fourth),
+            "pade" => Some(Self::Pade),
+            "spectral" => Some(Self::Spectral),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Second => "second",
+            Self::Fourth => "fourth",
+            Self::Pade => "pade",
+            Self::Spectral => "spectral",
+        }
+    }
+
+    /// First derivative of `f` along `axis` with this scheme.
+    pub fn deriv1(&self, f: &ArrayD<f64>, axis: usize, coords: &Array1<f64>, periodic: bool) -> Result<ArrayD<f64>, Error> {
+        self.deriv(f, axis, coords, periodic, 1)
+    }
+
+    /// Second derivative of `f` along `axis` with this scheme.
+    pub fn deriv2(&self, f: &ArrayD<f64>, axis: usize, coords: &Array1<f64>, periodic: bool) -> Result<ArrayD<f64>, Error> {
+        self.deriv(f, axis, coords, periodic, 2)
+    }
+
+    fn deriv(&self, f: &ArrayD<f64>, axis: usize, coords: &Array1<f64>, periodic: bool, order: usize) -> Result<ArrayD<f64>, Error> {
+        let n = f.shape()[axis];
+        let scheme = match self {
+            _ if n < 5 => Self::Second,
+            Self::Spectral if !periodic => Self::Pade,
+            s => *s,
+        };
+        if scheme != Self::Second && coords.len() != n {
+            return Err(format!("coords length {} != array size {} along axis {}", coords.len(), n, axis).into());
+        }
+        match (scheme, order) {
+            (Self::Second, 1) => deriv1_central(f, axis, coords, periodic),
+            (Self::Second, _) => deriv2_central(f, axis, coords, periodic),
+            (Self::Fourth, _) => Ok(explicit(f, axis, coords, periodic, order)),
+            (Self::Pade, _) => Ok(compact(f, axis, coords, periodic, order)),
+            (Self::Spectral, _) => Ok(spectral(f, axis, coords, order)),
+        }
+    }
+}
+
+// ─── explicit 5-point stencils ────────────────────────────────────────────────
+
+/// Weights `c[k][j]` of the k-th derivative (k ≤ m) at `x0` from the values
+/// at nodes `x` (Fornberg 1988).
+fn fd_weights(x0: f64, x: &[f64], m: usize) -> Vec<Vec<f64>> {
+    let n = x.len();
+    let mut c = vec![vec![0.0; n]; m + 1];
+    c[0][0] = 1.0;
+    let (mut c1, mut c4) = (1.0, x[0] - x0);
+    for i in 1..n {
+        let mn = i.min(m);
+        let mut c2 = 1.0;
+        let c5 = c4;
+        c4 = x[i] - x0;
+        for j in 0..i {
+            let c3 = x[i] - x[j];
+            c2 *= c3;
+            if j == i - 1 {
+                for k in (1..=mn).rev() {
+                    c[k][i] = c1 * (k as f64 * c[k - 1][i - 1] - c5 * c[k][i - 1]) / c2;
+                }
+                c[0][i] = -c1 * c5 * c[0][i - 1] / c2;
+            }
+            for k in (1..=mn).rev() {
+                c[k][j] = (c4 * c[k][j] - k as f64 * c[k - 1][j]) / c3;
+            }
+            c[0][j] = c4 * c[0][j] / c3;
+        }
+        c1 = c2;
+    }
+    c
+}
+
+fn explicit(f: &ArrayD<f64>, axis: usize, coords: &Array1<f64>, periodic: bool, order: usize) -> ArrayD<f64> {
+    let n = coords.len();
+    let h = coords[1] - coords[0];
+    let mut df = f.to_owned();
+    for i in 0..n {
+        let (idx, nodes): (Vec<usize>, Vec<f64>) = if periodic {
+            (0..5).map(|o| ((i + n + o - 2) % n, (o as f64 - 2.0) * h)).unzip()
+        } else {
+            let s = i.saturating_sub(2).min(n - 5);
+            (s..s + 5).map(|j| (j, coords[j] - coords[i])).unzip()
+        };
+        let w = &fd_weights(0.0, &nodes, order)[order];
+        let mut acc = f.index_axis(Axis(axis), idx[0]).to_owned() * w[0];
+        for (&j, &wj) in idx.iter().zip(w).skip(1) {
+            acc.scaled_add(wj, &f.index_axis(Axis(axis), j));
+        }
+        df.index_axis_mut(Axis(axis), i).assign(&acc);
+    }
+    df
+}
+
+// ─── compact (Padé) schemes ───────────────────────────────────────────────────
+
+/// Tridiagonal system with sub-, main- and super-diagonals `a`, `b`, `c`;
+/// `corner` couples the first and last unknowns (cyclic systems).
+struct Tridiag {
+    a: Vec<f64>,
+    b: Vec<f64>,
+    c: Vec<f64>,
+    corner: f64,
+}
+
+impl Tridiag {
+    fn thomas(a: &[f64], b: &[f64], c: &[f64], d: &mut [f64]) {
+        let n = d.len();
+        let mut cp = vec![0.0; n];
+        cp[0] = c[0] / b[0];
+        d[0] /= b[0];
+        for i in 1..n {
+            let m = b[i] - a[i] * cp[i - 1];
+            cp[i] = c[i] / m;
+            d[i] = (d[i] - a[i] * d[i - 1]) / m;
+        }
+        for i in (0..n - 1).rev() {
+            d[i] -= cp[i] * d[i + 1];
+        }
+    }
+
+    /// Solve in place; a cyclic system via Sherman–Morrison.
+    fn solve(&self, d: &mut [f64]) {
+        if self.corner == 0.0 {
+            return Self::thomas(&self.a, &self.b, &self.c, d);
+        }
+        let n = d.len();
+        let (al, gamma) = (self.corner, -self.b[0]);
+        let mut b = self.b.clone();
+        b[0] -= gamma;
+        b[n - 1] -= al * al / gamma;
+        let mut z = vec![0.0; n];
+        z[0] = gamma;
+        z[n - 1] = al;
+        Self::thomas(&self.a, &b, &self.c, d);
+        Self::thomas(&self.a, &b, &self.c, &mut z);
+        let fact = (d[0] + al * d[n - 1] / gamma) / (1.0 + z[0] + al * z[n - 1] / gamma);
+        for (v, zi) in d.iter_mut().zip(&z) {
+            *v -= fact * zi;
+        }
+    }
+}
+
+/// Compact derivative of order 1 or 2 of one line `f` with spacing `h`.
+fn pade_line(f: &[f64], h: f64, periodic: bool, order: usize) -> Vec<f64> {
+    let n = f.len();
+    let (alpha, close) = if order == 1 { (0.25, 2.0) } else { (0.1, 11.0) };
+    let at = |i: isize| f[i.rem_euclid(n as isize) as usize];
+    let mut rhs: Vec<f64> = (0..n as isize)
+        .map(|i| match order {
+            1 => 0.75 * (at(i + 1) - at(i - 1)) / h,
+            _ => 1.2 * (at(i + 1) - 2.0 * at(i) + at(i - 1)) / (h * h),
+        })
+        .collect();
+    let (mut a, b, mut c) = (vec![alpha; n], vec![1.0; n], vec![alpha; n]);
+    let corner = if periodic {
+        alpha
+    } else {
+        (c[0], a[n - 1]) = (close, close);
+        let m = n - 1;
+        if order == 1 {
+            rhs[0] = (-2.5 * f[0] + 2.0 * f[1] + 0.5 * f[2]) / h;
+            rhs[m] = (2.5 * f[m] - 2.0 * f[m - 1] - 0.5 * f[m - 2]) / h;
+        } else {
+            rhs[0] = (13.0 * f[0] - 27.0 * f[1] + 15.0 * f[2] - f[3]) / (h * h);
+            rhs[m] = (13.0 * f[m] - 27.0 * f[m - 1] + 15.0 * f[m - 2] - f[m - 3]) / (h * h);
+        }
+        0.0
+    };
+    a[0] = 0.0;
+    c[n - 1] = 0.0;
+    Tridiag { a, b, c, corner }.solve(&mut rhs);
+    rhs
+}
+
+/// Apply `g` to every line of `f` along `axis`.
+fn map_lines(f: &ArrayD<f64>, axis: usize, mut g: impl FnMut(&[f64]) -> Vec<f64>) -> ArrayD<f64> {
+    let mut out = f.to_owned();
+    let mut line = vec![0.0; f.shape()[axis]];
+    Zip::from(out.lanes_mut(Axis(axis))).and(f.lanes(Axis(axis))).for_each(|mut o, l| {
+        for (b, v) in line.iter_mut().zip(l.iter()) {
+            *b = *v;
+        }
+        for (t, v) in o.iter_mut().zip(g(&line)) {
+            *t = v;
+        }
+    });
+    out
+}
+
+fn compact(f: &ArrayD<f64>, axis: usize, coords: &Array1<f64>, periodic: bool, order: usize) -> ArrayD<f64> {
+    if periodic {
+        let h = coords[1] - coords[0];
+        return map_lines(f, axis, |l| pade_line(l, h, true, order));
+    }
+    // metrics of the mapping index ξ → x
+    let x = coords.to_vec();
+    let (x1, x2) = (pade_line(&x, 1.0, false, 1), pade_line(&x, 1.0, false, 2));
+    map_lines(f, axis, |l| {
+        let g = pade_line(l, 1.0, false, 1);
+        let fx: Vec<f64> = g.iter().zip(&x1).map(|(g, m)| g / m).collect();
+        if order == 1 {
+            return fx;
+        }
+        let s = pade_line(l, 1.0, false, 2);
+        (0..l.len()).map(|i| (s[i] - x2[i] * fx[i]) / (x1[i] * x1[i])).collect()
+    })
+}
+
+// ─── FFT spectral derivatives ─────────────────────────────────────────────────
+
+fn spectral(f: &ArrayD<f64>, axis: usize, coords: &Array1<f64>, order: usize) -> ArrayD<f64> {
+    let n = coords.len();
+    let len = n as f64 * (coords[1] - coords[0]);
+    let mut planner = FftPlanner::new();
+    let (fwd, inv) = (planner.plan_fft_forward(n), planner.plan_fft_inverse(n));
+    // (ik)ⁿ / n, with the unresolved odd derivative of the Nyquist mode dropped
+    let mult: Vec<Complex<f64>> = (0..n)
+        .map(|m| {
+            let k = 2.0 * std::f64::consts::PI / len * if 2 * m > n { m as f64 - n as f64 } else { m as f64 };
+            let ik = if order == 1 && 2 * m == n { Complex::new(0.0, 0.0) } else { Complex::new(0.0, k) };
+            ik.powu(order as u32) / n as f64
+        })
+        .collect();
+    let mut buf = vec![Complex::new(0.0, 0.0); n];
+    map_lines(f, axis, |l| {
+        for (b, &v) in buf.iter_mut().zip(l) {
+            *b = Complex::new(v, 0.0);
+        }
+        fwd.process(&mut buf);
+        for (b, m) in buf.iter_mut().zip(&mult) {
+            *b *= m;
+        }
+        inv.process(&mut buf);
+        buf.iter().map(|b| b.re).collect()
+    })
+}
+
+// ─── Tests ────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Max error of the first and second derivative of f = sin(2x) + e^{x/2}
+    /// on n points of a tanh-stretched grid over [0, 2].
+    fn stretched_errors(s: Scheme, n: usize) -> (f64, f64) {
+        let x = Array1::from_shape_fn(n, |i| {
+            let xi = i as f64 / (n - 1) as f64;
+            1.0 + (1.5 * (2.0 * xi - 1.0)).tanh() / 1.5f64.tanh()
+        });
+        let f = x.mapv(|v: f64| (2.0 * v).sin() + (0.5 * v).exp()).into_dyn();
+        let d1 = s.deriv1(&f, 0, &x, false).unwrap();
+        let d2 = s.deriv2(&f, 0, &x, false).unwrap();
+        let e1 = x.iter().zip(d1.iter()).map(|(&v, d)| (2.0 * (2.0 * v).cos() + 0.5 * (0.5 * v).exp() - d).abs());
+        let e2 = x.iter().zip(d2.iter()).map(|(&v, d)| (-4.0 * (2.0 * v).sin() + 0.25 * (0.5 * v).exp() - d).abs());
+        (e1.fold(0.0, f64::max), e2.fold(0.0, f64::max))
+    }
+
+    #[test]
+    fn convergence_on_stretched_grid() {
+        // observed orders between n = 41 and 81; the 3-point f'' is first
+        // order on a stretched grid, the Padé boundary closures third
+        for (s, p1, p2) in [
+            (Scheme::Second, 1.8, 0.9),
+            (Scheme::Fourth, 3.7, 3.1),
+            (Scheme::Pade, 2.8, 3.1),
+            (Scheme::Spectral, 2.8, 3.1),
+        ] {
+            let (a1, a2) = stretched_errors(s, 41);
+            let (b1, b2) = stretched_errors(s, 81);
+            let (o1, o2) = ((a1 / b1).log2(), (a2 / b2).log2());
+            assert!(o1 > p1 && o2 > p2, "{}: orders {o1:.2}, {o2:.2}", s.name());
+        }
+        let sec = stretched_errors(Scheme::Second, 81);
+        for s in [Scheme::Fourth, Scheme::Pade] {
+            let e = stretched_errors(s, 81);
+            assert!(e.0 < 0.1 * sec.0 && e.1 < 0.1 * sec.1, "{}", s.name());
+        }
+    }
+
+    #[test]
+    fn periodic_schemes_and_spectral_accuracy() {
+        // f = exp(sin y) on a uniform periodic grid of period 2π, along
+        // axis 1 of a (2, n, 3) field
+        let errors = |s: Scheme, n: usize| {
+            let y = Array1::from_shape_fn(n, |j| 2.0 * std::f64::consts::PI * j as f64 / n as f64);
+            let f = ndarray::Array3::from_shape_fn((2, n, 3), |(_, j, _)| y[j].sin().exp()).into_dyn();
+            let d1 = s.deriv1(&f, 1, &y, true).unwrap();
+            let d2 = s.deriv2(&f, 1, &y, true).unwrap();
+            let mut e = (0.0f64, 0.0f64);
+            for ((k, j, i), &v) in d1.clone().into_dimensionality::<ndarray::Ix3>().unwrap().indexed_iter() {
+                let (sy, cy) = y[j].sin_cos();
+                e.0 = e.0.max((cy * sy.exp() - v).abs());
+                e.1 = e.1.max(((cy * cy - sy) * sy.exp() - d2[[k, j, i]]).abs());
+            }
+            e
+        };
+        for (s, p) in [(Scheme::Second, 1.8), (Scheme::Fourth, 3.7), (Scheme::Pade, 3.7)] {
+            let (a, b) = (errors(s, 32), errors(s, 64));
+            assert!((a.0 / b.0).log2() > p && (a.1 / b.1).log2() > p, "{}", s.name());
+        }
+        let sp = errors(Scheme::Spectral, 32);
+        assert!(sp.0 < 1e-10 && sp.1 < 1e-9, "{sp:?}");
+        assert_eq!(Scheme::from_name("pade"), Some(Scheme::Pade));
+    }
+}
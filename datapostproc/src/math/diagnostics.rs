@@ -0,0 +1,124 @@
+//! Mean-shear diagnostics of a wall-normal profile: eddy viscosity, mixing
+//! length, log-law indicator function and Reynolds-stress fraction.
+//!
+//! Coordinate convention (matches the rest of this crate): z is the
+//! wall-normal distance from the lower wall, u streamwise, w wall-normal.
+//!
+//! From the mean profile ū(z) and the Reynolds shear stress u'w'(z):
+//!
+//!   ν_t   = −u'w' / (∂ū/∂z)
+//!   l_m   = |u'w'|^{1/2} / |∂ū/∂z|
+//!   Ξ     = z⁺ dū⁺/dz⁺ = z (∂ū/∂z) / u_τ      (= 1/κ in a log layer)
+//!   f_RS  = −u'w' / (ν ∂ū/∂z − u'w')           (share of the total stress)
+//!
+//! with u_τ from the first cell, `wall::wall_shear_stress`.  ν_t, l_m and
+//! f_RS are singular where the mean shear (and with it the total stress)
+//! changes sign, i.e. near the centreline; points with
+//! |∂ū/∂z| < `mask` · max|∂ū/∂z| are set to NaN there.
+
+use hdf5::Error;
+use ndarray::{Array1, Ix1};
+
+use super::ns::deriv1;
+use super::wall::{friction_velocity, wall_shear_stress};
+
+/// Diagnostic profiles, each of length nz.
+pub struct ShearDiagnostics {
+    /// Mean shear ∂ū/∂z.
+    pub dudz: Array1<f64>,
+    /// Eddy viscosity ν_t (NaN where masked).
+    pub nu_t: Array1<f64>,
+    /// Mixing length l_m (NaN where masked).
+    pub lmix: Array1<f64>,
+    /// Log-law indicator function z⁺ dū⁺/dz⁺.
+    pub indicator: Array1<f64>,
+    /// Reynolds shear stress over total stress (NaN where masked).
+    pub rs_fraction: Array1<f64>,
+    /// Friction velocity used for the indicator function.
+    pub utau: f64,
+}
+
+/// Diagnostics of the mean profile `u` and Reynolds shear stress `uw`
+/// (fluctuation moment, not the total ⟨uw⟩) over the wall-normal grid `zc`.
+pub fn shear_diagnostics(
+    u: &Array1<f64>,
+    uw: &Array1<f64>,
+    zc: &Array1<f64>,
+    nu: f64,
+    mask: f64,
+) -> Result<ShearDiagnostics, Error> {
+    if u.len() != zc.len() || uw.len() != zc.len() {
+        return Err(format!(
+            "shear_diagnostics: profile lengths ({}, {}) differ from nz = {}",
+            u.len(),
+            uw.len(),
+            zc.len()
+        )
+        .into());
+    }
+    let dudz = deriv1(&u.clone().into_dyn(), 0, zc, false)?
+        .into_dimensionality::<Ix1>()
+        .unwrap();
+    let utau = friction_velocity(wall_shear_stress(&u.clone().into_dyn(), &zc.clone().into_dyn(), nu)?);
+
+    let smax = dudz.iter().fold(0.0f64, |a, v| a.max(v.abs()));
+    let singular = |s: f64| s.abs() < mask * smax;
+    let masked = |f: &dyn Fn(usize) -> f64| {
+        Array1::from_shape_fn(zc.len(), |k| if singular(dudz[k]) { f64::NAN } else { f(k) })
+    };
+
+    let nu_t = masked(&|k| -uw[k] / dudz[k]);
+    let lmix = masked(&|k| uw[k].abs().sqrt() / dudz[k].abs());
+    let rs_fraction = masked(&|k| -uw[k] / (nu * dudz[k] - uw[k]));
+    let indicator = Array1::from_shape_fn(zc.len(), |k| {
+        if utau > 0.0 { zc[k] * dudz[k] / utau } else { f64::NAN }
+    });
+    Ok(ShearDiagnostics { dudz, nu_t, lmix, indicator, rs_fraction, utau })
+}
+
+// ─── Tests ────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Log layer ū = (u_τ/κ) ln z + C with −u'w' = u_τ²: ν_t = κu_τz,
+    /// l_m = κz, f_RS → 1 for small ν.
+    #[test]
+    fn log_layer_recovers_kappa() {
+        let (kappa, ut, nu) = (0.41, 0.05, 1e-7);
+        let nz = 400;
+        let zc = Array1::from_shape_fn(nz, |k| 0.01 * 1.01f64.powi(k as i32));
+        let u = zc.mapv(|z| ut / kappa * z.ln() + 1.0);
+        let uw = Array1::from_elem(nz, -ut * ut);
+        let d = shear_diagnostics(&u, &uw, &zc, nu, 0.01).unwrap();
+        for k in 1..nz - 1 {
+            let z = zc[k];
+            assert!((d.lmix[k] / (kappa * z) - 1.0).abs() < 1e-3, "l_m = {}", d.lmix[k]);
+            assert!((d.nu_t[k] / (kappa * ut * z) - 1.0).abs() < 1e-3);
+            assert!((d.rs_fraction[k] - 1.0).abs() < 1e-3);
+            // Ξ scales with 1/u_τ(wall), here the first-cell estimate
+            assert!((d.indicator[k] * d.utau - ut / kappa).abs() < 1e-3 * ut / kappa);
+        }
+    }
+
+    /// Laminar Poiseuille flow: no Reynolds stress, and the shear-free
+    /// centreline is masked.
+    #[test]
+    fn centreline_is_masked() {
+        let nz = 41;
+        let nu = 1e-3;
+        let zc = Array1::from_shape_fn(nz, |k| (k as f64 + 0.5) * 2.0 / nz as f64);
+        let u = zc.mapv(|z| z * (2.0 - z));
+        let uw = Array1::zeros(nz);
+        let d = shear_diagnostics(&u, &uw, &zc, nu, 0.05).unwrap();
+        let centre = nz / 2;
+        assert!(d.nu_t[centre].is_nan() && d.rs_fraction[centre].is_nan() && d.lmix[centre].is_nan());
+        assert!(d.indicator[centre].abs() < 1e-10);
+        assert_eq!(d.rs_fraction[0], 0.0);
+        assert!((d.dudz[5] - (2.0 - 2.0 * zc[5])).abs() < 1e-12);
+        assert!((d.utau - (nu * u[0] / zc[0]).sqrt()).abs() < 1e-15);
+        let masked = d.nu_t.iter().filter(|v| v.is_nan()).count();
+        assert!(masked > 0 && masked < 5, "masked {masked}");
+    }
+}
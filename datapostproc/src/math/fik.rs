@@ -300,6 +300,32 @@ pub fn fik_average(decs: &[&FikDecomposition]) -> Result<FikDecomposition, Error
     })
 }
 
+/// Spanwise-wavelength decomposition of C_turb_y.
+///
+/// C_turb_y is linear in u'w', so applying its weighting
+/// 6∫(1−η)(−·)dη + (·)|_{z=h} to each column of a u–w co-spectrum gives the
+/// contribution of every spanwise mode; the contributions sum to the
+/// `cf_turb_y` of [`fik_decomposition`] for the same stress.
+///
+/// `co` – co-spectrum per mode (density × Δk), shape `(nz, nk)`, over the
+/// full-height grid `zc`.  Returns the per-mode contributions, length nk.
+pub fn fik_turb_y_modes(co: &ArrayD<f64>, zc: &Array1<f64>, h: f64) -> Result<Array1<f64>, Error> {
+    if co.ndim() != 2 || co.shape()[0] != zc.len() {
+        return Err(format!(
+            "fik_turb_y_modes: co-spectrum shape {:?} incompatible with nz={}",
+            co.shape(),
+            zc.len()
+        )
+        .into());
+    }
+    let nk = co.shape()[1];
+    let (n_half, t, zc_h) = half_grid(zc, h)?;
+    let co_h = crop_extend(co, n_half, t);
+    let w1: Array1<f64> = zc_h.mapv(|z| 1.0 - z / h);
+    let at_h = Array1::from_iter((0..nk).map(|m| co_h[[n_half, m]]));
+    Ok(fik_integrate(&(-&co_h), &w1, &zc_h)? * 6.0 + &at_h)
+}
+
 // ─── Core: half-channel triple integration of one (nz, nx) plane/average ─────
 
 /// Spanwise-derivative integrands (already differentiated along y), shape (nz, nx).
@@ -690,6 +716,33 @@ mod tests {
         assert!(cf_diff_max   < 1e-6  * (6.0 / re_b), "cf_diff too large: {cf_diff_max:.2e}");
     }
 
+    /// Two "modes" whose stresses add up to u'w' = −A(1−η)z-profile: their
+    /// FIK-weighted contributions sum to the cf_turb_y of the full core.
+    #[test]
+    fn turb_y_modes_sum_to_cf_turb_y() {
+        let (nz, ny, nx) = (60, 1, 3);
+        let h = 1.0;
+        let zc = Array1::from_iter((0..nz).map(|i| 2.0 * h * (i as f64 + 0.5) / nz as f64));
+        let x = linspace(0.0, 1.0, nx);
+        let m1 = zc.mapv(|z| -1e-3 * (z * (2.0 - z)));
+        let m2 = zc.mapv(|z| 4e-4 * (std::f64::consts::PI * z).sin());
+        let uw_p = &m1 + &m2;
+
+        let co = ndarray::stack(Axis(1), &[m1.view(), m2.view()]).unwrap().into_dyn();
+        let modes = fik_turb_y_modes(&co, &zc, h).unwrap();
+
+        let zero = broadcast_z(&Array1::zeros(nz), ny, nx);
+        let fik = fik_decomposition(
+            &zero, &zero, &zero, &zero, &zero, &broadcast_z(&uw_p, ny, nx),
+            &x, &zc, 2800.0, h, false,
+        )
+        .unwrap();
+        for ix in 0..nx {
+            assert!((modes.sum() - fik.cf_turb_y[ix]).abs() < 1e-15, "{} vs {}", modes.sum(), fik.cf_turb_y[ix]);
+        }
+        assert!(modes[0] > 0.0 && modes[1] < 0.0);
+    }
+
     /// Spanwise-uniform flow: every per-plane decomposition must reproduce the
     /// spanwise-averaged one exactly, with zero spanwise terms; and averaging
     /// the planes (fik_average) must be the identity.
@@ -0,0 +1,356 @@
+//! Invariants of the velocity-gradient tensor and their joint (R, Q)
+//! statistics.
+//!
+//! Coordinate convention (matches the rest of this crate):
+//!   Array shape (nz, ny, nx): axis 0 = wall-normal z, axis 1 = spanwise y,
+//!   axis 2 = streamwise x.
+//!
+//! With A_ij = ∂u_i/∂x_j (assembled by `vortex::velocity_gradient`) and its
+//! characteristic polynomial λ³ + Pλ² + Qλ + R = 0:
+//!
+//!   P = −tr A,   Q = ½(P² − tr A²),   R = −det A,
+//!
+//! and for the strain-rate S and rotation W parts (incompressible forms)
+//!
+//!   Q_S = −½ S_ij S_ij,   R_S = −⅓ S_ij S_jk S_ki,   Q_W = ½ W_ij W_ij,
+//!
+//! so that Q = Q_S + Q_W when P = 0.
+//!
+//! [`QrJointPdf`] accumulates the joint PDF of (R*, Q*) = (R/⟨Q_W⟩^{3/2},
+//! Q/⟨Q_W⟩) over wall-normal bands × streamwise regions (e.g. the blowing
+//! strip and downstream of it).  ⟨Q_W⟩ is the ensemble mean over the
+//! band/region, so the snapshots are passed twice: first to
+//! [`QrJointPdf::normalise`], which sums Q_W, then to
+//! [`QrJointPdf::accumulate`], which bins every snapshot on the same scale.
+
+use hdf5::Error;
+use ndarray::{s, Array1, Array2, Array4, ArrayD, ArrayView3, Ix3};
+
+use super::vortex::VelocityGradient;
+
+/// Gradient invariants, each of the input field shape.
+pub struct GradientInvariants {
+    pub p: ArrayD<f64>,
+    pub q: ArrayD<f64>,
+    pub r: ArrayD<f64>,
+    pub qs: ArrayD<f64>,
+    pub rs: ArrayD<f64>,
+    pub qw: ArrayD<f64>,
+}
+
+/// P, Q, R of A and Q_S, R_S, Q_W of its symmetric/antisymmetric parts.
+pub fn gradient_invariants(grad: &VelocityGradient) -> GradientInvariants {
+    let shape = grad.g[0][0].raw_dim();
+    let mut out = GradientInvariants {
+        p: ArrayD::zeros(shape.clone()),
+        q: ArrayD::zeros(shape.clone()),
+        r: ArrayD::zeros(shape.clone()),
+        qs: ArrayD::zeros(shape.clone()),
+        rs: ArrayD::zeros(shape.clone()),
+        qw: ArrayD::zeros(shape),
+    };
+    let p = out.p.as_slice_mut().unwrap();
+    let q = out.q.as_slice_mut().unwrap();
+    let r = out.r.as_slice_mut().unwrap();
+    let qs = out.qs.as_slice_mut().unwrap();
+    let rs = out.rs.as_slice_mut().unwrap();
+    let qw = out.qw.as_slice_mut().unwrap();
+
+    for n in 0..grad.len() {
+        let a = grad.at(n);
+        let tr = a[0][0] + a[1][1] + a[2][2];
+        let sym = |sign: f64| -> [[f64; 3]; 3] {
+            std::array::from_fn(|i| std::array::from_fn(|j| 0.5 * (a[i][j] + sign * a[j][i])))
+        };
+        let (s, w) = (sym(1.0), sym(-1.0));
+        // tr(XY) = Σ_ij X_ij Y_ji
+        let tr_prod = |x: &[[f64; 3]; 3], y: &[[f64; 3]; 3]| -> f64 {
+            (0..3).flat_map(|i| (0..3).map(move |j| x[i][j] * y[j][i])).sum()
+        };
+        let s2: [[f64; 3]; 3] =
+            std::array::from_fn(|i| std::array::from_fn(|j| (0..3).map(|k| s[i][k] * s[k][j]).sum()));
+        let tr2 = tr_prod(&a, &a);
+        let ss = tr_prod(&s, &s);
+        let ww = -tr_prod(&w, &w);
+        let sss = tr_prod(&s2, &s);
+        let det = a[0][0] * (a[1][1] * a[2][2] - a[1][2] * a[2][1])
+            - a[0][1] * (a[1][0] * a[2][2] - a[1][2] * a[2][0])
+            + a[0][2] * (a[1][0] * a[2][1] - a[1][1] * a[2][0]);
+
+        p[n] = -tr;
+        q[n] = 0.5 * (tr * tr - tr2);
+        r[n] = -det;
+        qs[n] = -0.5 * ss;
+        rs[n] = -sss / 3.0;
+        qw[n] = 0.5 * ww;
+    }
+    out
+}
+
+/// Accumulator of (R*, Q*) joint PDFs per streamwise region × wall-normal
+/// band: feed it every snapshot with [`normalise`], then again with
+/// [`accumulate`], and read out [`pdf`].
+///
+/// [`normalise`]: QrJointPdf::normalise
+/// [`accumulate`]: QrJointPdf::accumulate
+/// [`pdf`]: QrJointPdf::pdf
+pub struct QrJointPdf {
+    /// Wall-normal bands and streamwise regions as half-open index ranges.
+    bands: Vec<(usize, usize)>,
+    regions: Vec<(usize, usize)>,
+    nr: usize,
+    nq: usize,
+    rmax: f64,
+    qmax: f64,
+    /// Bin counts, shape (nregion, nband, nq, nr).
+    counts: Array4<f64>,
+    /// Σ over snapshots of the band/region mean Q_W, shape (nregion, nband).
+    qw_sum: Array2<f64>,
+    qw_snapshots: usize,
+    /// Points binned per region/band (in range or not).
+    points: Array2<f64>,
+    snapshots: usize,
+}
+
+impl QrJointPdf {
+    /// `bands` — `[k0, k1)` wall-normal index ranges; `regions` — `[i0, i1)`
+    /// streamwise index ranges; `nr × nq` bins over R* ∈ [−rmax, rmax],
+    /// Q* ∈ [−qmax, qmax].
+    pub fn new(
+        bands: &[(usize, usize)],
+        regions: &[(usize, usize)],
+        nr: usize,
+        nq: usize,
+        rmax: f64,
+        qmax: f64,
+    ) -> Result<Self, Error> {
+        if bands.is_empty() || regions.is_empty() {
+            return Err("QrJointPdf: need at least one band and one region".into());
+        }
+        if bands.iter().chain(regions).any(|&(a, b)| a >= b) {
+            return Err("QrJointPdf: empty band or region range".into());
+        }
+        if nr == 0 || nq == 0 || rmax <= 0.0 || qmax <= 0.0 {
+            return Err("QrJointPdf: bin counts and ranges must be positive".into());
+        }
+        let (nreg, nband) = (regions.len(), bands.len());
+        Ok(Self {
+            bands: bands.to_vec(),
+            regions: regions.to_vec(),
+            nr,
+            nq,
+            rmax,
+            qmax,
+            counts: Array4::zeros((nreg, nband, nq, nr)),
+            qw_sum: Array2::zeros((nreg, nband)),
+            qw_snapshots: 0,
+            points: Array2::zeros((nreg, nband)),
+            snapshots: 0,
+        })
+    }
+
+    /// Check a snapshot against the bands/regions and view (q, r, qw) as 3-D.
+    fn views<'a>(&self, inv: &'a GradientInvariants) -> Result<[ArrayView3<'a, f64>; 3], Error> {
+        fn view(a: &ArrayD<f64>) -> Result<ArrayView3<'_, f64>, Error> {
+            a.view()
+                .into_dimensionality::<Ix3>()
+                .map_err(|_| Error::from("QrJointPdf: invariants must be 3-D (nz, ny, nx)"))
+        }
+        let [q, r, qw] = [view(&inv.q)?, view(&inv.r)?, view(&inv.qw)?];
+        let (nz, _, nx) = q.dim();
+        if self.bands.iter().any(|&(_, k1)| k1 > nz) || self.regions.iter().any(|&(_, i1)| i1 > nx) {
+            return Err(format!("QrJointPdf: band/region outside the ({nz}, {nx}) plane").into());
+        }
+        Ok([q, r, qw])
+    }
+
+    /// First pass: add the band/region mean Q_W of one `(nz, ny, nx)`
+    /// snapshot to the ensemble ⟨Q_W⟩.
+    pub fn normalise(&mut self, inv: &GradientInvariants) -> Result<(), Error> {
+        let [_, _, qw] = self.views(inv)?;
+        for (g, &(i0, i1)) in self.regions.iter().enumerate() {
+            for (b, &(k0, k1)) in self.bands.iter().enumerate() {
+                let band = qw.slice(s![k0..k1, .., i0..i1]);
+                self.qw_sum[[g, b]] += band.sum() / band.len() as f64;
+            }
+        }
+        self.qw_snapshots += 1;
+        Ok(())
+    }
+
+    /// Second pass: bin one `(nz, ny, nx)` snapshot with the ensemble
+    /// ⟨Q_W⟩.  Bands/regions with ⟨Q_W⟩ ≤ 0 (no rotation at all) are not
+    /// binned and have a NaN PDF.
+    pub fn accumulate(&mut self, inv: &GradientInvariants) -> Result<(), Error> {
+        let qw_mean = self.qw_mean()?;
+        let [q, r, _] = self.views(inv)?;
+        let ny = q.dim().1;
+        let (dr, dq) = (2.0 * self.rmax / self.nr as f64, 2.0 * self.qmax / self.nq as f64);
+
+        for (g, &(i0, i1)) in self.regions.iter().enumerate() {
+            for (b, &(k0, k1)) in self.bands.iter().enumerate() {
+                let qw = qw_mean[[g, b]];
+                if qw <= 0.0 {
+                    continue;
+                }
+                self.points[[g, b]] += ((k1 - k0) * ny * (i1 - i0)) as f64;
+                let (sq, sr) = (1.0 / qw, qw.powf(-1.5));
+                for k in k0..k1 {
+                    for j in 0..ny {
+                        for i in i0..i1 {
+                            let rb = ((r[[k, j, i]] * sr + self.rmax) / dr).floor();
+                            let qb = ((q[[k, j, i]] * sq + self.qmax) / dq).floor();
+                            if rb >= 0.0 && qb >= 0.0 && (rb as usize) < self.nr && (qb as usize) < self.nq {
+                                self.counts[[g, b, qb as usize, rb as usize]] += 1.0;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        self.snapshots += 1;
+        Ok(())
+    }
+
+    /// Number of snapshots binned.
+    pub fn samples(&self) -> usize {
+        self.snapshots
+    }
+
+    /// Bin centres of R* and Q*.
+    pub fn bins(&self) -> (Array1<f64>, Array1<f64>) {
+        let centres = |n: usize, m: f64| {
+            let d = 2.0 * m / n as f64;
+            Array1::from_shape_fn(n, |b| -m + (b as f64 + 0.5) * d)
+        };
+        (centres(self.nr, self.rmax), centres(self.nq, self.qmax))
+    }
+
+    /// Joint PDF P(R*, Q*), shape (nregion, nband, nq, nr), normalised with
+    /// all binned points so that Σ P ΔR* ΔQ* is the fraction inside the
+    /// plotted range.
+    pub fn pdf(&self) -> Result<Array4<f64>, Error> {
+        if self.snapshots == 0 {
+            return Err("QrJointPdf: no snapshots accumulated".into());
+        }
+        let cell = (2.0 * self.rmax / self.nr as f64) * (2.0 * self.qmax / self.nq as f64);
+        let mut pdf = self.counts.clone();
+        for ((g, b, _, _), p) in pdf.indexed_iter_mut() {
+            *p /= self.points[[g, b]] * cell;
+        }
+        Ok(pdf)
+    }
+
+    /// Ensemble mean ⟨Q_W⟩ per region/band, shape (nregion, nband).
+    pub fn qw_mean(&self) -> Result<Array2<f64>, Error> {
+        if self.qw_snapshots == 0 {
+            return Err("QrJointPdf: no snapshots normalised".into());
+        }
+        Ok(&self.qw_sum / self.qw_snapshots as f64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::Array3;
+
+    fn uniform(a: [[f64; 3]; 3], shape: (usize, usize, usize)) -> VelocityGradient {
+        let c = |i: usize, j: usize| Array3::from_elem(shape, a[i][j]).into_dyn();
+        VelocityGradient {
+            g: [
+                [c(0, 0), c(0, 1), c(0, 2)],
+                [c(1, 0), c(1, 1), c(1, 2)],
+                [c(2, 0), c(2, 1), c(2, 2)],
+            ],
+        }
+    }
+
+    #[test]
+    fn invariants_of_rotation_strain_and_shear() {
+        // solid-body rotation about z: Q = Q_W = Ω², R = 0
+        let om = 2.0;
+        let inv = gradient_invariants(&uniform([[0.0, -om, 0.0], [om, 0.0, 0.0], [0.0; 3]], (1, 1, 1)));
+        assert!((inv.q[[0, 0, 0]] - om * om).abs() < 1e-12);
+        assert!((inv.qw[[0, 0, 0]] - om * om).abs() < 1e-12);
+        assert!(inv.r[[0, 0, 0]].abs() < 1e-12 && inv.qs[[0, 0, 0]].abs() < 1e-12);
+
+        // pure strain diag(a, b, c), a + b + c = 0: Q = Q_S = −½Σa², R = R_S = −abc
+        let (a, b) = (1.0, 0.5);
+        let c = -(a + b);
+        let inv = gradient_invariants(&uniform([[a, 0.0, 0.0], [0.0, b, 0.0], [0.0, 0.0, c]], (1, 1, 1)));
+        let qex = -0.5 * (a * a + b * b + c * c);
+        assert!((inv.q[[0, 0, 0]] - qex).abs() < 1e-12 && (inv.qs[[0, 0, 0]] - qex).abs() < 1e-12);
+        assert!((inv.r[[0, 0, 0]] + a * b * c).abs() < 1e-12);
+        assert!((inv.rs[[0, 0, 0]] + a * b * c).abs() < 1e-12);
+        assert!(inv.p[[0, 0, 0]].abs() < 1e-12 && inv.qw[[0, 0, 0]].abs() < 1e-12);
+
+        // general traceless tensor: Q = Q_S + Q_W
+        let g = [[0.3, -1.2, 0.7], [0.4, -0.5, 0.2], [-0.9, 1.1, 0.2]];
+        let inv = gradient_invariants(&uniform(g, (1, 1, 1)));
+        let (q, qs, qw) = (inv.q[[0, 0, 0]], inv.qs[[0, 0, 0]], inv.qw[[0, 0, 0]]);
+        assert!((q - qs - qw).abs() < 1e-12, "{q} vs {qs} + {qw}");
+    }
+
+    #[test]
+    fn joint_pdf_normalisation_and_bins() {
+        let (nz, ny, nx) = (4, 3, 6);
+        let mut inv = gradient_invariants(&uniform([[0.0; 3]; 3], (nz, ny, nx)));
+        // Q_W = 4 everywhere, Q = 2 → Q* = 0.5; R = 4 → R* = 0.5, except in
+        // the upper band of region 1 where R* = 10 falls outside the range
+        inv.qw.fill(4.0);
+        inv.q.fill(2.0);
+        inv.r.fill(4.0);
+        for k in 2..4 {
+            for j in 0..ny {
+                for i in 3..6 {
+                    inv.r[[k, j, i]] = 80.0;
+                }
+            }
+        }
+        let mut acc = QrJointPdf::new(&[(0, 2), (2, 4)], &[(0, 3), (3, 6)], 4, 4, 2.0, 2.0).unwrap();
+        assert!(acc.accumulate(&inv).is_err());
+        for _ in 0..2 {
+            acc.normalise(&inv).unwrap();
+        }
+        acc.accumulate(&inv).unwrap();
+        acc.accumulate(&inv).unwrap();
+        assert_eq!(acc.samples(), 2);
+
+        let pdf = acc.pdf().unwrap();
+        let (rb, qb) = acc.bins();
+        assert_eq!(rb.to_vec(), vec![-1.5, -0.5, 0.5, 1.5]);
+        assert_eq!(qb.to_vec(), rb.to_vec());
+        // unit cell area: every in-range point lands in bin (q = 2, r = 2)
+        assert!((pdf[[0, 0, 2, 2]] - 1.0).abs() < 1e-12);
+        assert!((pdf[[0, 1, 2, 2]] - 1.0).abs() < 1e-12);
+        assert!((pdf[[1, 0, 2, 2]] - 1.0).abs() < 1e-12);
+        assert!(pdf.slice(ndarray::s![1, 1, .., ..]).sum().abs() < 1e-12);
+        assert!(acc.qw_mean().unwrap().iter().all(|&m| (m - 4.0).abs() < 1e-12));
+    }
+
+    #[test]
+    fn joint_pdf_uses_ensemble_qw() {
+        let (nz, ny, nx) = (4, 2, 3);
+        // snapshots with Q_W = 2 and 6 in the lower band: ⟨Q_W⟩ = 4, so
+        // Q = 2, R = 4 map to Q* = R* = 0.5 in both; the upper band has no
+        // rotation at all
+        let snap = |qw: f64| {
+            let mut inv = gradient_invariants(&uniform([[0.0; 3]; 3], (nz, ny, nx)));
+            inv.qw.slice_mut(s![0..2, .., ..]).fill(qw);
+            inv.q.fill(2.0);
+            inv.r.fill(4.0);
+            inv
+        };
+        let (a, b) = (snap(2.0), snap(6.0));
+        let mut acc = QrJointPdf::new(&[(0, 2), (2, 4)], &[(0, 3)], 4, 4, 2.0, 2.0).unwrap();
+        acc.normalise(&a).unwrap();
+        acc.normalise(&b).unwrap();
+        acc.accumulate(&a).unwrap();
+        acc.accumulate(&b).unwrap();
+        assert!((acc.qw_mean().unwrap()[[0, 0]] - 4.0).abs() < 1e-12);
+        let pdf = acc.pdf().unwrap();
+        assert!((pdf[[0, 0, 2, 2]] - 1.0).abs() < 1e-12);
+        assert!(pdf.slice(s![0, 1, .., ..]).iter().all(|p| p.is_nan()));
+    }
+}
@@ -0,0 +1,136 @@
+//! Law-of-the-wall fitting and composite reference profiles in wall units.
+//!
+//! Log law:  u⁺ = (1/κ) ln z⁺ + B.
+//!
+//! [`fit_log_law`] extracts κ and B by linear least squares of u⁺ against
+//! ln z⁺ over a z⁺ band; [`log_shift`] gives the log-law shift
+//!
+//!   ΔU⁺ = ⟨u⁺ − (1/κ_ref) ln z⁺ − B_ref⟩_band
+//!
+//! at a fixed reference slope, positive for drag reduction (upward shift).
+//!
+//! Composite profiles valid from the wall through the log layer:
+//!
+//!   Reichardt (1951):
+//!     u⁺ = (1/κ) ln(1 + κz⁺) + C [1 − e^{−z⁺/11} − (z⁺/11) e^{−z⁺/3}],
+//!     C = 7.8
+//!   Musker (1979), closed form for κ = 0.41, s = 0.001093:
+//!     u⁺ = 5.424 atan((2z⁺ − 8.15)/16.7)
+//!          + log₁₀[(z⁺ + 10.6)^9.6 / (z⁺² − 8.15z⁺ + 86)²] − 3.52
+
+use hdf5::Error;
+use ndarray::Array1;
+
+/// Least-squares log-law fit over a z⁺ band.
+#[derive(Clone, Copy, Debug)]
+pub struct LogFit {
+    pub kappa: f64,
+    pub b: f64,
+    /// RMS deviation of u⁺ from the fitted line over the band.
+    pub rms: f64,
+    /// Number of points in the band.
+    pub points: usize,
+}
+
+/// Indices of the points with `band.0 ≤ z⁺ ≤ band.1`.
+fn band_points(zplus: &Array1<f64>, band: (f64, f64)) -> Vec<usize> {
+    (0..zplus.len()).filter(|&k| zplus[k] >= band.0 && zplus[k] <= band.1).collect()
+}
+
+/// Fit u⁺ = (1/κ) ln z⁺ + B to the points with z⁺ in `band`.
+pub fn fit_log_law(zplus: &Array1<f64>, uplus: &Array1<f64>, band: (f64, f64)) -> Result<LogFit, Error> {
+    if zplus.len() != uplus.len() {
+        return Err("fit_log_law: z⁺ and u⁺ lengths differ".into());
+    }
+    let idx = band_points(zplus, band);
+    if idx.len() < 2 || band.0 <= 0.0 {
+        return Err(format!(
+            "fit_log_law: need at least two points with 0 < z⁺ in [{}, {}], found {}",
+            band.0,
+            band.1,
+            idx.len()
+        )
+        .into());
+    }
+    let n = idx.len() as f64;
+    let (sx, sy) = idx.iter().fold((0.0, 0.0), |(a, b), &k| (a + zplus[k].ln(), b + uplus[k]));
+    let (mx, my) = (sx / n, sy / n);
+    let (sxx, sxy) = idx.iter().fold((0.0, 0.0), |(a, b), &k| {
+        let dx = zplus[k].ln() - mx;
+        (a + dx * dx, b + dx * (uplus[k] - my))
+    });
+    if sxx <= 0.0 || sxy == 0.0 {
+        return Err("fit_log_law: degenerate band (no spread in ln z⁺ or flat u⁺)".into());
+    }
+    let slope = sxy / sxx;
+    let b = my - slope * mx;
+    let rms = (idx.iter().map(|&k| (uplus[k] - slope * zplus[k].ln() - b).powi(2)).sum::<f64>() / n).sqrt();
+    Ok(LogFit { kappa: 1.0 / slope, b, rms, points: idx.len() })
+}
+
+/// Log-law shift ΔU⁺ relative to (κ_ref, B_ref), averaged over `band`.
+pub fn log_shift(
+    zplus: &Array1<f64>,
+    uplus: &Array1<f64>,
+    band: (f64, f64),
+    kappa_ref: f64,
+    b_ref: f64,
+) -> Result<f64, Error> {
+    let idx = band_points(zplus, band);
+    if idx.is_empty() || band.0 <= 0.0 {
+        return Err(format!("log_shift: no points with 0 < z⁺ in [{}, {}]", band.0, band.1).into());
+    }
+    let s: f64 = idx.iter().map(|&k| uplus[k] - zplus[k].ln() / kappa_ref - b_ref).sum();
+    Ok(s / idx.len() as f64)
+}
+
+/// Reichardt's composite profile u⁺(z⁺).
+pub fn reichardt(zplus: f64, kappa: f64) -> f64 {
+    (1.0 + kappa * zplus).ln() / kappa
+        + 7.8 * (1.0 - (-zplus / 11.0).exp() - zplus / 11.0 * (-zplus / 3.0).exp())
+}
+
+/// Musker's composite profile u⁺(z⁺) (κ = 0.41, s = 0.001093).
+pub fn musker(zplus: f64) -> f64 {
+    let z = zplus;
+    5.424 * ((2.0 * z - 8.15) / 16.7).atan()
+        + ((z + 10.6).powf(9.6) / (z * z - 8.15 * z + 86.0).powi(2)).log10()
+        - 3.52
+}
+
+// ─── Tests ────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fit_and_shift_of_synthetic_log_layer() {
+        let zplus = Array1::from_shape_fn(200, |k| 1.05f64.powi(k as i32));
+        let (kappa, b) = (0.39, 6.1);
+        // viscous sublayer below z⁺ = 11, exact log layer above
+        let uplus = zplus.mapv(|z: f64| if z < 11.0 { z } else { z.ln() / kappa + b });
+        let fit = fit_log_law(&zplus, &uplus, (30.0, 300.0)).unwrap();
+        assert!((fit.kappa - kappa).abs() < 1e-12 && (fit.b - b).abs() < 1e-12);
+        assert!(fit.rms < 1e-12 && fit.points > 10);
+
+        let du = log_shift(&zplus, &uplus, (30.0, 300.0), kappa, 5.2).unwrap();
+        assert!((du - 0.9).abs() < 1e-12);
+        assert!(fit_log_law(&zplus, &uplus, (1e6, 1e7)).is_err());
+    }
+
+    #[test]
+    fn composite_profiles_limits() {
+        for z in [0.1, 0.5, 1.0] {
+            assert!((reichardt(z, 0.41) - z).abs() < 0.02 * z, "Reichardt {z}");
+            assert!((musker(z) - z).abs() < 0.02 * z + 0.01, "Musker {z}: {}", musker(z));
+        }
+        // log-layer slope 1/κ and intercept near 5
+        for f in [|z| reichardt(z, 0.41), musker as fn(f64) -> f64] {
+            let slope = (f(1000.0) - f(100.0)) / 10f64.ln();
+            assert!((slope - 1.0 / 0.41).abs() < 0.03, "slope {slope}");
+            let b = f(500.0) - 500f64.ln() / 0.41;
+            assert!(b > 4.8 && b < 5.7, "B = {b}");
+        }
+    }
+}
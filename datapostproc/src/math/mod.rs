@@ -1,9 +1,25 @@
+pub mod anisotropy;
 pub mod avg;
 pub mod centerline;
+pub mod check;
+pub mod closure;
+pub mod control;
+pub mod correlation;
+pub mod deriv;
+pub mod diagnostics;
 pub mod fik;
+pub mod invariants;
+pub mod isosurface;
+pub mod loglaw;
+pub mod momentum;
 pub mod ns;
 pub mod rd;
+pub mod sanity;
+pub mod scales;
+pub mod structures;
+pub mod spectral_budget;
 pub mod spectrum;
 pub mod tke;
+pub mod trim;
 pub mod vortex;
 pub mod wall;
@@ -0,0 +1,198 @@
+//! Term-by-term balance of the mean streamwise momentum equation.
+//!
+//! Coordinate convention (matches the rest of this crate):
+//!   array shape (nz, ny, nx): axis 0 = wall-normal z, axis 1 = spanwise y,
+//!   axis 2 = streamwise x; u = streamwise, v = spanwise, w = wall-normal.
+//!
+//! With the time mean ū_i and the fluctuation moments extracted from the
+//! stored totals, ⟨u'u_k'⟩ = ⟨u u_k⟩ − ū ū_k (same convention as
+//! `tke::stress_budget`), the steady x-momentum equation reads
+//!
+//!   ū∂ū/∂x + v̄∂ū/∂y + w̄∂ū/∂z + ∂⟨u'u'⟩/∂x + ∂⟨u'v'⟩/∂y + ∂⟨u'w'⟩/∂z
+//!       + ∂p̄/∂x − ν(∂²ū/∂x² + ∂²ū/∂y² + ∂²ū/∂z²) = 0.
+//!
+//! Every term is evaluated pointwise in (z, y, x) and spanwise-averaged at
+//! the end, so spanwise-coherent motion is kept in the y terms rather than
+//! being folded into the stresses.  The residual has the sign of
+//! `ns::rans_momentum_residual`'s x component.  The ∂/∂y terms need
+//! `ny ≥ 3` and are left zero otherwise (fully span-averaged files).
+
+use hdf5::Error;
+use ndarray::{Array1, ArrayD};
+
+use super::avg::avg_axis;
+use super::ns::{deriv1, deriv2};
+
+/// Spanwise-averaged terms of the mean x-momentum equation, each `(nz, nx)`,
+/// written as left-hand-side contributions except for the viscous terms.
+pub struct MomentumBudget {
+    /// Mean convection ū∂ū/∂x, v̄∂ū/∂y, w̄∂ū/∂z.
+    pub adv_x: ArrayD<f64>,
+    pub adv_y: ArrayD<f64>,
+    pub adv_z: ArrayD<f64>,
+    /// Reynolds-stress divergence ∂⟨u'u'⟩/∂x, ∂⟨u'v'⟩/∂y, ∂⟨u'w'⟩/∂z.
+    pub rs_x: ArrayD<f64>,
+    pub rs_y: ArrayD<f64>,
+    pub rs_z: ArrayD<f64>,
+    /// Mean pressure gradient ∂p̄/∂x (ρ = 1).
+    pub dpdx: ArrayD<f64>,
+    /// Viscous diffusion ν∂²ū/∂x², ν∂²ū/∂y², ν∂²ū/∂z².
+    pub visc_x: ArrayD<f64>,
+    pub visc_y: ArrayD<f64>,
+    pub visc_z: ArrayD<f64>,
+}
+
+impl MomentumBudget {
+    /// Residual adv + rs + ∂p̄/∂x − visc; ≈ 0 for converged statistics.
+    pub fn residual(&self) -> ArrayD<f64> {
+        let lhs = [
+            &self.adv_x, &self.adv_y, &self.adv_z,
+            &self.rs_x, &self.rs_y, &self.rs_z,
+            &self.dpdx,
+        ];
+        let mut r = ArrayD::<f64>::zeros(self.dpdx.raw_dim());
+        for t in lhs {
+            r = &r + t;
+        }
+        &(&(&r - &self.visc_x) - &self.visc_y) - &self.visc_z
+    }
+}
+
+/// Compute the mean x-momentum budget from a subavg file's mean fields and
+/// stored total second moments.
+///
+/// `load` fetches a dataset by its subavg name (u, v, w, p, uu, uv, uw) as a
+/// 3-D `(nz, ny, nx)` array aligned with the coordinate lengths.
+pub fn momentum_budget(
+    load: &mut dyn FnMut(&str) -> Result<ArrayD<f64>, Error>,
+    x:  &Array1<f64>,
+    y:  &Array1<f64>,
+    zc: &Array1<f64>,
+    nu: f64,
+    periodic_x: bool,
+) -> Result<MomentumBudget, Error> {
+    let with_y = y.len() >= 3;
+    let u = load("u")?;
+    let v = load("v")?;
+    let w = load("w")?;
+    let zeros = ArrayD::<f64>::zeros(u.raw_dim());
+
+    let dudx = deriv1(&u, 2, x, periodic_x)?;
+    let dudz = deriv1(&u, 0, zc, false)?;
+    let adv_x = &u * &dudx;
+    let adv_z = &w * &dudz;
+
+    let uu = &load("uu")? - &(&u * &u);
+    let uw = &load("uw")? - &(&u * &w);
+    let rs_x = deriv1(&uu, 2, x, periodic_x)?;
+    let rs_z = deriv1(&uw, 0, zc, false)?;
+
+    let dpdx = deriv1(&load("p")?, 2, x, periodic_x)?;
+    let visc_x = deriv2(&u, 2, x, periodic_x)? * nu;
+    let visc_z = deriv2(&u, 0, zc, false)? * nu;
+
+    let (adv_y, rs_y, visc_y) = if with_y {
+        let uv = &load("uv")? - &(&u * &v);
+        (
+            &v * &deriv1(&u, 1, y, true)?,
+            deriv1(&uv, 1, y, true)?,
+            deriv2(&u, 1, y, true)? * nu,
+        )
+    } else {
+        (zeros.clone(), zeros.clone(), zeros)
+    };
+
+    // ── spanwise average every term → (nz, nx) ────────────────────────────────
+    let avg = |a: &ArrayD<f64>| avg_axis(a, 1);
+    Ok(MomentumBudget {
+        adv_x:  avg(&adv_x)?,
+        adv_y:  avg(&adv_y)?,
+        adv_z:  avg(&adv_z)?,
+        rs_x:   avg(&rs_x)?,
+        rs_y:   avg(&rs_y)?,
+        rs_z:   avg(&rs_z)?,
+        dpdx:   avg(&dpdx)?,
+        visc_x: avg(&visc_x)?,
+        visc_y: avg(&visc_y)?,
+        visc_z: avg(&visc_z)?,
+    })
+}
+
+// ─── Tests ────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::Array3;
+    use std::collections::HashMap;
+
+    fn loader(fields: HashMap<&'static str, ArrayD<f64>>) -> impl FnMut(&str) -> Result<ArrayD<f64>, Error> {
+        move |name: &str| {
+            fields.get(name).cloned().ok_or_else(|| Error::from(format!("no dataset '{name}'")))
+        }
+    }
+
+    /// Plane Poiseuille flow ū = z(2 − z), p̄ = −2νx balances exactly
+    /// (second-order stencils are exact on quadratics), and an added
+    /// Reynolds shear stress ⟨u'w'⟩ = c·z shows up as ∂⟨u'w'⟩/∂z = c.
+    #[test]
+    fn poiseuille_balance_and_stress_divergence() {
+        let (nz, ny, nx) = (9, 4, 7);
+        let nu = 0.01;
+        let c = 0.3;
+        let x = Array1::linspace(0.0, 3.0, nx);
+        let y = Array1::linspace(0.0, 1.0, ny);
+        let zc = Array1::from_shape_fn(nz, |k| 2.0 * (k as f64 / (nz - 1) as f64).powf(1.3));
+
+        let f3 = |f: &dyn Fn(usize, usize) -> f64| Array3::from_shape_fn((nz, ny, nx), |(k, _, i)| f(k, i)).into_dyn();
+        let u = f3(&|k, _| zc[k] * (2.0 - zc[k]));
+        let zero = f3(&|_, _| 0.0);
+        let mut fields = HashMap::new();
+        fields.insert("uu", &u * &u);
+        fields.insert("uv", zero.clone());
+        fields.insert("uw", f3(&|k, _| c * zc[k]));
+        fields.insert("p", f3(&|_, i| -2.0 * nu * x[i]));
+        fields.insert("u", u);
+        fields.insert("v", zero.clone());
+        fields.insert("w", zero);
+
+        let mb = momentum_budget(&mut loader(fields), &x, &y, &zc, nu, false).unwrap();
+        let res = mb.residual();
+        // interior points: residual = ∂⟨u'w'⟩/∂z = c
+        for k in 1..nz - 1 {
+            for i in 0..nx {
+                assert!((mb.rs_z[[k, i]] - c).abs() < 1e-12);
+                assert!((mb.dpdx[[k, i]] + 2.0 * nu).abs() < 1e-12);
+                assert!((mb.visc_z[[k, i]] + 2.0 * nu).abs() < 1e-12, "visc_z = {}", mb.visc_z[[k, i]]);
+                assert!((res[[k, i]] - c).abs() < 1e-12, "residual = {}", res[[k, i]]);
+            }
+        }
+        assert!(mb.adv_x.iter().chain(mb.rs_x.iter()).all(|v| v.abs() < 1e-12));
+    }
+
+    /// Without a resolved spanwise direction the y terms are skipped.
+    #[test]
+    fn single_plane_skips_spanwise_terms() {
+        let (nz, ny, nx) = (5, 1, 6);
+        let x = Array1::linspace(0.0, 1.0, nx);
+        let y = Array1::zeros(ny);
+        let zc = Array1::linspace(0.1, 1.0, nz);
+        // ū = x: ū∂ū/∂x = x, the uv dataset is never requested
+        let u = Array3::from_shape_fn((nz, ny, nx), |(_, _, i)| x[i]).into_dyn();
+        let zero = ArrayD::<f64>::zeros(u.raw_dim());
+        let mut fields = HashMap::new();
+        fields.insert("uu", &u * &u);
+        fields.insert("uw", zero.clone());
+        fields.insert("p", zero.clone());
+        fields.insert("u", u);
+        fields.insert("v", zero.clone());
+        fields.insert("w", zero);
+
+        let mb = momentum_budget(&mut loader(fields), &x, &y, &zc, 1e-3, false).unwrap();
+        assert_eq!(mb.adv_x.shape(), &[nz, nx]);
+        for i in 0..nx {
+            assert!((mb.adv_x[[2, i]] - x[i]).abs() < 1e-12);
+        }
+        assert!(mb.adv_y.iter().chain(mb.rs_y.iter()).chain(mb.visc_y.iter()).all(|&v| v == 0.0));
+    }
+}
@@ -17,13 +17,37 @@
 use ndarray::{Array1, Array3, ArrayD, Axis};
 use hdf5::Error;
 
-/// First derivative of `f` along `axis` on a (possibly non-uniform) grid.
-///
-/// All points use second-order 3-point stencils (requires n >= 3):
+use super::deriv::scheme;
+
+/// First derivative of `f` along `axis` on a (possibly non-uniform) grid,
+/// with the scheme selected by `deriv::set_scheme` (default: the
+/// second-order stencils of [`deriv1_central`]).
+pub fn deriv1(
+    f: &ArrayD<f64>,
+    axis: usize,
+    coords: &Array1<f64>,
+    periodic: bool,
+) -> Result<ArrayD<f64>, Error> {
+    scheme().deriv1(f, axis, coords, periodic)
+}
+
+/// Second derivative of `f` along `axis` on a (possibly non-uniform) grid,
+/// with the scheme selected by `deriv::set_scheme` (default: the
+/// second-order stencils of [`deriv2_central`]).
+pub fn deriv2(
+    f: &ArrayD<f64>,
+    axis: usize,
+    coords: &Array1<f64>,
+    periodic: bool,
+) -> Result<ArrayD<f64>, Error> {
+    scheme().deriv2(f, axis, coords, periodic)
+}
+
+/// First derivative with second-order 3-point stencils (requires n >= 3):
 /// * Interior points   – central difference (Lagrange).
 /// * `periodic = true` – boundary points wrap around (uniform spacing assumed).
 /// * `periodic = false`– boundary points use a second-order one-sided stencil.
-pub fn deriv1(
+pub fn deriv1_central(
     f: &ArrayD<f64>,
     axis: usize,
     coords: &Array1<f64>,
@@ -111,12 +135,12 @@ pub fn deriv1(
     Ok(df)
 }
 
-/// Second derivative of `f` along `axis` on a (possibly non-uniform) grid.
+/// Second derivative with second-order 3-point stencils.
 ///
 /// * `periodic = false` – one-sided 3-point Lagrange stencil at the boundaries
 ///   (requires n >= 3).
 /// * `periodic = true`  – wraps around at boundaries, uniform spacing assumed.
-pub fn deriv2(
+pub fn deriv2_central(
     f: &ArrayD<f64>,
     axis: usize,
     coords: &Array1<f64>,
@@ -416,6 +440,9 @@ pub fn ns_residual_fortran_stencil(
     if nxf < nx + 1 {
         return Err("u x-dimension must be nx+1 (one right ghost cell)".into());
     }
+    if nz < 4 {
+        return Err(format!("need at least 4 z-faces, got {nz}").into());
+    }
     if zc.len() != nz || zd.len() != 2 * (nz - 1) + 1 {
         return Err(format!(
             "zc must have {} entries, zd must have {} entries; got {} and {}",
@@ -457,7 +484,7 @@ pub fn ns_residual_fortran_stencil(
     // stored at index iz-1 → range 0..ncells-2
 
     // Interior output region: iz=1..273, iy=0..ny-1, ix=1..799
-    let nz_out = 273usize;   // iz = 1..273 (inclusive)
+    let nz_out = nz - 3;     // iz = 1..273 (inclusive) for nz = 276
     let nx_out = nx - 1;     // ix = 1..799
 
     let mut rx = Array3::<f64>::zeros((nz_out, ny, nx_out));
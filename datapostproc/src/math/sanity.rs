@@ -0,0 +1,94 @@
+//! Sanity checks of stored fields and coordinates, run before any
+//! statistics are formed from them.
+//!
+//! Along a periodic direction the files carry copies of interior columns
+//! beyond the `n` coordinate points:
+//!
+//!   n + 1  one outflow column, a copy of column 0 (inst files);
+//!   n + 2  one ghost on each side, column 0 ≡ column n and column n + 1 ≡
+//!          column 1 (subavg files).
+//!
+//! [`ghost_mismatch`] measures how far the copies are from the columns they
+//! mirror, relative to the largest |value| of the field.
+
+use hdf5::Error;
+use ndarray::{Array1, ArrayD, Axis};
+
+/// Numbers of NaN and ±Inf entries.
+pub fn count_nonfinite(a: &ArrayD<f64>) -> (usize, usize) {
+    a.iter().fold((0, 0), |(nan, inf), v| {
+        (nan + v.is_nan() as usize, inf + v.is_infinite() as usize)
+    })
+}
+
+/// Whether the coordinate is strictly increasing.
+pub fn is_increasing(c: &Array1<f64>) -> bool {
+    c.windows(2).into_iter().all(|w| w[1] > w[0])
+}
+
+/// Largest relative deviation of the spacing from its mean,
+/// max |Δc_i − ⟨Δc⟩| / ⟨Δc⟩.
+pub fn spacing_deviation(c: &Array1<f64>) -> f64 {
+    let n = c.len();
+    if n < 3 {
+        return 0.0;
+    }
+    let mean = (c[n - 1] - c[0]) / (n - 1) as f64;
+    c.windows(2).into_iter().fold(0.0f64, |m, w| m.max(((w[1] - w[0]) - mean).abs())) / mean.abs()
+}
+
+/// Relative mismatch of the periodic copies along `axis` for `n` coordinate
+/// points; `None` when the field carries no extra columns.
+pub fn ghost_mismatch(a: &ArrayD<f64>, axis: usize, n: usize) -> Result<Option<f64>, Error> {
+    let len = a.shape()[axis];
+    let pairs: &[(usize, usize)] = match len.checked_sub(n) {
+        Some(0) => return Ok(None),
+        Some(1) => &[(n, 0)],
+        Some(2) => &[(0, n), (n + 1, 1)],
+        _ => return Err(format!("ghost_mismatch: extent {len} along axis {axis} does not fit {n} points").into()),
+    };
+    let scale = a.iter().fold(0.0f64, |m, v| m.max(v.abs()));
+    let mut diff = 0.0f64;
+    for &(g, i) in pairs {
+        let d = &a.index_axis(Axis(axis), g) - &a.index_axis(Axis(axis), i);
+        diff = d.iter().fold(diff, |m, v| m.max(v.abs()));
+    }
+    Ok(Some(if scale > 0.0 { diff / scale } else { diff }))
+}
+
+// ─── Tests ────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::Array3;
+
+    #[test]
+    fn nonfinite_and_coordinates() {
+        let mut a = Array3::<f64>::zeros((2, 2, 2)).into_dyn();
+        a[[0, 0, 0]] = f64::NAN;
+        a[[1, 1, 1]] = f64::NEG_INFINITY;
+        assert_eq!(count_nonfinite(&a), (1, 1));
+        let y = Array1::linspace(0.0, 1.0, 9);
+        assert!(is_increasing(&y) && spacing_deviation(&y) < 1e-12);
+        let z = Array1::from_vec(vec![0.0, 0.1, 0.3, 0.3]);
+        assert!(!is_increasing(&z));
+        assert!((spacing_deviation(&Array1::from_vec(vec![0.0, 1.0, 3.0])) - 1.0 / 3.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn ghost_columns() {
+        let n = 6;
+        let col = |i: usize| ((i % n) as f64 * 1.3).sin() + 2.0;
+        // subavg layout: ghosts 0 and n + 1 mirror columns n and 1
+        let sub = Array3::from_shape_fn((2, 3, n + 2), |(_, _, i)| col((i + n - 1) % n)).into_dyn();
+        assert!(ghost_mismatch(&sub, 2, n).unwrap().unwrap() < 1e-15);
+        // inst layout: outflow column n copies column 0, here off by 0.3
+        let mut inst = Array3::from_shape_fn((2, 3, n + 1), |(_, _, i)| col(i)).into_dyn();
+        inst[[1, 2, n]] += 0.3;
+        let max = inst.iter().fold(0.0f64, |m, v| m.max(v.abs()));
+        assert!((ghost_mismatch(&inst, 2, n).unwrap().unwrap() - 0.3 / max).abs() < 1e-12);
+        assert_eq!(ghost_mismatch(&inst, 2, n + 1).unwrap(), None);
+        assert!(ghost_mismatch(&inst, 2, n - 2).is_err());
+    }
+}
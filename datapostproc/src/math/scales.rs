@@ -0,0 +1,450 @@
+//! Turbulence length scales and grid-resolution diagnostics as functions of
+//! (z, x).
+//!
+//! Coordinate convention (matches the rest of this crate):
+//!   Array shape (nz, ny, nx): axis 0 = wall-normal z, axis 1 = spanwise y,
+//!   axis 2 = streamwise x.
+//!
+//! From instantaneous snapshots ([`ScaleAccumulator`]), per (z, x):
+//!
+//!   integral scales   L_y = ∫₀^{r₀} R(Δy) dΔy   (R from [`SpanwiseSpectrum`],
+//!                                                one y-line per (z, x))
+//!                     L_x = ∫₀^{r₀} R(Δx) dΔx   (forward separations only)
+//!   Taylor microscales λ_x² = ⟨u'²⟩ / ⟨(∂u'/∂x)²⟩,  λ_y² = ⟨u'²⟩ / ⟨(∂u'/∂y)²⟩
+//!
+//! each integrated up to the first zero crossing r₀ of R.  From the
+//! dissipation ε formed by `tke::stress_budget` ([`kolmogorov_scales`]):
+//!
+//!   η = (ν³/ε)^{1/4},   τ_η = (ν/ε)^{1/2},   v_η = (νε)^{1/4}
+//!
+//! and the grid spacings Δx/η, Δy/η, Δz/η ([`grid_ratios`]).
+//!
+//! In wall units ([`wall_unit_grid`]) each station x uses its own friction
+//! velocity u_τ(x) = (ν ū₁/z₁)^{1/2} from the first off-wall point, so a
+//! control that raises the wall shear shows up as a locally coarser grid.
+//!
+//! [`SpanwiseSpectrum`]: super::spectrum::SpanwiseSpectrum
+
+use hdf5::Error;
+use ndarray::{s, Array1, Array2, Array3, ArrayD, Axis, Ix2, Ix3};
+
+use super::correlation::integral_scale;
+use super::ns::deriv1;
+use super::spectrum::SpanwiseSpectrum;
+
+/// Accumulator for integral length scales and Taylor microscales of one
+/// variable: feed it snapshots with [`accumulate`], then read out the
+/// `(nz, nx)` maps.
+///
+/// [`accumulate`]: ScaleAccumulator::accumulate
+pub struct ScaleAccumulator {
+    nz: usize,
+    ny: usize,
+    nx: usize,
+    /// Streamwise separations (in points) accumulated for R(Δx).
+    nsep: usize,
+    x: Array1<f64>,
+    y: Array1<f64>,
+    periodic_x: bool,
+    /// Spanwise spectrum with every (z, x) line as its own "height".
+    spanwise: SpanwiseSpectrum,
+    /// Σ u, Σ u² over y and snapshots, shape (nz, nx).
+    sum: Array2<f64>,
+    sum2: Array2<f64>,
+    /// Σ u(x) u(x + sΔ), shape (nz, nx, nsep); only x + s < nx is filled.
+    lag: Array3<f64>,
+    /// Σ ∂u/∂x, Σ (∂u/∂x)², Σ ∂u/∂y, Σ (∂u/∂y)², shape (nz, nx).
+    dx1: Array2<f64>,
+    dx2: Array2<f64>,
+    dy1: Array2<f64>,
+    dy2: Array2<f64>,
+    /// Number of y-lines accumulated per (z, x) (ny × snapshots).
+    lines: usize,
+}
+
+impl ScaleAccumulator {
+    /// `x`, `y` — streamwise / spanwise coordinates (y uniform, periodic),
+    /// `nz` — wall-normal points, `nsep` — streamwise separations (points)
+    /// kept for R(Δx).
+    pub fn new(
+        nz: usize,
+        x: &Array1<f64>,
+        y: &Array1<f64>,
+        nsep: usize,
+        periodic_x: bool,
+    ) -> Result<Self, Error> {
+        let (ny, nx) = (y.len(), x.len());
+        if ny < 3 || nx < 3 {
+            return Err(format!("grid ({ny}, {nx}) too small for scale estimates").into());
+        }
+        if nsep < 2 {
+            return Err(format!("need at least 2 streamwise separations, got {nsep}").into());
+        }
+        let ly = (y[1] - y[0]) * ny as f64;
+        let nsep = nsep.min(nx);
+        Ok(Self {
+            nz,
+            ny,
+            nx,
+            nsep,
+            x: x.clone(),
+            y: y.clone(),
+            periodic_x,
+            spanwise: SpanwiseSpectrum::new(nz * nx, ny, ly)?,
+            sum: Array2::zeros((nz, nx)),
+            sum2: Array2::zeros((nz, nx)),
+            lag: Array3::zeros((nz, nx, nsep)),
+            dx1: Array2::zeros((nz, nx)),
+            dx2: Array2::zeros((nz, nx)),
+            dy1: Array2::zeros((nz, nx)),
+            dy2: Array2::zeros((nz, nx)),
+            lines: 0,
+        })
+    }
+
+    /// Add one snapshot, shape `(nz, ny, nx)`.
+    pub fn accumulate(&mut self, u: &ArrayD<f64>) -> Result<(), Error> {
+        let sh = u.shape();
+        if sh != [self.nz, self.ny, self.nx] {
+            return Err(format!(
+                "snapshot shape {sh:?} incompatible with ({}, {}, {})",
+                self.nz, self.ny, self.nx
+            )
+            .into());
+        }
+        // (nz, ny, nx) → (nz·nx, ny, 1): every (z, x) line is one spectrum row
+        let lines = u
+            .view()
+            .permuted_axes(vec![0, 2, 1])
+            .as_standard_layout()
+            .into_shape_with_order(ndarray::IxDyn(&[self.nz * self.nx, self.ny, 1]))
+            .map_err(|e| Error::from(e.to_string().as_str()))?
+            .to_owned();
+        self.spanwise.accumulate(&lines)?;
+
+        let u3 = u.view().into_dimensionality::<Ix3>().unwrap();
+        self.sum += &u3.sum_axis(Axis(1));
+        self.sum2 += &u3.mapv(|v| v * v).sum_axis(Axis(1));
+        for sep in 0..self.nsep {
+            let a = u3.slice(s![.., .., ..self.nx - sep]);
+            let b = u3.slice(s![.., .., sep..]);
+            let prod = (&a * &b).sum_axis(Axis(1));
+            let mut dst = self.lag.slice_mut(s![.., ..self.nx - sep, sep]);
+            dst += &prod;
+        }
+
+        let sum_y = |f: &ArrayD<f64>, sq: bool| -> Array2<f64> {
+            let f3 = f.view().into_dimensionality::<Ix3>().unwrap();
+            if sq {
+                f3.mapv(|v| v * v).sum_axis(Axis(1))
+            } else {
+                f3.sum_axis(Axis(1))
+            }
+        };
+        let dudx = deriv1(u, 2, &self.x, self.periodic_x)?;
+        self.dx1 += &sum_y(&dudx, false);
+        self.dx2 += &sum_y(&dudx, true);
+        let dudy = deriv1(u, 1, &self.y, true)?;
+        self.dy1 += &sum_y(&dudy, false);
+        self.dy2 += &sum_y(&dudy, true);
+
+        self.lines += self.ny;
+        Ok(())
+    }
+
+    /// Number of y-lines accumulated so far per (z, x).
+    pub fn samples(&self) -> usize {
+        self.lines
+    }
+
+    fn check_samples(&self) -> Result<(), Error> {
+        if self.lines == 0 {
+            return Err("no snapshots accumulated".into());
+        }
+        Ok(())
+    }
+
+    /// Fluctuation variance ⟨u'²⟩ about the span + ensemble mean, `(nz, nx)`.
+    pub fn variance(&self) -> Result<Array2<f64>, Error> {
+        self.check_samples()?;
+        let n = self.lines as f64;
+        Ok(&self.sum2 / n - &(&self.sum / n).mapv(|m| m * m))
+    }
+
+    /// Integral length scales `(L_x, L_y)`, each of shape `(nz, nx)`.
+    pub fn integral_scales(&self) -> Result<(Array2<f64>, Array2<f64>), Error> {
+        let var = self.variance()?;
+        let n = self.lines as f64;
+        let mean = &self.sum / n;
+
+        // spanwise: one correlation row per (z, x) line
+        let (r, rho) = self.spanwise.correlation()?;
+        let mut ly = Array2::zeros((self.nz, self.nx));
+        for ((iz, ix), l) in ly.indexed_iter_mut() {
+            *l = integral_scale(r.view(), rho.row(iz * self.nx + ix));
+        }
+
+        // streamwise: forward separations from each x
+        let mut lx = Array2::zeros((self.nz, self.nx));
+        for ((iz, ix), l) in lx.indexed_iter_mut() {
+            let ns = self.nsep.min(self.nx - ix);
+            if ns < 2 || var[[iz, ix]] <= 0.0 {
+                continue;
+            }
+            let sep = Array1::from_iter((0..ns).map(|k| self.x[ix + k] - self.x[ix]));
+            let rx = Array1::from_iter((0..ns).map(|k| {
+                let cov = self.lag[[iz, ix, k]] / n - mean[[iz, ix]] * mean[[iz, ix + k]];
+                let v = var[[iz, ix]] * var[[iz, ix + k]];
+                if v > 0.0 { cov / v.sqrt() } else { 0.0 }
+            }));
+            *l = integral_scale(sep.view(), rx.view());
+        }
+        Ok((lx, ly))
+    }
+
+    /// Taylor microscales `(λ_x, λ_y)`, each of shape `(nz, nx)`; zero where
+    /// the fluctuating gradient variance vanishes.
+    pub fn taylor_microscales(&self) -> Result<(Array2<f64>, Array2<f64>), Error> {
+        let var = self.variance()?;
+        let n = self.lines as f64;
+        let micro = |g1: &Array2<f64>, g2: &Array2<f64>| -> Array2<f64> {
+            let gvar = g2 / n - &(g1 / n).mapv(|m| m * m);
+            let mut lam = Array2::zeros(var.raw_dim());
+            for ((l, &v), &g) in lam.iter_mut().zip(var.iter()).zip(gvar.iter()) {
+                if g > 0.0 && v > 0.0 {
+                    *l = (v / g).sqrt();
+                }
+            }
+            lam
+        };
+        Ok((micro(&self.dx1, &self.dx2), micro(&self.dy1, &self.dy2)))
+    }
+}
+
+/// Kolmogorov length, time and velocity scales, each of the shape of `eps`.
+pub struct KolmogorovScales {
+    /// η = (ν³/ε)^{1/4}.
+    pub eta: ArrayD<f64>,
+    /// τ_η = (ν/ε)^{1/2}.
+    pub tau: ArrayD<f64>,
+    /// v_η = (νε)^{1/4}.
+    pub vel: ArrayD<f64>,
+}
+
+/// Kolmogorov scales from the (positive) dissipation ε, e.g. the
+/// `visc_diss` term of the TKE budget.  Points with ε ≤ 0 (unconverged or
+/// outside the flow) are set to NaN so they stand out in the output.
+pub fn kolmogorov_scales(eps: &ArrayD<f64>, nu: f64) -> Result<KolmogorovScales, Error> {
+    if nu <= 0.0 {
+        return Err(format!("nu = {nu} must be positive").into());
+    }
+    let f = |g: &dyn Fn(f64) -> f64| eps.mapv(|e| if e > 0.0 { g(e) } else { f64::NAN });
+    Ok(KolmogorovScales {
+        eta: f(&|e| (nu * nu * nu / e).powf(0.25)),
+        tau: f(&|e| (nu / e).sqrt()),
+        vel: f(&|e| (nu * e).powf(0.25)),
+    })
+}
+
+/// Local grid spacing along a coordinate: central half-differences inside,
+/// one-sided at the ends.
+fn spacing(c: &Array1<f64>) -> Array1<f64> {
+    let n = c.len();
+    Array1::from_iter((0..n).map(|i| {
+        let lo = if i > 0 { i - 1 } else { i };
+        let hi = if i + 1 < n { i + 1 } else { i };
+        (c[hi] - c[lo]) / (hi - lo) as f64
+    }))
+}
+
+/// Grid spacings in Kolmogorov units `[Δx/η, Δy/η, Δz/η]` for an `(nz, nx)`
+/// field `eta`; Δx and Δz are the local spacings of `x` and `z`, Δy the
+/// uniform spanwise spacing.
+pub fn grid_ratios(
+    eta: &ArrayD<f64>,
+    x: &Array1<f64>,
+    y: &Array1<f64>,
+    z: &Array1<f64>,
+) -> Result<[Array2<f64>; 3], Error> {
+    let eta = eta
+        .view()
+        .into_dimensionality::<Ix2>()
+        .map_err(|_| Error::from("eta must be a (nz, nx) field"))?;
+    if eta.dim() != (z.len(), x.len()) {
+        return Err(format!(
+            "eta shape {:?} does not match (nz, nx) = ({}, {})",
+            eta.dim(),
+            z.len(),
+            x.len()
+        )
+        .into());
+    }
+    if y.len() < 2 {
+        return Err("need at least 2 spanwise points".into());
+    }
+    let (dx, dz, dy) = (spacing(x), spacing(z), y[1] - y[0]);
+    Ok([
+        Array2::from_shape_fn(eta.dim(), |(k, i)| dx[i] / eta[[k, i]]),
+        eta.mapv(|e| dy / e),
+        Array2::from_shape_fn(eta.dim(), |(k, i)| dz[k] / eta[[k, i]]),
+    ])
+}
+
+/// Stretching ratio Δ_k / Δ_{k−1} of consecutive spacings at each interior
+/// point of a coordinate; 1 at the ends.
+pub fn stretching(c: &Array1<f64>) -> Array1<f64> {
+    let n = c.len();
+    Array1::from_shape_fn(n, |k| {
+        if k == 0 || k + 1 >= n {
+            1.0
+        } else {
+            (c[k + 1] - c[k]) / (c[k] - c[k - 1])
+        }
+    })
+}
+
+/// Per-station grid resolution in wall units, each of length nx.
+pub struct WallGrid {
+    /// Local friction velocity u_τ(x).
+    pub utau: Array1<f64>,
+    /// Δx⁺ of the local streamwise spacing.
+    pub dx: Array1<f64>,
+    /// Δy⁺ of the uniform spanwise spacing.
+    pub dy: Array1<f64>,
+    /// Δz⁺ of the first cell, wall to its upper face.
+    pub dz_wall: Array1<f64>,
+    /// Δz⁺ at the point nearest the centreline z = h.
+    pub dz_center: Array1<f64>,
+    /// z⁺ of the first off-wall velocity point.
+    pub z1: Array1<f64>,
+    /// Number of `zc` points with 0 < z⁺ ≤ 10.
+    pub n10: Array1<f64>,
+}
+
+/// Wall-unit resolution of the grid under the span-averaged (nz, nx) ū.
+///
+/// Wall points (z = 0) in `zc` are skipped.  With the dual grid `zd`
+/// (zd[2k] = zc[k] faces, zd[2k+1] cell centres) the first cell is
+/// [zd[0], zd[2]] with ū of row 0 at its centre zd[1]; without it, ū sits at
+/// the first off-wall `zc` point and the first cell spans from the wall to
+/// it.  τ_w = ν ū / z1 uses the same first-point distance z1 either way.
+pub fn wall_unit_grid(
+    u: &Array2<f64>,
+    x: &Array1<f64>,
+    y: &Array1<f64>,
+    zc: &Array1<f64>,
+    zd: Option<&Array1<f64>>,
+    nu: f64,
+    h: f64,
+) -> Result<WallGrid, Error> {
+    if u.dim() != (zc.len(), x.len()) {
+        return Err(format!("ū shape {:?} does not match (nz, nx) = ({}, {})", u.dim(), zc.len(), x.len()).into());
+    }
+    if y.len() < 2 || x.len() < 2 {
+        return Err("need at least 2 streamwise and 2 spanwise points".into());
+    }
+    let k1 = zc.iter().position(|&z| z > 0.0).ok_or_else(|| Error::from("zc has no off-wall point"))?;
+    let (ku, z1, dz1) = match zd {
+        Some(zd) if zd.len() >= 3 => (0, zd[1] - zd[0], zd[2] - zd[0]),
+        Some(zd) => return Err(format!("zd has {} points, need at least 3", zd.len()).into()),
+        None => (k1, zc[k1], zc[k1]),
+    };
+    let kc = (0..zc.len()).min_by(|&a, &b| (zc[a] - h).abs().total_cmp(&(zc[b] - h).abs())).unwrap();
+    let (dx, dz, dy) = (spacing(x), spacing(zc), y[1] - y[0]);
+    // τ ≤ 0 (separated or reversed flow) gives u_τ = 0, i.e. no constraint
+    let utau = u.row(ku).mapv(|v| (nu * v / z1).max(0.0).sqrt());
+    let plus = |len: f64| utau.mapv(|ut| len * ut / nu);
+    Ok(WallGrid {
+        dx: Array1::from_shape_fn(x.len(), |i| dx[i] * utau[i] / nu),
+        dy: plus(dy),
+        dz_wall: plus(dz1),
+        dz_center: plus(dz[kc]),
+        z1: plus(z1),
+        n10: utau.mapv(|ut| zc.iter().filter(|&&z| z > 0.0 && z * ut / nu <= 10.0).count() as f64),
+        utau,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PI: f64 = std::f64::consts::PI;
+
+    /// u = cos(2πj/N) cos(k x) + sin(2πj/N) sin(k x) = cos(k x − φ_j):
+    /// R(Δx) = cos(kΔx) so L_x = 1/k (away from the outlet), R(Δy) =
+    /// cos(2πΔy/L_y) so L_y = L_y/(2π), and λ_x = 1/k up to the stencil error.
+    #[test]
+    fn traveling_wave_scales() {
+        let (nz, ny, nx) = (2, 32, 400);
+        let k = 2.0;
+        let x = Array1::from_iter((0..nx).map(|i| 0.01 * i as f64));
+        let ly = 3.0;
+        let y = Array1::from_iter((0..ny).map(|j| ly * j as f64 / ny as f64));
+        let u = Array3::from_shape_fn((nz, ny, nx), |(_, j, i)| {
+            (k * x[i] - 2.0 * PI * j as f64 / ny as f64).cos()
+        })
+        .into_dyn();
+
+        let mut acc = ScaleAccumulator::new(nz, &x, &y, 120, false).unwrap();
+        acc.accumulate(&u).unwrap();
+        let var = acc.variance().unwrap();
+        assert!((var[[0, 50]] - 0.5).abs() < 1e-12);
+
+        let (lx, lyy) = acc.integral_scales().unwrap();
+        assert!((lx[[1, 10]] - 1.0 / k).abs() < 1e-3, "L_x = {}", lx[[1, 10]]);
+        let expected_ly = ly / (2.0 * PI);
+        assert!((lyy[[0, 7]] - expected_ly).abs() < 5e-3 * expected_ly, "L_y = {}", lyy[[0, 7]]);
+
+        let (lam_x, _) = acc.taylor_microscales().unwrap();
+        assert!((lam_x[[0, 200]] - 1.0 / k).abs() < 1e-3, "λ_x = {}", lam_x[[0, 200]]);
+    }
+
+    /// η τ_η⁻¹ = v_η and η v_η / ν = 1 by construction; ε ≤ 0 gives NaN.
+    #[test]
+    fn kolmogorov_identities_and_grid_ratios() {
+        let nu = 1e-3;
+        let eps = Array2::from_shape_fn((3, 4), |(k, i)| 0.1 * (k + i) as f64).into_dyn();
+        let ks = kolmogorov_scales(&eps, nu).unwrap();
+        assert!(ks.eta[[0, 0]].is_nan());
+        let (e, t, v) = (ks.eta[[2, 3]], ks.tau[[2, 3]], ks.vel[[2, 3]]);
+        assert!((e / t - v).abs() < 1e-12 * v);
+        assert!((e * v / nu - 1.0).abs() < 1e-12);
+
+        let x = Array1::from_vec(vec![0.0, 1.0, 3.0, 6.0]);
+        let y = Array1::from_vec(vec![0.0, 0.5]);
+        let z = Array1::from_vec(vec![0.1, 0.3, 0.7]);
+        let [rx, ry, rz] = grid_ratios(&ks.eta, &x, &y, &z).unwrap();
+        assert!((rx[[2, 2]] - 2.5 / ks.eta[[2, 2]]).abs() < 1e-9);
+        assert!((ry[[1, 1]] - 0.5 / ks.eta[[1, 1]]).abs() < 1e-9);
+        assert!((rz[[0, 3]] - 0.2 / ks.eta[[0, 3]]).abs() < 1e-9);
+    }
+
+    /// Linear near-wall ū = a z gives τ = ν a and u_τ = (ν a)^{1/2} at every
+    /// station; doubling a scales every Δ⁺ by √2.
+    #[test]
+    fn wall_units_follow_local_utau() {
+        let nu = 1e-4;
+        let x = Array1::from_vec(vec![0.0, 0.1, 0.2, 0.3]);
+        let y = Array1::from_vec(vec![0.0, 0.05, 0.1]);
+        let zc = Array1::from_vec(vec![0.0, 0.01, 0.03, 0.07, 0.15, 0.31, 0.6, 1.0, 1.4]);
+        let a = |i: usize| if i < 2 { 1.0 } else { 2.0 };
+        let u = Array2::from_shape_fn((zc.len(), x.len()), |(k, i)| a(i) * zc[k]);
+        let g = wall_unit_grid(&u, &x, &y, &zc, None, nu, 1.0).unwrap();
+        let ut0 = (nu * 1.0f64).sqrt();
+        assert!((g.utau[0] - ut0).abs() < 1e-12);
+        assert!((g.dy[0] - 0.05 * ut0 / nu).abs() < 1e-9);
+        assert!((g.z1[3] / g.z1[0] - 2f64.sqrt()).abs() < 1e-12);
+        assert!((g.dz_center[0] - 0.4 * ut0 / nu).abs() < 1e-9);
+        // z⁺ = 1, 3, 7, 15 at u_τ = 0.01; z = 0.07 stays at 9.9 for √2 u_τ
+        assert_eq!((g.n10[0], g.n10[3]), (3.0, 3.0));
+        // staggered: row 0 of ū sits at the first cell centre zd[1] = 0.005
+        let zd = Array1::from_vec(vec![0.0, 0.005, 0.01]);
+        let ud = Array2::from_shape_fn((zc.len(), x.len()), |(k, i)| a(i) * if k == 0 { 0.005 } else { zc[k] });
+        let gd = wall_unit_grid(&ud, &x, &y, &zc, Some(&zd), nu, 1.0).unwrap();
+        assert!((gd.utau[0] - ut0).abs() < 1e-12 && (gd.utau[3] - 2f64.sqrt() * ut0).abs() < 1e-12);
+        assert!((gd.z1[0] - 0.5).abs() < 1e-9 && (gd.dz_wall[0] - 1.0).abs() < 1e-9);
+        let r = stretching(&zc);
+        assert!((r[2] - 2.0).abs() < 1e-12 && r[0] == 1.0);
+    }
+}
@@ -0,0 +1,531 @@
+//! Scale-by-scale (spanwise-wavenumber) budget of the normal Reynolds
+//! stresses and of the TKE, from instantaneous snapshots.
+//!
+//! Coordinate convention (matches the rest of this crate):
+//!   array shape (nz, ny, nx): axis 0 = wall-normal z, axis 1 = spanwise y,
+//!   axis 2 = streamwise x; u = streamwise, v = spanwise, w = wall-normal.
+//!
+//! Fluctuations are taken about the span + ensemble mean,
+//! u_i' = u_i − ū_i(z, x), so the k_y = 0 mode keeps the spanwise-uniform
+//! unsteadiness (unlike `spectrum::SpanwiseSpectrum`, which removes the mean
+//! of every line).  The mean must therefore be known before the first
+//! snapshot is accumulated.
+//!
+//! With â the DFT of a y-line and the one-sided weights w_m of
+//! `math::spectrum`, the co-spectrum C_ab(k_m) = w_m Re⟨â*_m b̂_m⟩ / N²
+//! sums over m to the spanwise mean ⟨a b⟩ (Parseval).  For each normal
+//! component α (no summation over α):
+//!
+//!   energy      E_αα(k)  = C(u_α', u_α')
+//!   production  P_αα(k)  = −2 Σ_j C(u_α', u_j') ∂ū_α/∂x_j      (j = x, z)
+//!   dissipation ε_αα(k)  = 2ν Σ_j C(∂u_α'/∂x_j, ∂u_α'/∂x_j)
+//!   press.-strain Π_αα(k) = 2 C(p', ∂u_α'/∂x_α)                 (ρ = 1)
+//!   transfer    T_αα(k)  = −2 C(u_α', u_j' ∂u_α'/∂x_j)
+//!
+//! and the TKE terms are half the trace.  T(k) redistributes energy between
+//! spanwise scales.  By Parseval each term sums over k exactly to the
+//! spanwise mean of its physical-space product; for T that is
+//! −2⟨u_α' u_j' ∂u_α'/∂x_j⟩, which equals the turbulent transport
+//! −∂⟨u_α'u_α'u_j'⟩/∂x_j of `tke::stress_budget` only through continuity and
+//! the product rule.  Neither holds exactly for the discrete derivatives
+//! (`ns::deriv1`, also along y), so the two agree up to the truncation error
+//! of the stencils and the divergence error of the data;
+//! [`SpectralBudget::turbulent_transport`] forms the divergence form from the
+//! same snapshots to measure that gap.
+//!
+//! Lines are ensemble-averaged over the x points of each streamwise window
+//! and over the snapshots.
+
+use hdf5::Error;
+use ndarray::{
+    s, Array, Array1, Array2, Array3, Array4, ArrayD, ArrayView1, ArrayView3, Axis, Dimension, Ix3, Ix4,
+};
+use rustfft::{num_complex::Complex, Fft, FftPlanner};
+use std::sync::Arc;
+
+use super::ns::deriv1;
+use super::vortex::velocity_gradient;
+
+/// Spectral (or physical-space) budget terms.  Component axis: ⟨u'u'⟩,
+/// ⟨v'v'⟩, ⟨w'w'⟩, k.
+pub struct SpectralTerms<D: Dimension> {
+    pub energy: Array<f64, D>,
+    pub prod: Array<f64, D>,
+    pub diss: Array<f64, D>,
+    pub pstrain: Array<f64, D>,
+    pub transfer: Array<f64, D>,
+}
+
+impl<D: Dimension> SpectralTerms<D> {
+    /// `(name, term)` pairs in a fixed order, for output.
+    pub fn named(&self) -> [(&'static str, &Array<f64, D>); 5] {
+        [
+            ("energy", &self.energy),
+            ("prod", &self.prod),
+            ("diss", &self.diss),
+            ("pstrain", &self.pstrain),
+            ("transfer", &self.transfer),
+        ]
+    }
+}
+
+/// Accumulator for the spanwise-wavenumber budget over streamwise windows:
+/// feed it snapshots with [`accumulate`], then read out the spectral
+/// densities or their physical-space sums.
+///
+/// [`accumulate`]: SpectralBudget::accumulate
+pub struct SpectralBudget {
+    nz: usize,
+    ny: usize,
+    nk: usize,
+    ly: f64,
+    nu: f64,
+    x: Array1<f64>,
+    y: Array1<f64>,
+    z: Array1<f64>,
+    periodic_x: bool,
+    windows: Vec<(usize, usize)>,
+    /// ū, v̄, w̄, p̄ shaped (nz, 1, nx) for broadcasting.
+    mean: [ArrayD<f64>; 4],
+    /// ∂ū_α/∂x and ∂ū_α/∂z, each (nz, nx).
+    dmean: [[Array2<f64>; 2]; 3],
+    /// Weighted co-spectra summed over lines, (nwin, 3, nz, nk), in the order
+    /// of [`SpectralTerms::named`].
+    spec: [Array4<f64>; 5],
+    /// Directly computed spanwise means summed over lines, (nwin, 3, nz).
+    phys: [Array3<f64>; 5],
+    /// Spanwise means ⟨u_α'u_α'u_x'⟩ and ⟨u_α'u_α'u_z'⟩ summed over
+    /// snapshots, each (nz, nx).
+    triple: [[Array2<f64>; 2]; 3],
+    snapshots: usize,
+    fft: Arc<dyn Fft<f64>>,
+}
+
+impl SpectralBudget {
+    /// `mean` — span + ensemble means ū, v̄, w̄, p̄, each `(nz, nx)`;
+    /// `x`, `y`, `z` — coordinates (y uniform and periodic);
+    /// `windows` — half-open streamwise index ranges to average over.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        mean: [&ArrayD<f64>; 4],
+        x: &Array1<f64>,
+        y: &Array1<f64>,
+        z: &Array1<f64>,
+        nu: f64,
+        windows: &[(usize, usize)],
+        periodic_x: bool,
+    ) -> Result<Self, Error> {
+        let (nz, ny, nx) = (z.len(), y.len(), x.len());
+        if ny < 3 {
+            return Err(format!("ny = {ny} too small for a spanwise budget").into());
+        }
+        if mean.iter().any(|m| m.shape() != [nz, nx]) {
+            return Err(format!("mean fields must be (nz, nx) = ({nz}, {nx})").into());
+        }
+        if windows.is_empty() || windows.iter().any(|&(i0, i1)| i0 >= i1 || i1 > nx) {
+            return Err(format!("invalid x windows {windows:?} (nx = {nx})").into());
+        }
+        let ly = (y[1] - y[0]) * ny as f64;
+        let nk = ny / 2 + 1;
+
+        let to2 = |a: ArrayD<f64>| a.into_dimensionality::<ndarray::Ix2>().unwrap();
+        let grad = |m: &ArrayD<f64>| -> Result<[Array2<f64>; 2], Error> {
+            Ok([to2(deriv1(m, 1, x, periodic_x)?), to2(deriv1(m, 0, z, false)?)])
+        };
+        let dmean = [grad(mean[0])?, grad(mean[1])?, grad(mean[2])?];
+        let nwin = windows.len();
+
+        Ok(Self {
+            nz,
+            ny,
+            nk,
+            ly,
+            nu,
+            x: x.clone(),
+            y: y.clone(),
+            z: z.clone(),
+            periodic_x,
+            windows: windows.to_vec(),
+            mean: mean.map(|m| m.clone().insert_axis(Axis(1))),
+            dmean,
+            spec: std::array::from_fn(|_| Array4::zeros((nwin, 3, nz, nk))),
+            phys: std::array::from_fn(|_| Array3::zeros((nwin, 3, nz))),
+            triple: std::array::from_fn(|_| std::array::from_fn(|_| Array2::zeros((nz, nx)))),
+            snapshots: 0,
+            fft: FftPlanner::new().plan_fft_forward(ny),
+        })
+    }
+
+    /// Add one snapshot of u, v, w, p, each `(nz, ny, nx)`.
+    pub fn accumulate(
+        &mut self,
+        u: &ArrayD<f64>,
+        v: &ArrayD<f64>,
+        w: &ArrayD<f64>,
+        p: &ArrayD<f64>,
+    ) -> Result<(), Error> {
+        let shape = [self.nz, self.ny, self.x.len()];
+        if [u, v, w, p].iter().any(|f| f.shape() != shape) {
+            return Err(format!("snapshot fields must be {shape:?}").into());
+        }
+        let up = [u - &self.mean[0], v - &self.mean[1], w - &self.mean[2]];
+        let pp = p - &self.mean[3];
+        // the spanwise mean of ∂/∂y of a periodic line vanishes: only the x
+        // and z fluxes enter the turbulent transport
+        for (a, t) in self.triple.iter_mut().enumerate() {
+            let aa = &up[a] * &up[a];
+            for (tj, uj) in t.iter_mut().zip([&up[0], &up[2]]) {
+                *tj += &(&aa * uj).mean_axis(Axis(1)).unwrap().into_dimensionality::<ndarray::Ix2>().unwrap();
+            }
+        }
+        let g = velocity_gradient(
+            &up[0], &up[1], &up[2], &self.x, &self.y, &self.z,
+            [false, true, self.periodic_x],
+        )?
+        .g;
+        // nonlinear term u_j' ∂u_α'/∂x_j
+        let nl: [ArrayD<f64>; 3] = std::array::from_fn(|a| {
+            let mut n = &up[0] * &g[a][0];
+            n += &(&up[1] * &g[a][1]);
+            n += &(&up[2] * &g[a][2]);
+            n
+        });
+        fn view(a: &ArrayD<f64>) -> ArrayView3<'_, f64> {
+            a.view().into_dimensionality::<Ix3>().unwrap()
+        }
+        fn lane<'a>(a: &ArrayView3<'a, f64>, iz: usize, ix: usize) -> ArrayView1<'a, f64> {
+            (*a).slice_move(s![iz, .., ix])
+        }
+        let (upv, ppv) = (up.each_ref().map(view), view(&pp));
+        let nlv = nl.each_ref().map(view);
+        let gv = g.each_ref().map(|ga| ga.each_ref().map(view));
+
+        let (ny, nk) = (self.ny, self.nk);
+        let norm = 1.0 / (ny * ny) as f64;
+        let zero = Complex::new(0.0, 0.0);
+        let fft = self.fft.clone();
+        let transform = |line: ArrayView1<f64>, buf: &mut Vec<Complex<f64>>| {
+            for (b, &v) in buf.iter_mut().zip(line.iter()) {
+                *b = Complex::new(v, 0.0);
+            }
+            fft.process(buf);
+        };
+        let weights: Vec<f64> = (0..nk)
+            .map(|m| if m == 0 || (ny % 2 == 0 && m == nk - 1) { 1.0 } else { 2.0 })
+            .collect();
+        // w_m Re(â* b̂) / N² and its plain spanwise-mean counterpart
+        let co = |a: &[Complex<f64>], b: &[Complex<f64>], m: usize| weights[m] * (a[m].conj() * b[m]).re * norm;
+        let mean = |a: ArrayView1<f64>, b: ArrayView1<f64>| a.iter().zip(b.iter()).map(|(p, q)| p * q).sum::<f64>() / ny as f64;
+
+        let mut uh = vec![vec![zero; ny]; 3];
+        let mut nh = vec![vec![zero; ny]; 3];
+        let mut gh = vec![vec![vec![zero; ny]; 3]; 3];
+        let mut ph = vec![zero; ny];
+
+        for (iw, &(i0, i1)) in self.windows.iter().enumerate() {
+            for ix in i0..i1 {
+                for iz in 0..self.nz {
+                    let line = |a| lane(a, iz, ix);
+                    for a in 0..3 {
+                        transform(line(&upv[a]), &mut uh[a]);
+                        transform(line(&nlv[a]), &mut nh[a]);
+                        for j in 0..3 {
+                            transform(line(&gv[a][j]), &mut gh[a][j]);
+                        }
+                    }
+                    transform(line(&ppv), &mut ph);
+
+                    for a in 0..3 {
+                        let (dx, dz) = (self.dmean[a][0][[iz, ix]], self.dmean[a][1][[iz, ix]]);
+                        for m in 0..nk {
+                            let terms = [
+                                co(&uh[a], &uh[a], m),
+                                -2.0 * (co(&uh[a], &uh[0], m) * dx + co(&uh[a], &uh[2], m) * dz),
+                                2.0 * self.nu * gh[a].iter().map(|gj| co(gj, gj, m)).sum::<f64>(),
+                                2.0 * co(&ph, &gh[a][a], m),
+                                -2.0 * co(&uh[a], &nh[a], m),
+                            ];
+                            for (acc, t) in self.spec.iter_mut().zip(terms) {
+                                acc[[iw, a, iz, m]] += t;
+                            }
+                        }
+                        let (ua, u0, u2) = (line(&upv[a]), line(&upv[0]), line(&upv[2]));
+                        let terms = [
+                            mean(ua, ua),
+                            -2.0 * (mean(ua, u0) * dx + mean(ua, u2) * dz),
+                            2.0 * self.nu * (0..3).map(|j| mean(line(&gv[a][j]), line(&gv[a][j]))).sum::<f64>(),
+                            2.0 * mean(line(&ppv), line(&gv[a][a])),
+                            -2.0 * mean(ua, line(&nlv[a])),
+                        ];
+                        for (acc, t) in self.phys.iter_mut().zip(terms) {
+                            acc[[iw, a, iz]] += t;
+                        }
+                    }
+                }
+            }
+        }
+        self.snapshots += 1;
+        Ok(())
+    }
+
+    /// Number of snapshots accumulated so far.
+    pub fn samples(&self) -> usize {
+        self.snapshots
+    }
+
+    /// Spanwise wavenumbers k_m = 2π m / L_y, m = 0..=N/2.
+    pub fn wavenumbers(&self) -> Array1<f64> {
+        let dk = 2.0 * std::f64::consts::PI / self.ly;
+        Array1::from_iter((0..self.nk).map(|m| m as f64 * dk))
+    }
+
+    /// Ensemble-averaged spectral densities, each `(nwin, 4, nz, nk)`,
+    /// normalised so that Σ_m term[.., m] Δk equals [`physical`].
+    ///
+    /// [`physical`]: SpectralBudget::physical
+    pub fn densities(&self) -> Result<SpectralTerms<Ix4>, Error> {
+        self.check_samples()?;
+        let dk = 2.0 * std::f64::consts::PI / self.ly;
+        let [energy, prod, diss, pstrain, transfer] = self.spec.each_ref().map(|a| {
+            let mut out = Array4::zeros((self.windows.len(), 4, self.nz, self.nk));
+            self.finish(a.view().into_dyn(), out.view_mut().into_dyn(), dk);
+            out
+        });
+        Ok(SpectralTerms { energy, prod, diss, pstrain, transfer })
+    }
+
+    /// Ensemble-averaged physical-space terms, each `(nwin, 4, nz)`, computed
+    /// directly from the spanwise means of the products.
+    pub fn physical(&self) -> Result<SpectralTerms<Ix3>, Error> {
+        self.check_samples()?;
+        let [energy, prod, diss, pstrain, transfer] = self.phys.each_ref().map(|a| {
+            let mut out = Array3::zeros((self.windows.len(), 4, self.nz));
+            self.finish(a.view().into_dyn(), out.view_mut().into_dyn(), 1.0);
+            out
+        });
+        Ok(SpectralTerms { energy, prod, diss, pstrain, transfer })
+    }
+
+    /// Ensemble-averaged turbulent transport −∂⟨u_α'u_α'u_j'⟩/∂x_j in the
+    /// divergence form of `tke::stress_budget`, `(nwin, 4, nz)`; it differs
+    /// from Σ_k Δk `transfer` by the discretization error (module doc).
+    pub fn turbulent_transport(&self) -> Result<Array3<f64>, Error> {
+        self.check_samples()?;
+        let n = self.snapshots as f64;
+        let mut out = Array3::zeros((self.windows.len(), 4, self.nz));
+        for (a, [tx, tz]) in self.triple.iter().enumerate() {
+            let (tx, tz) = ((tx / n).into_dyn(), (tz / n).into_dyn());
+            let div = &deriv1(&tx, 1, &self.x, self.periodic_x)? + &deriv1(&tz, 0, &self.z, false)?;
+            for (iw, &(i0, i1)) in self.windows.iter().enumerate() {
+                let win = div.slice_axis(Axis(1), (i0..i1).into()).mean_axis(Axis(1)).unwrap();
+                out.slice_mut(s![iw, a, ..]).assign(&(-win));
+            }
+        }
+        for mut w in out.outer_iter_mut() {
+            let tke = w.slice(s![0..3, ..]).sum_axis(Axis(0)) * 0.5;
+            w.slice_mut(s![3, ..]).assign(&tke);
+        }
+        Ok(out)
+    }
+
+    /// Divide the per-window sums by the line count and `scale`, and append
+    /// the TKE (half the trace) as component 3.
+    fn finish(&self, sums: ndarray::ArrayViewD<f64>, mut out: ndarray::ArrayViewMutD<f64>, scale: f64) {
+        for (iw, &(i0, i1)) in self.windows.iter().enumerate() {
+            let lines = ((i1 - i0) * self.snapshots) as f64 * scale;
+            let src = sums.index_axis(Axis(0), iw);
+            let mut dst = out.index_axis_mut(Axis(0), iw);
+            for a in 0..3 {
+                dst.index_axis_mut(Axis(0), a).assign(&(&src.index_axis(Axis(0), a) / lines));
+            }
+            let tke = src.sum_axis(Axis(0)) / (2.0 * lines);
+            dst.index_axis_mut(Axis(0), 3).assign(&tke);
+        }
+    }
+
+    fn check_samples(&self) -> Result<(), Error> {
+        if self.snapshots == 0 {
+            return Err("no snapshots accumulated".into());
+        }
+        Ok(())
+    }
+}
+
+// ─── Tests ────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::PI;
+
+    fn grid(nz: usize, ny: usize, nx: usize) -> (Array1<f64>, Array1<f64>, Array1<f64>) {
+        let x = Array1::from_shape_fn(nx, |i| 0.3 * i as f64 + 0.02 * (i * i) as f64);
+        let y = Array1::from_shape_fn(ny, |j| j as f64 * 2.0 / ny as f64);
+        let z = Array1::from_shape_fn(nz, |k| (k as f64 / (nz - 1) as f64).powf(1.4));
+        (x, y, z)
+    }
+
+    /// Deterministic, broadband pseudo-random fields.
+    fn noisy(seed: u64, shape: (usize, usize, usize)) -> ArrayD<f64> {
+        let mut s = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        Array3::from_shape_simple_fn(shape, || {
+            s = s.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            (s >> 11) as f64 / (1u64 << 53) as f64 - 0.5
+        })
+        .into_dyn()
+    }
+
+    #[test]
+    fn wavenumber_sums_reproduce_physical_terms() {
+        let (nz, ny, nx) = (5, 8, 7);
+        let (x, y, z) = grid(nz, ny, nx);
+        let snaps: Vec<[ArrayD<f64>; 4]> = (0..3)
+            .map(|n| {
+                std::array::from_fn(|c| {
+                    let shear = Array3::from_shape_fn((nz, ny, nx), |(k, _, i)| (c == 0) as u8 as f64 * z[k] * (1.0 + x[i]));
+                    &noisy(10 * n + c as u64, (nz, ny, nx)) + &shear.into_dyn()
+                })
+            })
+            .collect();
+        let mean: [ArrayD<f64>; 4] = std::array::from_fn(|c| {
+            let s = snaps.iter().fold(ArrayD::zeros(snaps[0][c].raw_dim()), |a, f| a + &f[c]);
+            (s / snaps.len() as f64).mean_axis(Axis(1)).unwrap()
+        });
+
+        let windows = [(0, 3), (3, 7)];
+        let mut sb = SpectralBudget::new(mean.each_ref(), &x, &y, &z, 0.01, &windows, false).unwrap();
+        for [u, v, w, p] in &snaps {
+            sb.accumulate(u, v, w, p).unwrap();
+        }
+        let k = sb.wavenumbers();
+        let dk = k[1];
+        let dens = sb.densities().unwrap();
+        let phys = sb.physical().unwrap();
+        for ((name, d), (_, p)) in dens.named().into_iter().zip(phys.named()) {
+            assert_eq!(d.shape(), &[2, 4, nz, ny / 2 + 1]);
+            let sum = d.sum_axis(Axis(3)) * dk;
+            let scale = p.iter().fold(0.0f64, |a, v| a.max(v.abs()));
+            assert!(scale > 0.0, "{name} vanishes");
+            for (a, b) in sum.iter().zip(p.iter()) {
+                assert!((a - b).abs() < 1e-12 * scale, "{name}: Σ_k = {a}, physical = {b}");
+            }
+        }
+    }
+
+    /// On an ensemble of zero-sum triples about a spanwise-uniform mean (so
+    /// that the pointwise time mean of `tke::stress_budget` is the span mean
+    /// used here), the wavenumber sums of production, dissipation and
+    /// pressure strain and the divergence-form turbulent transport match the
+    /// physical-space budget computed from total moments.
+    #[test]
+    fn wavenumber_sums_match_stress_budget() {
+        use crate::math::tke::stress_budget;
+        let (nz, ny, nx) = (6, 8, 7);
+        let (x, y, z) = grid(nz, ny, nx);
+        let base: [ArrayD<f64>; 4] = std::array::from_fn(|c| {
+            Array3::from_shape_fn((nz, ny, nx), |(k, _, i)| (1.0 + c as f64) * z[k] * (1.0 + 0.3 * x[i])).into_dyn()
+        });
+        // fluctuations f, g, −(f + g): zero pointwise mean, non-zero triple
+        // moments
+        let snaps: Vec<[ArrayD<f64>; 4]> = (0..2)
+            .flat_map(|n| {
+                let f: [ArrayD<f64>; 4] = std::array::from_fn(|c| noisy(20 * n + c as u64, (nz, ny, nx)));
+                let g: [ArrayD<f64>; 4] = std::array::from_fn(|c| noisy(20 * n + 10 + c as u64, (nz, ny, nx)));
+                let h: [ArrayD<f64>; 4] = std::array::from_fn(|c| -(&f[c] + &g[c]));
+                [f, g, h].map(|d| -> [ArrayD<f64>; 4] { std::array::from_fn(|c| &base[c] + &d[c]) })
+            })
+            .collect();
+        let mean: [ArrayD<f64>; 4] = std::array::from_fn(|c| base[c].mean_axis(Axis(1)).unwrap());
+        let nu = 0.01;
+        let windows = [(0, 3), (3, 7)];
+        let mut sb = SpectralBudget::new(mean.each_ref(), &x, &y, &z, nu, &windows, false).unwrap();
+        for [u, v, w, p] in &snaps {
+            sb.accumulate(u, v, w, p).unwrap();
+        }
+        let dk = sb.wavenumbers()[1];
+        let dens = sb.densities().unwrap();
+        let turb = sb.turbulent_transport().unwrap();
+
+        // total moments of the subavg names: a product of u/v/w/p factors,
+        // each optionally differentiated along the x/y/z letter after it
+        let mut load = |name: &str| -> Result<ArrayD<f64>, Error> {
+            let mut prod = Array3::<f64>::ones((nz, ny, nx)).into_dyn();
+            let mut sum = ArrayD::zeros(prod.raw_dim());
+            for snap in &snaps {
+                prod.fill(1.0);
+                let mut c = name.chars().peekable();
+                while let Some(v) = c.next() {
+                    let f = &snap["uvwp".find(v).unwrap()];
+                    let f = match c.next_if(|d| "xyz".contains(*d)) {
+                        Some('x') => deriv1(f, 2, &x, false)?,
+                        Some('y') => deriv1(f, 1, &y, true)?,
+                        Some(_) => deriv1(f, 0, &z, false)?,
+                        None => f.clone(),
+                    };
+                    prod *= &f;
+                }
+                sum += &prod;
+            }
+            Ok(sum / snaps.len() as f64)
+        };
+        for a in 0..3 {
+            let b = stress_budget(&mut load, a, a, &x, &y, &z, nu, false).unwrap();
+            for (name, spec, phys) in [
+                ("prod", &dens.prod, &b.prod),
+                ("diss", &dens.diss, &b.visc_diss),
+                ("pstrain", &dens.pstrain, &b.press_strain),
+            ] {
+                let scale = phys.iter().fold(0.0f64, |m, v| m.max(v.abs()));
+                assert!(scale > 1e-3, "{name} {a} vanishes");
+                for (iw, &(i0, i1)) in windows.iter().enumerate() {
+                    for iz in 0..nz {
+                        let sum = spec.slice(s![iw, a, iz, ..]).sum() * dk;
+                        let exact = phys.slice(s![iz, i0..i1]).mean().unwrap();
+                        assert!((sum - exact).abs() < 1e-10 * scale, "{name} {a} ({iw}, {iz}): {sum} vs {exact}");
+                    }
+                }
+            }
+            let scale = b.turb_trans.iter().fold(0.0f64, |m, v| m.max(v.abs()));
+            assert!(scale > 1e-3, "turb_trans {a} vanishes");
+            for (iw, &(i0, i1)) in windows.iter().enumerate() {
+                for iz in 0..nz {
+                    let exact = b.turb_trans.slice(s![iz, i0..i1]).mean().unwrap();
+                    assert!((turb[[iw, a, iz]] - exact).abs() < 1e-10 * scale, "turb_trans {a} ({iw}, {iz})");
+                }
+            }
+        }
+    }
+
+    /// u' = a cos(k₁y), w' = b cos(k₁y) in the mean shear ū = S z: all of the
+    /// ⟨u'u'⟩ production −2⟨u'w'⟩S = −abS sits in the k₁ mode.
+    #[test]
+    fn single_mode_production() {
+        let (nz, ny, nx) = (5, 16, 4);
+        let (x, y, z) = grid(nz, ny, nx);
+        let (a, b, shear) = (0.3, -0.2, 2.0);
+        let ly = 2.0;
+        let k1 = 2.0 * PI * 3.0 / ly;
+        let f = |g: &dyn Fn(usize, usize) -> f64| Array3::from_shape_fn((nz, ny, nx), |(k, j, _)| g(k, j)).into_dyn();
+        let u = f(&|k, j| shear * z[k] + a * (k1 * y[j]).cos());
+        let w = f(&|_, j| b * (k1 * y[j]).cos());
+        let zero = f(&|_, _| 0.0);
+        let mean_u = Array2::from_shape_fn((nz, nx), |(k, _)| shear * z[k]).into_dyn();
+        let mean0 = Array2::<f64>::zeros((nz, nx)).into_dyn();
+
+        let mut sb = SpectralBudget::new([&mean_u, &mean0, &mean0, &mean0], &x, &y, &z, 1e-3, &[(0, nx)], false)
+            .unwrap();
+        sb.accumulate(&u, &zero, &w, &zero).unwrap();
+        let dk = sb.wavenumbers()[1];
+        let d = sb.densities().unwrap();
+        for iz in 0..nz {
+            for m in 0..ny / 2 + 1 {
+                let (p, e) = (d.prod[[0, 0, iz, m]] * dk, d.energy[[0, 0, iz, m]] * dk);
+                let (pe, ee) = if m == 3 { (-a * b * shear, 0.5 * a * a) } else { (0.0, 0.0) };
+                assert!((p - pe).abs() < 1e-12, "m = {m}: production {p}");
+                assert!((e - ee).abs() < 1e-12, "m = {m}: energy {e}");
+                assert!(d.prod[[0, 2, iz, m]].abs() < 1e-12);
+                assert!((d.prod[[0, 3, iz, m]] - 0.5 * d.prod[[0, 0, iz, m]]).abs() < 1e-12);
+            }
+        }
+    }
+}
@@ -29,9 +29,31 @@
 //! only the uniform (k=0) mode is removed per line, so a steady spanwise-varying
 //! mean shows up as sharp peaks at the control wavenumber and its harmonics.
 //! That is physically meaningful (dispersive component) — do not be surprised.
+//!
+//! Two-dimensional (k_x, k_y) spectra ([`PlaneSpectrum`]) additionally
+//! transform along x over windows of a uniform streamwise sub-range.  The
+//! streamwise direction is not periodic, so each window is tapered with a
+//! periodic Hann window w_n and consecutive windows overlap by 50 %:
+//!
+//!   fluctuation:   u'(x, y) = u − (1/N_y) Σ_j u(x, y_j)  (spanwise mean per x)
+//!   DFT:           ĉ_ml = Σ_n Σ_j w_n u'_nj e^{−2πi (m n / N_x + l j / N_y)}
+//!   stored power:  P_ml = ⟨|ĉ_ml|²⟩ / (N_x N_y² Σ_n w_n²)
+//!
+//! so that Σ_ml P_ml is the window-weighted variance Σ_n w_n² ⟨u'²⟩_n / Σ w².
+//! The one-sided density E(k_x, k_y) folds the ± modes together and divides by
+//! Δk_x Δk_y, with Δk_x = 2π / (N_x Δx).
+//!
+//! Co-spectra of two fields ([`SpanwiseCospectrum`]) are averaged over
+//! streamwise windows and take the fluctuations about the span + ensemble
+//! mean instead of the per-line mean, so that the k=0 mode carries the
+//! spanwise-uniform unsteadiness and Σ_m C_m equals the Reynolds stress
+//! ⟨a'b'⟩ = ⟨ab⟩ − āb̄ used by the FIK/RD cores:
+//!
+//!   C_m = w_m Re⟨â*_m b̂_m⟩ / N²                         (m ≥ 1)
+//!   C_0 = ⟨[a]_y [b]_y⟩ − ⟨[a]_y⟩⟨[b]_y⟩                ([·]_y spanwise mean)
 
 use hdf5::Error;
-use ndarray::{Array1, Array2, ArrayD, Axis};
+use ndarray::{s, Array1, Array2, Array3, ArrayD, Axis};
 use rustfft::{num_complex::Complex, Fft, FftPlanner};
 use std::sync::Arc;
 
@@ -198,10 +220,377 @@ impl SpanwiseSpectrum {
     }
 }
 
+/// Accumulator for two-dimensional (k_x, k_y) spectra over Hann-windowed
+/// (x-window, y) planes: feed it snapshots with [`accumulate`], then read out
+/// the 2-D density, its premultiplied form, or the 1-D spectra obtained by
+/// integrating out one direction.
+///
+/// [`accumulate`]: PlaneSpectrum::accumulate
+pub struct PlaneSpectrum {
+    nz: usize,
+    ny: usize,
+    nxw: usize,
+    lx: f64, // window length nxw·Δx
+    ly: f64,
+    window: Array1<f64>,
+    /// Accumulated two-sided power, shape (nz, nxw, ny); divide by `planes`.
+    power: Array3<f64>,
+    /// Normalization 1 / (N_x N_y² Σ w²) applied to |ĉ_ml|².
+    norm: f64,
+    /// Number of windowed planes accumulated per z (windows × snapshots).
+    planes: usize,
+    fft_x: Arc<dyn Fft<f64>>,
+    fft_y: Arc<dyn Fft<f64>>,
+}
+
+impl PlaneSpectrum {
+    /// `nz` — wall-normal planes, `nxw` — streamwise points per window,
+    /// `ny` — spanwise points (uniform, periodic), `dx` — streamwise spacing
+    /// (uniform over the analysed range), `ly` — spanwise period length.
+    pub fn new(nz: usize, nxw: usize, ny: usize, dx: f64, ly: f64) -> Result<Self, Error> {
+        if nxw < 4 {
+            return Err(format!("window length {nxw} too small for a spectrum").into());
+        }
+        if ny < 2 {
+            return Err(format!("ny = {ny} too small for a spectrum").into());
+        }
+        if dx <= 0.0 || ly <= 0.0 {
+            return Err(format!("dx = {dx} and ly = {ly} must be positive").into());
+        }
+        let two_pi = 2.0 * std::f64::consts::PI;
+        let window =
+            Array1::from_iter((0..nxw).map(|n| 0.5 * (1.0 - (two_pi * n as f64 / nxw as f64).cos())));
+        let wsum2 = window.iter().map(|w| w * w).sum::<f64>();
+        let mut planner = FftPlanner::new();
+        Ok(Self {
+            nz,
+            ny,
+            nxw,
+            lx: nxw as f64 * dx,
+            ly,
+            window,
+            power: Array3::zeros((nz, nxw, ny)),
+            norm: 1.0 / (nxw as f64 * (ny * ny) as f64 * wsum2),
+            planes: 0,
+            fft_x: planner.plan_fft_forward(nxw),
+            fft_y: planner.plan_fft_forward(ny),
+        })
+    }
+
+    /// Add one snapshot, shape `(nz, ny, nx_sel)` with `nx_sel ≥ nxw`.  The
+    /// streamwise extent is cut into 50 %-overlapping windows; every window at
+    /// every z counts as one realization.  Returns the number of windows used.
+    pub fn accumulate(&mut self, u: &ArrayD<f64>) -> Result<usize, Error> {
+        let s = u.shape();
+        if s.len() != 3 || s[0] != self.nz || s[1] != self.ny || s[2] < self.nxw {
+            return Err(format!(
+                "snapshot shape {s:?} incompatible with nz={} ny={} window={}",
+                self.nz, self.ny, self.nxw
+            )
+            .into());
+        }
+        let (ny, nxw) = (self.ny, self.nxw);
+        let hop = (nxw / 2).max(1);
+        let nwin = (s[2] - nxw) / hop + 1;
+
+        let zero = Complex::new(0.0, 0.0);
+        let mut rows: Vec<Complex<f64>> = vec![zero; nxw * ny]; // [i·ny + j]
+        let mut cols: Vec<Complex<f64>> = vec![zero; ny * nxw]; // [j·nxw + i]
+
+        for iw in 0..nwin {
+            let x0 = iw * hop;
+            for iz in 0..self.nz {
+                for i in 0..nxw {
+                    let line = u.slice(ndarray::s![iz, .., x0 + i]);
+                    let mean = line.sum() / ny as f64;
+                    let wi = self.window[i];
+                    for (j, &v) in line.iter().enumerate() {
+                        rows[i * ny + j] = Complex::new(wi * (v - mean), 0.0);
+                    }
+                }
+                // y-transforms of every row, then x-transforms of every column
+                self.fft_y.process(&mut rows);
+                for i in 0..nxw {
+                    for j in 0..ny {
+                        cols[j * nxw + i] = rows[i * ny + j];
+                    }
+                }
+                self.fft_x.process(&mut cols);
+                let mut plane = self.power.index_axis_mut(Axis(0), iz);
+                for j in 0..ny {
+                    for i in 0..nxw {
+                        plane[[i, j]] += cols[j * nxw + i].norm_sqr() * self.norm;
+                    }
+                }
+            }
+        }
+        self.planes += nwin;
+        Ok(nwin)
+    }
+
+    /// Number of windowed planes accumulated so far (per z).
+    pub fn samples(&self) -> usize {
+        self.planes
+    }
+
+    /// Wavenumbers `(k_x, k_y)` of the one-sided spectrum (lengths
+    /// nxw/2+1 and ny/2+1).
+    pub fn wavenumbers(&self) -> (Array1<f64>, Array1<f64>) {
+        let (dkx, dky) = self.dk();
+        (
+            Array1::from_iter((0..self.nxw / 2 + 1).map(|m| m as f64 * dkx)),
+            Array1::from_iter((0..self.ny / 2 + 1).map(|l| l as f64 * dky)),
+        )
+    }
+
+    fn dk(&self) -> (f64, f64) {
+        let two_pi = 2.0 * std::f64::consts::PI;
+        (two_pi / self.lx, two_pi / self.ly)
+    }
+
+    /// One-sided 2-D energy spectral density `e` of shape
+    /// `(nz, nxw/2+1, ny/2+1)`, normalized so that
+    /// `Σ_ml e[z,m,l] Δk_x Δk_y` is the window-weighted variance at z.
+    pub fn energy_spectrum(&self) -> Result<Array3<f64>, Error> {
+        if self.planes == 0 {
+            return Err("no snapshots accumulated".into());
+        }
+        let (nkx, nky) = (self.nxw / 2 + 1, self.ny / 2 + 1);
+        let (dkx, dky) = self.dk();
+        let scale = 1.0 / (self.planes as f64 * dkx * dky);
+        // distinct two-sided indices folded onto one-sided index k
+        let mirror = |k: usize, n: usize| -> Vec<usize> {
+            let neg = (n - k) % n;
+            if neg == k { vec![k] } else { vec![k, neg] }
+        };
+        let mut e = Array3::zeros((self.nz, nkx, nky));
+        for m in 0..nkx {
+            let ms = mirror(m, self.nxw);
+            for l in 0..nky {
+                let ls = mirror(l, self.ny);
+                for iz in 0..self.nz {
+                    let mut sum = 0.0;
+                    for &mi in &ms {
+                        for &li in &ls {
+                            sum += self.power[[iz, mi, li]];
+                        }
+                    }
+                    e[[iz, m, l]] = sum * scale;
+                }
+            }
+        }
+        Ok(e)
+    }
+
+    /// Window-weighted variance ⟨u'²⟩(z) reconstructed from the spectrum.
+    pub fn variance(&self) -> Result<Array1<f64>, Error> {
+        let (dkx, dky) = self.dk();
+        let e = self.energy_spectrum()?;
+        Ok(e.sum_axis(Axis(2)).sum_axis(Axis(1)) * (dkx * dky))
+    }
+
+    /// Wavelengths `(λ_x, λ_y) = 2π / k` of the non-zero wavenumbers (the
+    /// k = 0 modes have infinite wavelength); lengths nxw/2 and ny/2.
+    pub fn wavelengths(&self) -> (Array1<f64>, Array1<f64>) {
+        let (kx, ky) = self.wavenumbers();
+        let two_pi = 2.0 * std::f64::consts::PI;
+        (
+            kx.slice(ndarray::s![1..]).mapv(|k| two_pi / k),
+            ky.slice(ndarray::s![1..]).mapv(|k| two_pi / k),
+        )
+    }
+
+    /// Premultiplied spectrum k_x k_y E(λ_x, λ_y) for the contour plots, on
+    /// the [`wavelengths`] grid: shape `(nz, nxw/2, ny/2)`.
+    ///
+    /// [`wavelengths`]: PlaneSpectrum::wavelengths
+    pub fn premultiplied(&self) -> Result<Array3<f64>, Error> {
+        let e = self.energy_spectrum()?;
+        let (kx, ky) = self.wavenumbers();
+        let mut pm = e.slice(ndarray::s![.., 1.., 1..]).to_owned();
+        for ((_, m, l), v) in pm.indexed_iter_mut() {
+            *v *= kx[m + 1] * ky[l + 1];
+        }
+        Ok(pm)
+    }
+
+    /// 1-D streamwise spectrum E(k_x) = Σ_l E(k_x, k_y) Δk_y, shape
+    /// `(nz, nxw/2+1)`.
+    pub fn streamwise_spectrum(&self) -> Result<Array2<f64>, Error> {
+        let (_, dky) = self.dk();
+        Ok(self.energy_spectrum()?.sum_axis(Axis(2)) * dky)
+    }
+
+    /// 1-D spanwise spectrum E(k_y) = Σ_m E(k_x, k_y) Δk_x, shape
+    /// `(nz, ny/2+1)`.  Matches [`SpanwiseSpectrum::energy_spectrum`] up to the
+    /// Hann weighting of the streamwise positions.
+    pub fn spanwise_spectrum(&self) -> Result<Array2<f64>, Error> {
+        let (dkx, _) = self.dk();
+        Ok(self.energy_spectrum()?.sum_axis(Axis(1)) * dkx)
+    }
+}
+
+/// Accumulator for the one-sided spanwise co-spectrum of two fields over
+/// streamwise windows: feed it snapshot pairs with [`accumulate`], then read
+/// out the co-spectral density per window.
+///
+/// [`accumulate`]: SpanwiseCospectrum::accumulate
+pub struct SpanwiseCospectrum {
+    nz: usize,
+    ny: usize,
+    nx: usize,
+    nk: usize,
+    ly: f64,
+    windows: Vec<(usize, usize)>,
+    /// Accumulated Re(â* b̂)/N² for m ≥ 1, shape (nwin, nz, nk); column 0 unused.
+    co: Array3<f64>,
+    /// Per-(z, x) sums over snapshots of the spanwise means [a]_y, [b]_y,
+    /// [a]_y [b]_y and [ab]_y, each (nz, nx).
+    sum_a: Array2<f64>,
+    sum_b: Array2<f64>,
+    sum_ab_mean: Array2<f64>,
+    sum_ab: Array2<f64>,
+    snapshots: usize,
+    fft: Arc<dyn Fft<f64>>,
+}
+
+impl SpanwiseCospectrum {
+    /// `nz`, `ny`, `nx` — field shape (y uniform and periodic), `ly` —
+    /// spanwise period length, `windows` — half-open x-index ranges.
+    pub fn new(nz: usize, ny: usize, nx: usize, ly: f64, windows: &[(usize, usize)]) -> Result<Self, Error> {
+        if ny < 2 {
+            return Err(format!("ny = {ny} too small for a spectrum").into());
+        }
+        if ly <= 0.0 {
+            return Err(format!("ly = {ly} must be positive").into());
+        }
+        if windows.is_empty() || windows.iter().any(|&(i0, i1)| i0 >= i1 || i1 > nx) {
+            return Err(format!("invalid x windows {windows:?} (nx = {nx})").into());
+        }
+        let nk = ny / 2 + 1;
+        Ok(Self {
+            nz,
+            ny,
+            nx,
+            nk,
+            ly,
+            windows: windows.to_vec(),
+            co: Array3::zeros((windows.len(), nz, nk)),
+            sum_a: Array2::zeros((nz, nx)),
+            sum_b: Array2::zeros((nz, nx)),
+            sum_ab_mean: Array2::zeros((nz, nx)),
+            sum_ab: Array2::zeros((nz, nx)),
+            snapshots: 0,
+            fft: FftPlanner::new().plan_fft_forward(ny),
+        })
+    }
+
+    /// Add one snapshot of the pair `(a, b)`, each `(nz, ny, nx)`.
+    pub fn accumulate(&mut self, a: &ArrayD<f64>, b: &ArrayD<f64>) -> Result<(), Error> {
+        let shape = [self.nz, self.ny, self.nx];
+        if a.shape() != shape || b.shape() != shape {
+            return Err(format!(
+                "snapshot shapes {:?}, {:?} incompatible with {shape:?}",
+                a.shape(),
+                b.shape()
+            )
+            .into());
+        }
+        let ny_f = self.ny as f64;
+        let norm = 1.0 / (ny_f * ny_f);
+        for iz in 0..self.nz {
+            for ix in 0..self.nx {
+                let (la, lb) = (a.slice(s![iz, .., ix]), b.slice(s![iz, .., ix]));
+                let (ma, mb) = (la.sum() / ny_f, lb.sum() / ny_f);
+                self.sum_a[[iz, ix]] += ma;
+                self.sum_b[[iz, ix]] += mb;
+                self.sum_ab_mean[[iz, ix]] += ma * mb;
+                self.sum_ab[[iz, ix]] += la.iter().zip(lb.iter()).map(|(p, q)| p * q).sum::<f64>() / ny_f;
+            }
+        }
+
+        let mut ba: Vec<Complex<f64>> = vec![Complex::new(0.0, 0.0); self.ny];
+        let mut bb = ba.clone();
+        for (iw, &(i0, i1)) in self.windows.iter().enumerate() {
+            for ix in i0..i1 {
+                for iz in 0..self.nz {
+                    for (buf, f) in [(&mut ba, a), (&mut bb, b)] {
+                        for (c, &v) in buf.iter_mut().zip(f.slice(s![iz, .., ix]).iter()) {
+                            *c = Complex::new(v, 0.0);
+                        }
+                        self.fft.process(buf);
+                    }
+                    for m in 1..self.nk {
+                        self.co[[iw, iz, m]] += (ba[m].conj() * bb[m]).re * norm;
+                    }
+                }
+            }
+        }
+        self.snapshots += 1;
+        Ok(())
+    }
+
+    /// Number of snapshots accumulated so far.
+    pub fn samples(&self) -> usize {
+        self.snapshots
+    }
+
+    /// One-sided weight w_m (as [`SpanwiseSpectrum`]).
+    fn weight(&self, m: usize) -> f64 {
+        if m == 0 || 2 * m == self.ny { 1.0 } else { 2.0 }
+    }
+
+    /// Ensemble span means ā(z, x), b̄(z, x).
+    pub fn means(&self) -> Result<(Array2<f64>, Array2<f64>), Error> {
+        self.check_samples()?;
+        let n = self.snapshots as f64;
+        Ok((&self.sum_a / n, &self.sum_b / n))
+    }
+
+    /// Reynolds stress ⟨a'b'⟩(z, x) = ⟨ab⟩ − āb̄ computed directly.
+    pub fn covariance(&self) -> Result<Array2<f64>, Error> {
+        let (ma, mb) = self.means()?;
+        Ok(&self.sum_ab / self.snapshots as f64 - &ma * &mb)
+    }
+
+    /// One-sided co-spectral density per window.
+    ///
+    /// Returns `(k, c)` with `k[m] = 2π m / L_y` and `c` of shape
+    /// `(nwin, nz, nk)`, normalized so that `Σ_m c[w,z,m] Δk` is
+    /// [`covariance`] averaged over the x points of window `w`.
+    ///
+    /// [`covariance`]: SpanwiseCospectrum::covariance
+    pub fn cospectrum(&self) -> Result<(Array1<f64>, Array3<f64>), Error> {
+        self.check_samples()?;
+        let n = self.snapshots as f64;
+        let dk = 2.0 * std::f64::consts::PI / self.ly;
+        let k = Array1::from_iter((0..self.nk).map(|m| m as f64 * dk));
+        // k = 0: fluctuation of the spanwise mean about the ensemble mean
+        let c0 = &self.sum_ab_mean / n - &(&self.sum_a / n) * &(&self.sum_b / n);
+        let mut c = Array3::zeros((self.windows.len(), self.nz, self.nk));
+        for (iw, &(i0, i1)) in self.windows.iter().enumerate() {
+            let lines = (i1 - i0) as f64 * n;
+            for iz in 0..self.nz {
+                c[[iw, iz, 0]] = c0.slice(s![iz, i0..i1]).sum() / (i1 - i0) as f64 / dk;
+                for m in 1..self.nk {
+                    c[[iw, iz, m]] = self.weight(m) * self.co[[iw, iz, m]] / lines / dk;
+                }
+            }
+        }
+        Ok((k, c))
+    }
+
+    fn check_samples(&self) -> Result<(), Error> {
+        if self.snapshots == 0 {
+            return Err("no snapshots accumulated".into());
+        }
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use ndarray::Array3;
 
     const PI: f64 = std::f64::consts::PI;
 
@@ -288,6 +677,54 @@ mod tests {
         }
     }
 
+    /// Σ_m C_m Δk recovers ⟨ab⟩ − āb̄ including the spanwise-uniform
+    /// unsteadiness, which lives in the k = 0 mode.
+    #[test]
+    fn cospectrum_sums_to_reynolds_stress() {
+        let (nz, ny, nx) = (2, 12, 5);
+        let ly = 1.5;
+        let noise = |s: usize| {
+            Array3::from_shape_fn((nz, ny, nx), move |(i, j, k)| {
+                ((i * 7919 + j * 104729 + k * 1299709 + s * 15485863) % 10007) as f64 / 10007.0
+            })
+            .into_dyn()
+        };
+        let windows = [(0, 2), (2, 5)];
+        let mut cs = SpanwiseCospectrum::new(nz, ny, nx, ly, &windows).unwrap();
+        let mut snaps = Vec::new();
+        for n in 0..3 {
+            // snapshot-dependent spanwise-uniform offset on top of the noise
+            let a = noise(2 * n).mapv(|v| v + 0.3 * n as f64);
+            let b = &noise(2 * n + 1) + &a.mapv(|v| 0.5 * v);
+            cs.accumulate(&a, &b).unwrap();
+            snaps.push((a, b));
+        }
+        let (k, c) = cs.cospectrum().unwrap();
+        let cov = cs.covariance().unwrap();
+        assert!(c.slice(s![.., .., 0]).iter().all(|v| v.abs() > 1e-6));
+
+        let nt = snaps.len() as f64;
+        for (iw, &(i0, i1)) in windows.iter().enumerate() {
+            for iz in 0..nz {
+                let mut direct = 0.0;
+                for ix in i0..i1 {
+                    let (mut ab, mut ma, mut mb) = (0.0, 0.0, 0.0);
+                    for (a, b) in &snaps {
+                        for j in 0..ny {
+                            ab += a[[iz, j, ix]] * b[[iz, j, ix]] / (nt * ny as f64);
+                            ma += a[[iz, j, ix]] / (nt * ny as f64);
+                            mb += b[[iz, j, ix]] / (nt * ny as f64);
+                        }
+                    }
+                    assert!((cov[[iz, ix]] - (ab - ma * mb)).abs() < 1e-12);
+                    direct += (ab - ma * mb) / (i1 - i0) as f64;
+                }
+                let sum = c.slice(s![iw, iz, ..]).sum() * k[1];
+                assert!((sum - direct).abs() < 1e-12, "window {iw}, z {iz}: {sum} vs {direct}");
+            }
+        }
+    }
+
     /// Ensemble averaging: two snapshots with amplitudes A and B give the
     /// mean of the two individual spectra.
     #[test]
@@ -313,4 +750,81 @@ mod tests {
         let expected = 0.5 * (1.0 * 1.0 / 2.0 + 2.0 * 2.0 / 2.0);
         assert!((var[0] - expected).abs() < 1e-12, "{} vs {expected}", var[0]);
     }
+
+    /// u' = A cos(k_x0 x + k_y0 y) with whole periods in the window: the Hann
+    /// taper leaks into k_x0 ± Δk_x, but the spanwise spectrum is exact and the
+    /// total energy is A²/2.
+    #[test]
+    fn plane_spectrum_single_oblique_mode() {
+        let (nz, ny, nx) = (2, 16, 32);
+        let (dx, ly) = (0.1, 1.6);
+        let (m0, l0) = (5usize, 3usize);
+        let a = 1.3;
+        let lx = nx as f64 * dx;
+
+        let u = Array3::from_shape_fn((nz, ny, nx), |(_, j, i)| {
+            let (x, y) = (i as f64 * dx, j as f64 * ly / ny as f64);
+            a * (2.0 * PI * (m0 as f64 * x / lx + l0 as f64 * y / ly)).cos()
+        })
+        .into_dyn();
+
+        let mut spec = PlaneSpectrum::new(nz, nx, ny, dx, ly).unwrap();
+        assert_eq!(spec.accumulate(&u).unwrap(), 1);
+
+        let var = spec.variance().unwrap();
+        assert!((var[0] - a * a / 2.0).abs() < 1e-12, "var = {}", var[0]);
+
+        let dky = 2.0 * PI / ly;
+        let ey = spec.spanwise_spectrum().unwrap();
+        for l in 0..ey.shape()[1] {
+            let expected = if l == l0 { a * a / 2.0 / dky } else { 0.0 };
+            assert!((ey[[1, l]] - expected).abs() < 1e-10, "ky mode {l}: {}", ey[[1, l]]);
+        }
+
+        let dkx = 2.0 * PI / lx;
+        let ex = spec.streamwise_spectrum().unwrap();
+        let near: f64 = (m0 - 1..=m0 + 1).map(|m| ex[[0, m]] * dkx).sum();
+        assert!((near - a * a / 2.0).abs() < 1e-12, "leakage outside m0 ± 1: {near}");
+
+        let (lambda_x, lambda_y) = spec.wavelengths();
+        let pm = spec.premultiplied().unwrap();
+        assert_eq!(pm.shape(), &[nz, nx / 2, ny / 2]);
+        assert!((lambda_x[m0 - 1] - lx / m0 as f64).abs() < 1e-12);
+        assert!((lambda_y[l0 - 1] - ly / l0 as f64).abs() < 1e-12);
+    }
+
+    /// Parseval over several overlapping windows: the reconstructed variance
+    /// equals the directly computed Hann-weighted variance.
+    #[test]
+    fn plane_spectrum_parseval_with_overlap() {
+        let (nz, ny, nx, nxw) = (2, 12, 40, 16);
+        let u = Array3::from_shape_fn((nz, ny, nx), |(i, j, k)| {
+            ((i * 7919 + j * 104729 + k * 1299709) % 10007) as f64 / 10007.0
+        })
+        .into_dyn();
+
+        let mut spec = PlaneSpectrum::new(nz, nxw, ny, 0.2, 2.0).unwrap();
+        let nwin = spec.accumulate(&u).unwrap();
+        assert_eq!(nwin, 4);
+        let var = spec.variance().unwrap();
+
+        let w: Vec<f64> = (0..nxw)
+            .map(|n| 0.5 * (1.0 - (2.0 * PI * n as f64 / nxw as f64).cos()))
+            .collect();
+        let wsum2: f64 = w.iter().map(|v| v * v).sum();
+        for iz in 0..nz {
+            let mut direct = 0.0;
+            for iw in 0..nwin {
+                for (n, wn) in w.iter().enumerate() {
+                    let ix = iw * nxw / 2 + n;
+                    let line: Vec<f64> = (0..ny).map(|j| u[[iz, j, ix]]).collect();
+                    let mean = line.iter().sum::<f64>() / ny as f64;
+                    let lv = line.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / ny as f64;
+                    direct += wn * wn * lv / wsum2;
+                }
+            }
+            direct /= nwin as f64;
+            assert!((var[iz] - direct).abs() < 1e-12, "z {iz}: {} vs {direct}", var[iz]);
+        }
+    }
 }
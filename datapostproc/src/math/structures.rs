@@ -0,0 +1,382 @@
+//! Coherent-structure extraction: thresholding and 3-D connected-component
+//! labeling of a scalar field (Q, λ₂, u', u'w', …).
+//!
+//! Coordinate convention (matches the rest of this crate):
+//!   Array shape (nz, ny, nx): axis 0 = wall-normal z, axis 1 = spanwise y,
+//!   axis 2 = streamwise x.
+//!
+//! A point belongs to a structure when the field passes the threshold rule
+//! ([`Threshold`]), optionally scaled by a local (z, x) reference such as the
+//! spanwise rms (e.g. u' < −α u'_rms(z, x) for low-speed streaks, or
+//! |u'w'| > H u'_rms w'_rms for Reynolds-stress structures).  Points are then
+//! grouped into objects by face (6-)connectivity; the spanwise direction is
+//! periodic, so an object crossing y = L_y stays one object.  Its spanwise
+//! coordinates are unwrapped during the flood fill, so the bounding box and
+//! centroid are those of the contiguous object (centroid folded back into
+//! the box).
+//!
+//! Per object ([`Structure`]): volume Σ Δx Δy Δz, volume-weighted centroid,
+//! bounding box of the cell centres and a wall-attached flag
+//! (z_min ≤ attach height, e.g. 20 ν/u_τ following del Álamo et al. 2006).
+//! The attach height is given per streamwise station, so that with a local
+//! u_τ(x) each object is judged in the wall units of the station nearest
+//! its centroid.
+
+use hdf5::Error;
+use ndarray::{Array1, Array2, Array3, ArrayD, ArrayView2, Axis, Ix3};
+
+/// Threshold rule selecting structure points.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Threshold {
+    /// f > level (Q, positive u', swirling strength).
+    Above(f64),
+    /// f < level (λ₂, low-speed streaks with a negative level).
+    Below(f64),
+    /// |f| > level (u'w' of either sign).
+    AbsAbove(f64),
+}
+
+impl Threshold {
+    fn test(self, f: f64, scale: f64) -> bool {
+        match self {
+            Threshold::Above(t) => f > t * scale,
+            Threshold::Below(t) => f < t * scale,
+            Threshold::AbsAbove(t) => f.abs() > t * scale,
+        }
+    }
+}
+
+/// One connected object.  Coordinates are (x, y, z); the spanwise bounds are
+/// unwrapped, so `max[1]` may exceed the domain end for objects crossing the
+/// periodic boundary.
+#[derive(Clone, Debug)]
+pub struct Structure {
+    /// Label in the array returned by [`label_structures`] (1-based).
+    pub label: u32,
+    /// Number of grid points.
+    pub cells: usize,
+    /// Volume Σ Δx Δy Δz.
+    pub volume: f64,
+    /// Volume-weighted centroid (x, y, z).
+    pub centroid: [f64; 3],
+    /// Streamwise station nearest the centroid.
+    pub ix: usize,
+    /// Bounding box of the cell centres.
+    pub min: [f64; 3],
+    pub max: [f64; 3],
+    /// z_min ≤ attach height at station `ix`.
+    pub attached: bool,
+}
+
+impl Structure {
+    /// Bounding-box extents (Δx, Δy, Δz) of the cell centres.
+    pub fn extent(&self) -> [f64; 3] {
+        [
+            self.max[0] - self.min[0],
+            self.max[1] - self.min[1],
+            self.max[2] - self.min[2],
+        ]
+    }
+}
+
+/// Apply `rule` to every point of a `(nz, ny, nx)` field.  `scale` — optional
+/// `(nz, nx)` reference multiplying the threshold level point-wise.
+pub fn threshold_mask(
+    field: &ArrayD<f64>,
+    rule: Threshold,
+    scale: Option<ArrayView2<f64>>,
+) -> Result<Array3<bool>, Error> {
+    let f = field
+        .view()
+        .into_dimensionality::<Ix3>()
+        .map_err(|_| Error::from("threshold_mask: field must be 3-D (nz, ny, nx)"))?;
+    let (nz, ny, nx) = f.dim();
+    if let Some(s) = scale.as_ref().filter(|s| s.dim() != (nz, nx)) {
+        return Err(format!(
+            "threshold_mask: scale shape {:?} does not match (nz, nx) = ({nz}, {nx})",
+            s.dim()
+        )
+        .into());
+    }
+    Ok(Array3::from_shape_fn((nz, ny, nx), |(k, j, i)| {
+        let sc = scale.as_ref().map_or(1.0, |s| s[[k, i]]);
+        rule.test(f[[k, j, i]], sc)
+    }))
+}
+
+/// Spanwise rms of the fluctuation about the spanwise mean, shape (nz, nx).
+pub fn spanwise_rms(field: &ArrayD<f64>) -> Result<Array2<f64>, Error> {
+    let f = field
+        .view()
+        .into_dimensionality::<Ix3>()
+        .map_err(|_| Error::from("spanwise_rms: field must be 3-D (nz, ny, nx)"))?;
+    let mean = f.mean_axis(Axis(1)).ok_or("spanwise_rms: empty field")?;
+    let mean2 = f.mapv(|v| v * v).mean_axis(Axis(1)).ok_or("spanwise_rms: empty field")?;
+    Ok((mean2 - &mean * &mean).mapv(|v| v.max(0.0).sqrt()))
+}
+
+/// Cell widths around each coordinate point (half-way to the neighbours,
+/// one-sided at the ends).
+fn cell_widths(c: &Array1<f64>) -> Array1<f64> {
+    let n = c.len();
+    if n < 2 {
+        return Array1::ones(n);
+    }
+    Array1::from_shape_fn(n, |i| {
+        let lo = if i == 0 { c[0] - 0.5 * (c[1] - c[0]) } else { 0.5 * (c[i - 1] + c[i]) };
+        let hi = if i == n - 1 {
+            c[n - 1] + 0.5 * (c[n - 1] - c[n - 2])
+        } else {
+            0.5 * (c[i] + c[i + 1])
+        };
+        hi - lo
+    })
+}
+
+/// Label the face-connected components of `mask` (periodic in y) and measure
+/// them.  Returns the label array (0 = background, objects numbered from 1 in
+/// order of discovery) and one [`Structure`] per label with at least
+/// `min_cells` points; smaller objects are reset to 0.
+///
+/// `x`, `y`, `z` — coordinates of the (nx, ny, nz) grid points (y uniform);
+/// `attach` — wall-attached threshold on z_min per streamwise station (NaN:
+/// never attached).
+pub fn label_structures(
+    mask: &Array3<bool>,
+    x: &Array1<f64>,
+    y: &Array1<f64>,
+    z: &Array1<f64>,
+    attach: &Array1<f64>,
+    min_cells: usize,
+) -> Result<(Array3<u32>, Vec<Structure>), Error> {
+    label(mask, x, y, z, attach, min_cells).map(|(labels, objects, _)| (labels, objects))
+}
+
+/// Label of cells of discarded objects while labeling, so that they are
+/// neither seeded nor visited again.
+const REJECTED: u32 = u32::MAX;
+
+/// [`label_structures`], also returning the number of flood fills started.
+fn label(
+    mask: &Array3<bool>,
+    x: &Array1<f64>,
+    y: &Array1<f64>,
+    z: &Array1<f64>,
+    attach: &Array1<f64>,
+    min_cells: usize,
+) -> Result<(Array3<u32>, Vec<Structure>, usize), Error> {
+    let (nz, ny, nx) = mask.dim();
+    if attach.len() != nx {
+        return Err(format!("label_structures: {} attach heights for nx = {nx}", attach.len()).into());
+    }
+    if x.len() != nx || y.len() != ny || z.len() != nz {
+        return Err(format!(
+            "label_structures: coordinate lengths ({}, {}, {}) do not match mask (nz={nz}, ny={ny}, nx={nx})",
+            x.len(),
+            y.len(),
+            z.len()
+        )
+        .into());
+    }
+    let dy = if ny > 1 { y[1] - y[0] } else { 1.0 };
+    let ly = dy * ny as f64;
+    let (wx, wz) = (cell_widths(x), cell_widths(z));
+
+    let mut labels = Array3::<u32>::zeros((nz, ny, nx));
+    let mut objects = Vec::new();
+    // stack entries: (k, j, i, unwrapped spanwise index)
+    let mut stack: Vec<(usize, usize, usize, isize)> = Vec::new();
+    let mut members: Vec<(usize, usize, usize)> = Vec::new();
+    let mut next: u32 = 0;
+    let mut fills = 0;
+
+    for ((k0, j0, i0), &on) in mask.indexed_iter() {
+        if !on || labels[[k0, j0, i0]] != 0 {
+            continue;
+        }
+        fills += 1;
+        next += 1;
+        labels[[k0, j0, i0]] = next;
+        stack.push((k0, j0, i0, j0 as isize));
+        members.clear();
+
+        let mut vol = 0.0;
+        let mut cen = [0.0; 3];
+        let mut lo = [f64::INFINITY; 3];
+        let mut hi = [f64::NEG_INFINITY; 3];
+
+        while let Some((k, j, i, ju)) = stack.pop() {
+            members.push((k, j, i));
+            let yu = y[0] + ju as f64 * dy;
+            let dv = wx[i] * dy * wz[k];
+            vol += dv;
+            for (d, c) in [x[i], yu, z[k]].into_iter().enumerate() {
+                cen[d] += dv * c;
+                lo[d] = lo[d].min(c);
+                hi[d] = hi[d].max(c);
+            }
+
+            let mut visit = |kk: usize, jj: usize, ii: usize, jju: isize| {
+                if mask[[kk, jj, ii]] && labels[[kk, jj, ii]] == 0 {
+                    labels[[kk, jj, ii]] = next;
+                    stack.push((kk, jj, ii, jju));
+                }
+            };
+            if k > 0 { visit(k - 1, j, i, ju); }
+            if k + 1 < nz { visit(k + 1, j, i, ju); }
+            if i > 0 { visit(k, j, i - 1, ju); }
+            if i + 1 < nx { visit(k, j, i + 1, ju); }
+            visit(k, (j + ny - 1) % ny, i, ju - 1);
+            visit(k, (j + 1) % ny, i, ju + 1);
+        }
+
+        if members.len() < min_cells {
+            for &(k, j, i) in &members {
+                labels[[k, j, i]] = REJECTED;
+            }
+            next -= 1;
+            continue;
+        }
+        for c in cen.iter_mut() {
+            *c /= vol;
+        }
+        // fold the spanwise centroid back into the domain
+        cen[1] = y[0] + (cen[1] - y[0]).rem_euclid(ly);
+        let ix = (0..nx).min_by(|&a, &b| (x[a] - cen[0]).abs().total_cmp(&(x[b] - cen[0]).abs())).unwrap();
+        objects.push(Structure {
+            label: next,
+            cells: members.len(),
+            volume: vol,
+            centroid: cen,
+            ix,
+            min: lo,
+            max: hi,
+            attached: lo[2] <= attach[ix],
+        });
+    }
+    labels.mapv_inplace(|l| if l == REJECTED { 0 } else { l });
+    Ok((labels, objects, fills))
+}
+
+/// Probability density of `values` over the bins delimited by `edges`
+/// (values outside are dropped; the PDF integrates to the fraction inside).
+pub fn histogram_pdf(values: &[f64], edges: &Array1<f64>) -> Result<Array1<f64>, Error> {
+    if edges.len() < 2 {
+        return Err(format!("histogram_pdf: need at least 2 bin edges, got {}", edges.len()).into());
+    }
+    let nb = edges.len() - 1;
+    let mut pdf = Array1::<f64>::zeros(nb);
+    if values.is_empty() {
+        return Ok(pdf);
+    }
+    for &v in values {
+        if !(v >= edges[0] && v <= edges[nb]) {
+            continue;
+        }
+        // last bin is closed on the right
+        let b = edges
+            .as_slice()
+            .unwrap()
+            .partition_point(|&e| e <= v)
+            .saturating_sub(1)
+            .min(nb - 1);
+        pdf[b] += 1.0;
+    }
+    let n = values.len() as f64;
+    for b in 0..nb {
+        pdf[b] /= n * (edges[b + 1] - edges[b]);
+    }
+    Ok(pdf)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid(n: usize) -> Array1<f64> {
+        Array1::from_shape_fn(n, |i| i as f64)
+    }
+
+    #[test]
+    fn labels_blobs_across_periodic_boundary() {
+        let (nz, ny, nx) = (6, 8, 10);
+        let mut mask = Array3::from_elem((nz, ny, nx), false);
+        // blob A: touches the wall and wraps around y = L_y (j = 7 → j = 0)
+        for k in 0..3 {
+            for &j in &[7, 0, 1] {
+                mask[[k, j, 2]] = true;
+            }
+        }
+        // blob B: detached 2×2×2 cube
+        for k in 3..5 {
+            for j in 3..5 {
+                for i in 6..8 {
+                    mask[[k, j, i]] = true;
+                }
+            }
+        }
+        // isolated point, removed by min_cells
+        mask[[0, 4, 9]] = true;
+
+        let (x, y, z) = (grid(nx), grid(ny), grid(nz));
+        let (labels, objs) = label_structures(&mask, &x, &y, &z, &Array1::from_elem(nx, 0.5), 2).unwrap();
+        assert_eq!(objs.len(), 2);
+        assert_eq!(labels[[0, 4, 9]], 0);
+
+        let a = objs.iter().find(|o| o.cells == 9).unwrap();
+        assert!(a.attached);
+        assert_eq!(labels[[0, 7, 2]], labels[[0, 0, 2]]);
+        assert!((a.extent()[1] - 2.0).abs() < 1e-12, "span {:?}", a.extent());
+        // unwrapped centroid y = 8 → folded to 0
+        assert!(a.centroid[1].abs() < 1e-12, "yc = {}", a.centroid[1]);
+
+        let b = objs.iter().find(|o| o.cells == 8).unwrap();
+        assert!(!b.attached);
+        assert_eq!(b.min, [6.0, 3.0, 3.0]);
+        assert_eq!(b.max, [7.0, 4.0, 4.0]);
+        assert!((b.volume - 8.0).abs() < 1e-12);
+        assert!((b.centroid[0] - 6.5).abs() < 1e-12);
+
+        // attach height per station: the wall blob at x = 2 is detached
+        // where the local viscous length is short
+        let attach = x.mapv(|v| if v < 5.0 { -1.0 } else { 0.5 });
+        let (_, objs) = label_structures(&mask, &x, &y, &z, &attach, 2).unwrap();
+        assert!(objs.iter().all(|o| !o.attached));
+    }
+
+    #[test]
+    fn discarded_objects_are_filled_once() {
+        // one streak of 40 cells below min_cells, seeded from its first cell
+        let (nz, ny, nx) = (2, 2, 40);
+        let mut mask = Array3::from_elem((nz, ny, nx), false);
+        mask.slice_mut(ndarray::s![0, 0, ..]).fill(true);
+        let (x, y, z) = (grid(nx), grid(ny), grid(nz));
+        let (labels, objs, fills) = label(&mask, &x, &y, &z, &Array1::zeros(nx), 50).unwrap();
+        assert!(objs.is_empty() && labels.iter().all(|&l| l == 0));
+        assert_eq!(fills, 1);
+    }
+
+    #[test]
+    fn threshold_with_local_scale() {
+        let f = Array3::from_shape_fn((2, 3, 2), |(k, j, _)| (k + j) as f64).into_dyn();
+        let scale = Array2::from_shape_fn((2, 2), |(k, _)| if k == 0 { 1.0 } else { 2.0 });
+        let m = threshold_mask(&f, Threshold::Above(1.0), Some(scale.view())).unwrap();
+        // k = 0: f = j > 1 → j = 2;  k = 1: f = 1 + j > 2 → j = 2
+        assert_eq!(m.iter().filter(|&&b| b).count(), 4);
+        assert!(m[[0, 2, 0]] && m[[1, 2, 1]] && !m[[1, 1, 0]]);
+
+        let m = threshold_mask(&(-&f), Threshold::AbsAbove(2.5), None).unwrap();
+        assert_eq!(m.iter().filter(|&&b| b).count(), 2);
+    }
+
+    #[test]
+    fn pdf_integrates_to_one() {
+        let v: Vec<f64> = (0..100).map(|i| i as f64 / 99.0).collect();
+        let edges = Array1::linspace(0.0, 1.0, 11);
+        let pdf = histogram_pdf(&v, &edges).unwrap();
+        let total: f64 = pdf.iter().map(|p| p * 0.1).sum();
+        assert!((total - 1.0).abs() < 1e-12);
+        assert!(pdf.iter().all(|&p| (p - 1.0).abs() < 0.2));
+        assert!(histogram_pdf(&v, &Array1::from_elem(1, 0.0)).is_err());
+    }
+}
@@ -353,6 +353,90 @@ pub fn stress_budget(
     })
 }
 
+/// Compute the mean-kinetic-energy budget, K = ½ ū_i ū_i, from a subavg
+/// file's mean fields and stored total moments, in the same `BudgetTerms`
+/// layout as [`stress_budget`]:
+///
+///   C = ū_k ∂K/∂x_k
+///     = ⟨u_i'u_k'⟩ ∂ū_i/∂x_k                (`prod`: the production sink,
+///                                            = −P_k, usually negative)
+///     − ∂(ū_i ⟨u_i'u_k'⟩)/∂x_k             (`turb_trans`)
+///     + ν ∂²K/∂x_k²                         (`visc_trans`)
+///     − ∂(p̄ ū_k)/∂x_k                       (`press_trans`)
+///     − ν ∂ū_i/∂x_k ∂ū_i/∂x_k               (`visc_diss`, reported positive)
+///
+/// `stress` holds K and `press_strain` is zero (pressure only transports
+/// MKE).  The sink and the mean dissipation use the stored mean gradients
+/// (ux…wz) like `stress_budget`, so `prod` is exactly minus the TKE
+/// production; the transport and convection terms differentiate
+/// numerically, evaluated pointwise and spanwise-averaged at the end.
+pub fn mke_budget(
+    load: &mut dyn FnMut(&str) -> Result<ArrayD<f64>, Error>,
+    x:  &Array1<f64>,
+    y:  &Array1<f64>,
+    zc: &Array1<f64>,
+    nu: f64,
+    periodic_x: bool,
+) -> Result<BudgetTerms, Error> {
+    let with_y = y.len() >= 3;
+    let d1 = |f: &ArrayD<f64>, k: usize| -> Result<ArrayD<f64>, Error> {
+        match k {
+            0 => deriv1(f, 2, x, periodic_x),
+            1 => deriv1(f, 1, y, true),
+            _ => deriv1(f, 0, zc, false),
+        }
+    };
+    let d2 = |f: &ArrayD<f64>, k: usize| -> Result<ArrayD<f64>, Error> {
+        match k {
+            0 => deriv2(f, 2, x, periodic_x),
+            1 => deriv2(f, 1, y, true),
+            _ => deriv2(f, 0, zc, false),
+        }
+    };
+    let dirs: Vec<usize> = (0..3).filter(|&k| k != 1 || with_y).collect();
+
+    let ubar = [load("u")?, load("v")?, load("w")?];
+    let pbar = load("p")?;
+    let mke = (&(&(&ubar[0] * &ubar[0]) + &(&ubar[1] * &ubar[1])) + &(&ubar[2] * &ubar[2])) * 0.5;
+    let zeros = ArrayD::<f64>::zeros(mke.raw_dim());
+
+    let mut prod = zeros.clone();
+    let mut diss = zeros.clone();
+    let mut turb = zeros.clone();
+    let mut ptr = zeros.clone();
+    let mut visc = zeros.clone();
+    let mut conv = zeros.clone();
+    for k in 0..3 {
+        // ū_i ⟨u_i'u_k'⟩, accumulated over i for the transport flux
+        let mut flux = zeros.clone();
+        for i in 0..3 {
+            let rik = &load(&mom2_name(i, k))? - &(&ubar[i] * &ubar[k]);
+            let g = load(&format!("{}{}", VEL[i], DIR[k]))?;
+            prod = &prod + &(&rik * &g);
+            diss = &diss + &(&g * &g);
+            flux = &flux + &(&ubar[i] * &rik);
+        }
+        if dirs.contains(&k) {
+            turb = &turb - &d1(&flux, k)?;
+            ptr = &ptr - &d1(&(&pbar * &ubar[k]), k)?;
+            visc = &visc + &d2(&mke, k)?;
+            conv = &conv + &(&ubar[k] * &d1(&mke, k)?);
+        }
+    }
+
+    let avg = |a: &ArrayD<f64>| avg_axis(a, 1);
+    Ok(BudgetTerms {
+        stress:       avg(&mke)?,
+        prod:         avg(&prod)?,
+        turb_trans:   avg(&turb)?,
+        visc_trans:   avg(&(visc * nu))?,
+        press_strain: avg(&zeros)?,
+        press_trans:  avg(&ptr)?,
+        visc_diss:    avg(&(diss * nu))?,
+        conv:         avg(&conv)?,
+    })
+}
+
 // ─── Tests ────────────────────────────────────────────────────────────────────
 
 #[cfg(test)]
@@ -509,4 +593,46 @@ mod tests {
         check("uw stress", &b.stress, r_uw);
         check("uw diss", &b.visc_diss, 0.0);
     }
+
+    /// MKE budget of the same homogeneous shear layer, ū = S·z:
+    ///
+    ///   sink ⟨u'w'⟩S = R_uw·S,  turbulent transport −∂(ū R_uw)/∂z = −R_uw·S,
+    ///   viscous transport ν∂²(½S²z²)/∂z² = νS²,  dissipation νS²,
+    ///
+    /// convection and pressure transport zero, so the balance closes.
+    #[test]
+    fn homogeneous_shear_mke_budget() {
+        let (nz, ny, nx) = (8, 4, 6);
+        let (s_shear, nu, r_uw) = (2.5_f64, 1e-3_f64, -1.2e-3_f64);
+        let zc: Array1<f64> = Array1::linspace(0.1, 1.9, nz);
+        let x:  Array1<f64> = Array1::linspace(0.0, 5.0, nx);
+        let y:  Array1<f64> = Array1::linspace(0.0, 0.3, ny);
+
+        let full = |v: f64| ArrayD::from_elem(ndarray::IxDyn(&[nz, ny, nx]), v);
+        let ubar = Array3::from_shape_fn((nz, ny, nx), |(iz, _, _)| s_shear * zc[iz]).into_dyn();
+        let mut load = |name: &str| -> Result<ArrayD<f64>, Error> {
+            Ok(match name {
+                "u" => ubar.clone(),
+                "uu" => &ubar * &ubar,
+                "uw" => &ubar * &full(0.0) + &full(r_uw),
+                "uz" => full(s_shear),
+                _ => full(0.0),
+            })
+        };
+
+        let b = mke_budget(&mut load, &x, &y, &zc, nu, false).expect("mke budget failed");
+        let check = |name: &str, field: &ArrayD<f64>, want: f64| {
+            let max_err = field.iter().map(|v| (v - want).abs()).fold(0.0_f64, f64::max);
+            assert!(max_err < 1e-10, "{name}: max |value − {want}| = {max_err:.2e}");
+        };
+        check("prod", &b.prod, r_uw * s_shear);
+        check("turb_trans", &b.turb_trans, -r_uw * s_shear);
+        check("visc_trans", &b.visc_trans, nu * s_shear * s_shear);
+        check("visc_diss", &b.visc_diss, nu * s_shear * s_shear);
+        check("press_trans", &b.press_trans, 0.0);
+        check("conv", &b.conv, 0.0);
+        check("balance", &b.balance(), 0.0);
+        let k_mid = 0.5 * (s_shear * zc[3]).powi(2);
+        assert!((b.stress[[3, 2]] - k_mid).abs() < 1e-12);
+    }
 }
@@ -0,0 +1,101 @@
+//! Detection of corrupted inflow/outflow regions along x.
+//!
+//! Each indicator is a non-negative streamwise profile that is small and
+//! roughly uniform in the well-behaved interior, e.g. the RMS over (z, y) of
+//! the momentum residual or of ∇·u, or the mismatch |C_f,FIK − C_f,direct|.
+//! Normalizing every indicator by its median over x and taking the largest
+//! gives a score of order one in the interior:
+//!
+//!   s(x) = max_k  I_k(x) / median_x I_k.
+//!
+//! The trim is peeled from each end: the kept range starts at the first run
+//! of `min_run` consecutive points with s ≤ `tol` and ends after the last
+//! such run.  Interior peaks (e.g. over an actuator) are never trimmed.
+
+use hdf5::Error;
+use ndarray::{Array1, ArrayD, Axis};
+
+/// RMS over all axes but the last (x) of a `(.., nx)` field.
+pub fn x_profile_rms(a: &ArrayD<f64>) -> Array1<f64> {
+    let nx = a.shape()[a.ndim() - 1];
+    Array1::from_shape_fn(nx, |i| {
+        let lane = a.index_axis(Axis(a.ndim() - 1), i);
+        (lane.iter().map(|v| v * v).sum::<f64>() / lane.len() as f64).sqrt()
+    })
+}
+
+/// Combined score s(x) of the indicator profiles (all of length nx).
+pub fn trim_score(indicators: &[Array1<f64>]) -> Result<Array1<f64>, Error> {
+    let nx = indicators.first().ok_or_else(|| Error::from("trim_score: no indicators"))?.len();
+    let mut score = Array1::<f64>::zeros(nx);
+    for ind in indicators {
+        if ind.len() != nx {
+            return Err(format!("trim_score: indicator lengths {} and {nx} differ", ind.len()).into());
+        }
+        let mut sorted: Vec<f64> = ind.iter().map(|v| v.abs()).collect();
+        sorted.sort_by(|a, b| a.total_cmp(b));
+        let med = sorted[nx / 2];
+        if med <= 0.0 || !med.is_finite() {
+            continue;
+        }
+        for (s, v) in score.iter_mut().zip(ind.iter()) {
+            let r = v.abs() / med;
+            // NaN marks corrupted data: always out of tolerance
+            *s = if r.is_nan() { f64::INFINITY } else { s.max(r) };
+        }
+    }
+    Ok(score)
+}
+
+/// Trim `(start, end)` point counts that keep the interior of `score`
+/// between the first and last run of `min_run` points with s ≤ `tol`.
+pub fn detect_trim(score: &Array1<f64>, tol: f64, min_run: usize) -> Result<(usize, usize), Error> {
+    let nx = score.len();
+    let run = min_run.max(1);
+    let good = |i: usize| (i..i + run).all(|k| score[k] <= tol);
+    if nx < run {
+        return Err(format!("detect_trim: {nx} points, fewer than min_run = {run}").into());
+    }
+    let lo = (0..=nx - run).find(|&i| good(i));
+    let hi = (0..=nx - run).rev().find(|&i| good(i)).map(|i| i + run);
+    match (lo, hi) {
+        (Some(lo), Some(hi)) => Ok((lo, nx - hi)),
+        _ => Err(format!("detect_trim: no run of {run} points with score ≤ {tol}").into()),
+    }
+}
+
+// ─── Tests ────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::Array3;
+
+    #[test]
+    fn peels_corrupted_ends_keeps_interior_peak() {
+        let nx = 60;
+        // residual ~1 in the interior, huge over the first 5 and last 3
+        // points, one isolated quiet point inside the lead-in, and a
+        // physical peak (actuator) at x = 30
+        let res = Array1::from_shape_fn(nx, |i| match i {
+            2 => 1.0,
+            0..=4 => 50.0,
+            29..=31 => 20.0,
+            i if i >= nx - 3 => 1e3,
+            _ => 1.0 + 0.1 * (i as f64).sin(),
+        });
+        let cf_err = Array1::from_shape_fn(nx, |i| if i == nx - 4 { f64::NAN } else { 0.01 });
+        let s = trim_score(&[res, cf_err]).unwrap();
+        assert_eq!(detect_trim(&s, 3.0, 3).unwrap(), (5, 4));
+        assert!(detect_trim(&Array1::from_elem(10, 10.0), 3.0, 3).is_err());
+    }
+
+    #[test]
+    fn profile_rms_over_z_and_y() {
+        let a = Array3::from_shape_fn((4, 3, 5), |(k, _, i)| if k % 2 == 0 { i as f64 } else { -(i as f64) })
+            .into_dyn();
+        let r = x_profile_rms(&a);
+        assert_eq!(r.len(), 5);
+        assert!(r.iter().enumerate().all(|(i, &v)| (v - i as f64).abs() < 1e-12));
+    }
+}
@@ -1,4 +1,4 @@
-/// Vortex-identification criteria (Q and λ₂) for instantaneous 3-D fields.
+/// Vortex-identification criteria for instantaneous 3-D fields.
 ///
 /// Array convention (matches the rest of this crate): shape (nz, ny, nx)
 ///   axis 0 = z (wall-normal), axis 1 = y (spanwise), axis 2 = x (streamwise)
@@ -9,7 +9,7 @@
 ///
 ///   S_ij = (g_ij + g_ji)/2,      Ω_ij = (g_ij − g_ji)/2,
 ///
-/// the two criteria are:
+/// the classic criteria are:
 ///
 /// * Q criterion (Hunt, Wray & Moin 1988):
 ///     Q = ½(‖Ω‖² − ‖S‖²),  vortex where Q > 0
@@ -20,6 +20,26 @@
 ///   vortex where λ₂ < 0 (pressure sectional minimum in the plane
 ///   perpendicular to the vortex axis).
 ///
+/// The eigenvalue-based criteria use the characteristic polynomial of g,
+///   λ³ + Pλ² + Qλ + R = 0,  P = −tr g,  Q = ½(P² − tr g²),  R = −det g,
+/// reduced to t³ + pt + q = 0 (λ = t − P/3):
+///
+/// * Δ criterion (Chong, Perry & Cantwell 1990): Δ = (p/3)³ + (q/2)²,
+///   vortex where Δ > 0 (complex eigenvalue pair).
+///
+/// * Swirling strength λ_ci (Zhou et al. 1999): imaginary part of the complex
+///   eigenvalue pair (0 where Δ ≤ 0).  The signed variant carries the sign of
+///   the spanwise vorticity ω_y = ∂u/∂z − ∂w/∂x, so at the lower wall (mean
+///   ω_y > 0) positive = prograde, negative = retrograde.
+///
+/// * Ω criterion (Liu et al. 2016): Ω = ‖Ω‖² / (‖S‖² + ‖Ω‖² + ε) with
+///   ε = 0.001 · max(‖Ω‖² − ‖S‖²); vortex where Ω > 0.52.
+///
+/// * Liutex / Rortex (Liu et al. 2018, Wang et al. 2019): direction r = the
+///   real eigenvector of g oriented so that ω·r > 0, magnitude
+///   R = ω·r − √((ω·r)² − 4λ_ci²), i.e. the rigid-rotation part of the
+///   vorticity with the shear removed.
+///
 /// Derivatives use the second-order stencils of `math/ns.rs` (`deriv1`),
 /// non-uniform-grid aware; the spanwise direction is normally periodic.
 use hdf5::Error;
@@ -80,16 +100,48 @@ fn middle_eigenvalue_sym3(
     3.0 * q - eig_hi - eig_lo // trace identity → middle eigenvalue
 }
 
-/// Compute the Q and λ₂ vortex-identification fields.
+/// Velocity-gradient tensor g[i][j] = ∂u_i/∂x_j, each component of the
+/// input field shape (i, j ∈ {x, y, z}; u_x = u, u_y = v, u_z = w).
+pub struct VelocityGradient {
+    pub g: [[ArrayD<f64>; 3]; 3],
+}
+
+impl VelocityGradient {
+    /// Vorticity components ω = ∇×u as `[ω_x, ω_y, ω_z]`.
+    pub fn vorticity(&self) -> [ArrayD<f64>; 3] {
+        let g = &self.g;
+        [
+            &g[2][1] - &g[1][2],
+            &g[0][2] - &g[2][0],
+            &g[1][0] - &g[0][1],
+        ]
+    }
+
+    /// The nine components at flat index `i` (all arrays are standard layout).
+    #[inline]
+    pub(crate) fn at(&self, i: usize) -> [[f64; 3]; 3] {
+        let c = |a: usize, b: usize| self.g[a][b].as_slice().unwrap()[i];
+        [
+            [c(0, 0), c(0, 1), c(0, 2)],
+            [c(1, 0), c(1, 1), c(1, 2)],
+            [c(2, 0), c(2, 1), c(2, 2)],
+        ]
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.g[0][0].len()
+    }
+}
+
+/// Compute the velocity-gradient tensor with the second-order stencils of
+/// `math/ns.rs`.
 ///
 /// # Arguments
 /// * `u`, `v`, `w` – velocity components (streamwise, spanwise, wall-normal),
 ///   all shaped `(nz, ny, nx)`.
 /// * `x`, `y`, `z` – coordinates along axes 2, 1, 0 (lengths nx, ny, nz).
-/// * `periodic`    – boundary treatment per axis `[z, y, x]`;
-///   DNS channel-flow convention: `[false, true, false]` for a spatially
-///   developing simulation (`[false, true, true]` when streamwise-periodic).
-pub fn vortex_criteria(
+/// * `periodic`    – boundary treatment per axis `[z, y, x]`.
+pub fn velocity_gradient(
     u: &ArrayD<f64>,
     v: &ArrayD<f64>,
     w: &ArrayD<f64>,
@@ -97,7 +149,7 @@ pub fn vortex_criteria(
     y: &Array1<f64>,
     z: &Array1<f64>,
     periodic: [bool; 3],
-) -> Result<VortexFields, Error> {
+) -> Result<VelocityGradient, Error> {
     let shape = u.shape().to_vec();
     if shape.len() != 3 {
         return Err(format!("expected 3-D fields, got shape {shape:?}").into());
@@ -116,20 +168,47 @@ pub fn vortex_criteria(
         .into());
     }
 
-    // Velocity-gradient tensor, physical indexing g[i][j] = ∂u_i/∂x_j.
     // Axis mapping: x → array axis 2, y → axis 1, z → axis 0.
-    let dux = deriv1(u, 2, x, periodic[2])?;
-    let duy = deriv1(u, 1, y, periodic[1])?;
-    let duz = deriv1(u, 0, z, periodic[0])?;
-    let dvx = deriv1(v, 2, x, periodic[2])?;
-    let dvy = deriv1(v, 1, y, periodic[1])?;
-    let dvz = deriv1(v, 0, z, periodic[0])?;
-    let dwx = deriv1(w, 2, x, periodic[2])?;
-    let dwy = deriv1(w, 1, y, periodic[1])?;
-    let dwz = deriv1(w, 0, z, periodic[0])?;
-
-    let mut q_field = ArrayD::<f64>::zeros(u.raw_dim());
-    let mut l2_field = ArrayD::<f64>::zeros(u.raw_dim());
+    let grad = |f: &ArrayD<f64>| -> Result<[ArrayD<f64>; 3], Error> {
+        Ok([
+            deriv1(f, 2, x, periodic[2])?,
+            deriv1(f, 1, y, periodic[1])?,
+            deriv1(f, 0, z, periodic[0])?,
+        ])
+    };
+    Ok(VelocityGradient {
+        g: [grad(u)?, grad(v)?, grad(w)?],
+    })
+}
+
+/// Compute the Q and λ₂ vortex-identification fields.
+///
+/// # Arguments
+/// * `u`, `v`, `w` – velocity components (streamwise, spanwise, wall-normal),
+///   all shaped `(nz, ny, nx)`.
+/// * `x`, `y`, `z` – coordinates along axes 2, 1, 0 (lengths nx, ny, nz).
+/// * `periodic`    – boundary treatment per axis `[z, y, x]`;
+///   DNS channel-flow convention: `[false, true, false]` for a spatially
+///   developing simulation (`[false, true, true]` when streamwise-periodic).
+pub fn vortex_criteria(
+    u: &ArrayD<f64>,
+    v: &ArrayD<f64>,
+    w: &ArrayD<f64>,
+    x: &Array1<f64>,
+    y: &Array1<f64>,
+    z: &Array1<f64>,
+    periodic: [bool; 3],
+) -> Result<VortexFields, Error> {
+    let grad = velocity_gradient(u, v, w, x, y, z, periodic)?;
+    Ok(q_lambda2(&grad))
+}
+
+/// Q and λ₂ from a precomputed velocity gradient.
+pub fn q_lambda2(grad: &VelocityGradient) -> VortexFields {
+    let [[dux, duy, duz], [dvx, dvy, dvz], [dwx, dwy, dwz]] = &grad.g;
+
+    let mut q_field = ArrayD::<f64>::zeros(dux.raw_dim());
+    let mut l2_field = ArrayD::<f64>::zeros(dux.raw_dim());
 
     // All arrays are freshly allocated in standard layout → flat slices.
     let (gxx, gxy, gxz) = (
@@ -184,12 +263,295 @@ pub fn vortex_criteria(
         l2s[i] = middle_eigenvalue_sym3(a11, a22, a33, a12, a13, a23);
     }
 
-    Ok(VortexFields {
+    VortexFields {
         q: q_field,
         lambda2: l2_field,
+    }
+}
+
+/// Vorticity-derived fields of one snapshot, each of the input field shape.
+pub struct VorticityFields {
+    /// ω = ∇×u components.
+    pub omega_x: ArrayD<f64>,
+    pub omega_y: ArrayD<f64>,
+    pub omega_z: ArrayD<f64>,
+    /// Enstrophy density ½|ω|².
+    pub enstrophy: ArrayD<f64>,
+    /// Helicity density u·ω.
+    pub helicity: ArrayD<f64>,
+    /// Strain-rate magnitude |S| = √(2 S_ij S_ij).
+    pub strain: ArrayD<f64>,
+}
+
+/// Vorticity, enstrophy, helicity and strain-rate magnitude from a velocity
+/// gradient and the velocities it was computed from.
+pub fn vorticity_fields(
+    u: &ArrayD<f64>,
+    v: &ArrayD<f64>,
+    w: &ArrayD<f64>,
+    grad: &VelocityGradient,
+) -> Result<VorticityFields, Error> {
+    if u.shape() != grad.g[0][0].shape() || v.shape() != u.shape() || w.shape() != u.shape() {
+        return Err("velocity and gradient shapes do not match".into());
+    }
+    let [ox, oy, oz] = grad.vorticity();
+    let enstrophy = (&(&ox * &ox) + &(&(&oy * &oy) + &(&oz * &oz))) * 0.5;
+    let helicity = &(&(u * &ox) + &(v * &oy)) + &(w * &oz);
+    let mut strain = ArrayD::<f64>::zeros(u.raw_dim());
+    for (i, s) in strain.iter_mut().enumerate() {
+        let g = grad.at(i);
+        let mut ss = 0.0;
+        for (a, row) in g.iter().enumerate() {
+            for (b, gab) in row.iter().enumerate() {
+                let sab = 0.5 * (gab + g[b][a]);
+                ss += sab * sab;
+            }
+        }
+        *s = (2.0 * ss).sqrt();
+    }
+    Ok(VorticityFields {
+        omega_x: ox,
+        omega_y: oy,
+        omega_z: oz,
+        enstrophy,
+        helicity,
+        strain,
     })
 }
 
+/// Selectable vortex-identification fields for [`vortex_fields`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Criterion {
+    Q,
+    Lambda2,
+    LambdaCi,
+    SignedLambdaCi,
+    Delta,
+    Omega,
+    Liutex,
+}
+
+impl Criterion {
+    pub const ALL: [Criterion; 7] = [
+        Criterion::Q,
+        Criterion::Lambda2,
+        Criterion::LambdaCi,
+        Criterion::SignedLambdaCi,
+        Criterion::Delta,
+        Criterion::Omega,
+        Criterion::Liutex,
+    ];
+
+    /// Output / command-line name of the field.
+    pub fn name(self) -> &'static str {
+        match self {
+            Criterion::Q => "q",
+            Criterion::Lambda2 => "lambda2",
+            Criterion::LambdaCi => "lambda_ci",
+            Criterion::SignedLambdaCi => "lambda_ci_signed",
+            Criterion::Delta => "delta",
+            Criterion::Omega => "omega",
+            Criterion::Liutex => "liutex",
+        }
+    }
+
+    /// Parse a command-line name (see [`Criterion::name`]).
+    pub fn from_name(name: &str) -> Result<Self, Error> {
+        Self::ALL
+            .into_iter()
+            .find(|c| c.name() == name)
+            .ok_or_else(|| {
+                let known: Vec<&str> = Self::ALL.iter().map(|c| c.name()).collect();
+                format!("unknown vortex field '{name}' (known: {})", known.join(", ")).into()
+            })
+    }
+}
+
+/// Eigen-structure of a 3×3 velocity gradient: `(λ_r, λ_ci, Δ)` with λ_r the
+/// real eigenvalue (meaningful only when Δ > 0), λ_ci ≥ 0 the imaginary part
+/// of the complex pair and Δ the discriminant of the reduced cubic.
+#[inline]
+fn gradient_eigen(g: &[[f64; 3]; 3]) -> (f64, f64, f64) {
+    let tr = g[0][0] + g[1][1] + g[2][2];
+    let mut tr_g2 = 0.0;
+    for (a, row) in g.iter().enumerate() {
+        for (b, gab) in row.iter().enumerate() {
+            tr_g2 += gab * g[b][a];
+        }
+    }
+    let det = g[0][0] * (g[1][1] * g[2][2] - g[1][2] * g[2][1])
+        - g[0][1] * (g[1][0] * g[2][2] - g[1][2] * g[2][0])
+        + g[0][2] * (g[1][0] * g[2][1] - g[1][1] * g[2][0]);
+    let (pc, qc, rc) = (-tr, 0.5 * (tr * tr - tr_g2), -det);
+    // λ = t − P/3 → t³ + p t + q = 0
+    let p = qc - pc * pc / 3.0;
+    let q = 2.0 * pc * pc * pc / 27.0 - pc * qc / 3.0 + rc;
+    let delta = (p / 3.0).powi(3) + (q / 2.0).powi(2);
+    if delta <= 0.0 {
+        return (0.0, 0.0, delta);
+    }
+    let sq = delta.sqrt();
+    let a = (-0.5 * q + sq).cbrt();
+    let b = (-0.5 * q - sq).cbrt();
+    (a + b - pc / 3.0, 0.5 * 3f64.sqrt() * (a - b).abs(), delta)
+}
+
+/// Unit real eigenvector of `g` for the real eigenvalue `lr`: the largest
+/// cross product of two rows of g − λ_r I (all rows are ⟂ to it).
+#[inline]
+fn real_eigenvector(g: &[[f64; 3]; 3], lr: f64) -> [f64; 3] {
+    let mut m = *g;
+    for (k, row) in m.iter_mut().enumerate() {
+        row[k] -= lr;
+    }
+    let cross = |a: &[f64; 3], b: &[f64; 3]| {
+        [
+            a[1] * b[2] - a[2] * b[1],
+            a[2] * b[0] - a[0] * b[2],
+            a[0] * b[1] - a[1] * b[0],
+        ]
+    };
+    let norm2 = |c: &[f64; 3]| c[0] * c[0] + c[1] * c[1] + c[2] * c[2];
+    let cands = [cross(&m[0], &m[1]), cross(&m[0], &m[2]), cross(&m[1], &m[2])];
+    let best = cands
+        .into_iter()
+        .max_by(|a, b| norm2(a).partial_cmp(&norm2(b)).unwrap())
+        .unwrap();
+    let n = norm2(&best).sqrt();
+    if n == 0.0 {
+        return [0.0; 3];
+    }
+    [best[0] / n, best[1] / n, best[2] / n]
+}
+
+/// Compute the selected vortex-identification fields from a velocity
+/// gradient.  Returns `(name, field)` pairs in the order requested (a
+/// repeated criterion is returned once); Liutex adds its magnitude `liutex`
+/// followed by the components `liutex_x`, `liutex_y`, `liutex_z`.  Only the
+/// fields the selected criteria need are allocated.
+pub fn vortex_fields(
+    grad: &VelocityGradient,
+    which: &[Criterion],
+) -> Result<Vec<(String, ArrayD<f64>)>, Error> {
+    let dim = grad.g[0][0].raw_dim();
+    let n = grad.len();
+    let want = |c: Criterion| which.contains(&c);
+    let eigen_needed = [
+        Criterion::LambdaCi,
+        Criterion::SignedLambdaCi,
+        Criterion::Delta,
+        Criterion::Liutex,
+    ]
+    .into_iter()
+    .any(want);
+
+    let zeros = || ArrayD::<f64>::zeros(dim.clone());
+    let field = |c: Criterion| want(c).then(zeros);
+    let (mut lci, mut slci, mut delta) =
+        (field(Criterion::LambdaCi), field(Criterion::SignedLambdaCi), field(Criterion::Delta));
+    let (mut s2, mut o2) = (field(Criterion::Omega), field(Criterion::Omega));
+    let mut liutex = want(Criterion::Liutex).then(|| [zeros(), zeros(), zeros(), zeros()]);
+
+    if eigen_needed || s2.is_some() {
+        fn slice(a: &mut Option<ArrayD<f64>>) -> Option<&mut [f64]> {
+            a.as_mut().map(|a| a.as_slice_mut().unwrap())
+        }
+        let (mut lci_s, mut slci_s, mut delta_s) = (slice(&mut lci), slice(&mut slci), slice(&mut delta));
+        let (mut s2_s, mut o2_s) = (slice(&mut s2), slice(&mut o2));
+        let mut lr_s = liutex.as_mut().map(|l| l.each_mut().map(|a| a.as_slice_mut().unwrap()));
+
+        for i in 0..n {
+            let g = grad.at(i);
+            let om = [g[2][1] - g[1][2], g[0][2] - g[2][0], g[1][0] - g[0][1]];
+            if let (Some(s2_s), Some(o2_s)) = (&mut s2_s, &mut o2_s) {
+                let mut ss = 0.0;
+                for (a, row) in g.iter().enumerate() {
+                    for (b, gab) in row.iter().enumerate() {
+                        let sab = 0.5 * (gab + g[b][a]);
+                        ss += sab * sab;
+                    }
+                }
+                s2_s[i] = ss;
+                o2_s[i] = 0.5 * (om[0] * om[0] + om[1] * om[1] + om[2] * om[2]);
+            }
+
+            if !eigen_needed {
+                continue;
+            }
+            let (lr_val, ci, d) = gradient_eigen(&g);
+            if let Some(s) = &mut delta_s {
+                s[i] = d;
+            }
+            if let Some(s) = &mut lci_s {
+                s[i] = ci;
+            }
+            if let Some(s) = &mut slci_s {
+                s[i] = if om[1] < 0.0 { -ci } else { ci };
+            }
+            if let Some([lr, lx, ly, lz]) = &mut lr_s
+                && ci > 0.0
+            {
+                let mut r = real_eigenvector(&g, lr_val);
+                let mut wr = om[0] * r[0] + om[1] * r[1] + om[2] * r[2];
+                if wr < 0.0 {
+                    r = [-r[0], -r[1], -r[2]];
+                    wr = -wr;
+                }
+                let mag = wr - (wr * wr - 4.0 * ci * ci).max(0.0).sqrt();
+                lr[i] = mag;
+                lx[i] = mag * r[0];
+                ly[i] = mag * r[1];
+                lz[i] = mag * r[2];
+            }
+        }
+    }
+
+    let (mut q, mut lambda2) = if want(Criterion::Q) || want(Criterion::Lambda2) {
+        let ql = q_lambda2(grad);
+        (Some(ql.q), Some(ql.lambda2))
+    } else {
+        (None, None)
+    };
+    let mut out: Vec<(String, ArrayD<f64>)> = Vec::new();
+    for &c in which {
+        let mut push = |a: Option<ArrayD<f64>>| {
+            if let Some(a) = a {
+                out.push((c.name().into(), a));
+            }
+        };
+        match c {
+            Criterion::Q => push(q.take()),
+            Criterion::Lambda2 => push(lambda2.take()),
+            Criterion::LambdaCi => push(lci.take()),
+            Criterion::SignedLambdaCi => push(slci.take()),
+            Criterion::Delta => push(delta.take()),
+            Criterion::Omega => {
+                let (Some(s2), Some(o2)) = (s2.take(), o2.take()) else { continue };
+                let emax = o2
+                    .iter()
+                    .zip(s2.iter())
+                    .map(|(b, a)| b - a)
+                    .fold(f64::NEG_INFINITY, f64::max);
+                let eps = 1e-3 * emax.max(0.0);
+                let mut om = o2;
+                for (r, &a) in om.iter_mut().zip(s2.iter()) {
+                    let den = a + *r + eps;
+                    *r = if den > 0.0 { *r / den } else { 0.0 };
+                }
+                push(Some(om));
+            }
+            Criterion::Liutex => {
+                let Some([lr, lx, ly, lz]) = liutex.take() else { continue };
+                out.push(("liutex".into(), lr));
+                out.push(("liutex_x".into(), lx));
+                out.push(("liutex_y".into(), ly));
+                out.push(("liutex_z".into(), lz));
+            }
+        }
+    }
+    Ok(out)
+}
+
 // ─── Tests ────────────────────────────────────────────────────────────────────
 
 #[cfg(test)]
@@ -327,4 +689,95 @@ mod tests {
             }
         }
     }
+
+    /// Linear field with g = [[−a/2, −ω, γ], [ω, −a/2, 0], [0, 0, a]]:
+    /// eigenvalues a and −a/2 ± iω, so λ_ci = ω and Δ > 0; the real
+    /// eigenvector solves (g − aI)r = 0 in closed form, giving the Liutex
+    /// magnitude R = ω·r − √((ω·r)² − 4ω²).  ω_y = γ > 0 → signed λ_ci > 0.
+    #[test]
+    fn swirl_with_axial_strain_and_shear() {
+        let (nz, ny, nx) = (5, 6, 7);
+        let (a, om, gam) = (0.8_f64, 1.5_f64, 0.6_f64);
+        let x = linspace(0.0, 1.0, nx);
+        let y = linspace(0.0, 1.2, ny);
+        let z = linspace(0.0, 0.9, nz);
+        let u = Array3::from_shape_fn((nz, ny, nx), |(k, j, i)| {
+            -0.5 * a * x[i] - om * y[j] + gam * z[k]
+        })
+        .into_dyn();
+        let v = Array3::from_shape_fn((nz, ny, nx), |(_, j, i)| om * x[i] - 0.5 * a * y[j])
+            .into_dyn();
+        let w = Array3::from_shape_fn((nz, ny, nx), |(k, _, _)| a * z[k]).into_dyn();
+
+        let grad = velocity_gradient(&u, &v, &w, &x, &y, &z, [false; 3]).unwrap();
+        let which = [
+            Criterion::LambdaCi,
+            Criterion::SignedLambdaCi,
+            Criterion::Delta,
+            Criterion::Liutex,
+        ];
+        let f = vortex_fields(&grad, &which).unwrap();
+        let get = |name: &str| &f.iter().find(|(n, _)| n == name).unwrap().1;
+
+        let d = 2.25 * a * a + om * om;
+        let r = [1.5 * a * gam / d, om * gam / d, 1.0];
+        let rn = (r[0] * r[0] + r[1] * r[1] + r[2] * r[2]).sqrt();
+        let wr = (gam * r[1] + 2.0 * om * r[2]) / rn;
+        let mag = wr - (wr * wr - 4.0 * om * om).sqrt();
+
+        let p = [2, 3, 4];
+        assert!((get("lambda_ci")[p] - om).abs() < 1e-10, "λ_ci = {}", get("lambda_ci")[p]);
+        assert!((get("lambda_ci_signed")[p] - om).abs() < 1e-10);
+        assert!(get("delta")[p] > 0.0);
+        assert!((get("liutex")[p] - mag).abs() < 1e-9, "R = {} vs {mag}", get("liutex")[p]);
+        assert!((get("liutex_z")[p] - mag / rn).abs() < 1e-9);
+        assert!((get("liutex_x")[p] - mag * r[0] / rn).abs() < 1e-9);
+    }
+
+    /// Simple shear has no swirl: λ_ci = 0, Liutex = 0, and the Ω criterion
+    /// sits at exactly ½ (rotation and strain balance).
+    #[test]
+    fn simple_shear_has_no_swirl() {
+        let (nz, ny, nx) = (6, 4, 5);
+        let x = linspace(0.0, 1.0, nx);
+        let y = linspace(0.0, 1.0, ny);
+        let z = linspace(0.0, 1.0, nz);
+        let u = Array3::from_shape_fn((nz, ny, nx), |(k, _, _)| 2.0 * z[k]).into_dyn();
+        let zero = ArrayD::<f64>::zeros(u.raw_dim());
+
+        let grad = velocity_gradient(&u, &zero, &zero, &x, &y, &z, [false; 3]).unwrap();
+        let names: Vec<Criterion> = ["lambda_ci", "omega", "liutex"]
+            .iter()
+            .map(|n| Criterion::from_name(n).unwrap())
+            .collect();
+        let f = vortex_fields(&grad, &names).unwrap();
+        assert!(Criterion::from_name("swirl").is_err());
+        for (name, a) in &f {
+            let expected = if name == "omega" { 0.5 } else { 0.0 };
+            assert!(a.iter().all(|&v| (v - expected).abs() < 1e-12), "{name}");
+        }
+    }
+
+    /// Solid-body rotation about z plus a uniform axial velocity W: ω = (0, 0,
+    /// 2ω), enstrophy 2ω², helicity 2ωW and no strain.
+    #[test]
+    fn vorticity_of_rotating_column() {
+        let (nz, ny, nx) = (5, 6, 7);
+        let (om, wz) = (1.25_f64, 0.4_f64);
+        let x = linspace(0.0, 1.0, nx);
+        let y = linspace(0.0, 1.0, ny);
+        let z = linspace(0.0, 1.0, nz);
+        let u = Array3::from_shape_fn((nz, ny, nx), |(_, j, _)| -om * y[j]).into_dyn();
+        let v = Array3::from_shape_fn((nz, ny, nx), |(_, _, i)| om * x[i]).into_dyn();
+        let w = ArrayD::<f64>::from_elem(u.raw_dim(), wz);
+
+        let grad = velocity_gradient(&u, &v, &w, &x, &y, &z, [false; 3]).unwrap();
+        let f = vorticity_fields(&u, &v, &w, &grad).unwrap();
+        let all = |a: &ArrayD<f64>, e: f64| a.iter().all(|&v| (v - e).abs() < 1e-10);
+        assert!(all(&f.omega_x, 0.0) && all(&f.omega_y, 0.0));
+        assert!(all(&f.omega_z, 2.0 * om));
+        assert!(all(&f.enstrophy, 2.0 * om * om));
+        assert!(all(&f.helicity, 2.0 * om * wz));
+        assert!(all(&f.strain, 0.0));
+    }
 }
@@ -0,0 +1,192 @@
+/// Write triangle meshes (e.g. isosurfaces from `math::isosurface`) for
+/// visualization without loading the full 3-D field.
+///
+/// Formats:
+///   * VTK legacy PolyData, binary (`.vtk`) — ParaView/VisIt; the colour field
+///     is stored as POINT_DATA scalars.
+///   * PLY, binary little-endian (`.ply`) — MeshLab/Blender; the colour field
+///     is stored as a `value` property plus an RGB blue–white–red map over its
+///     range.
+///   * STL, binary (`.stl`) — geometry only, per-facet unit normals.
+use crate::math::isosurface::TriMesh;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+/// Mesh file format, chosen from the output extension by [`MeshFormat::from_path`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MeshFormat {
+    Vtk,
+    Ply,
+    Stl,
+}
+
+impl MeshFormat {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "vtk" => Some(Self::Vtk),
+            "ply" => Some(Self::Ply),
+            "stl" => Some(Self::Stl),
+            _ => None,
+        }
+    }
+
+    pub fn from_path(path: &Path) -> Option<Self> {
+        path.extension().and_then(|e| e.to_str()).and_then(Self::from_name)
+    }
+}
+
+/// Write `mesh` to `path` in `format`; `name` labels the scalar field.
+pub fn write_mesh(path: &Path, mesh: &TriMesh, format: MeshFormat, name: &str) -> std::io::Result<()> {
+    let mut w = BufWriter::new(File::create(path)?);
+    match format {
+        MeshFormat::Vtk => write_vtk(&mut w, mesh, name)?,
+        MeshFormat::Ply => write_ply(&mut w, mesh, name)?,
+        MeshFormat::Stl => write_stl(&mut w, mesh, name)?,
+    }
+    w.flush()
+}
+
+fn write_vtk<W: Write>(w: &mut W, mesh: &TriMesh, name: &str) -> std::io::Result<()> {
+    let (nv, nt) = (mesh.vertices.len(), mesh.triangles.len());
+    write!(w, "# vtk DataFile Version 3.0\nisosurface {name}\nBINARY\nDATASET POLYDATA\n")?;
+    writeln!(w, "POINTS {nv} float")?;
+    for v in &mesh.vertices {
+        for c in v {
+            w.write_all(&(*c as f32).to_be_bytes())?;
+        }
+    }
+    write!(w, "\nPOLYGONS {nt} {}\n", 4 * nt)?;
+    for t in &mesh.triangles {
+        w.write_all(&3i32.to_be_bytes())?;
+        for &i in t {
+            w.write_all(&(i as i32).to_be_bytes())?;
+        }
+    }
+    if let Some(s) = &mesh.scalars {
+        write!(w, "\nPOINT_DATA {nv}\nSCALARS {name} float 1\nLOOKUP_TABLE default\n")?;
+        for v in s {
+            w.write_all(&(*v as f32).to_be_bytes())?;
+        }
+    }
+    writeln!(w)
+}
+
+/// Blue–white–red map of `v` over `[lo, hi]`.
+fn diverging_rgb(v: f64, lo: f64, hi: f64) -> [u8; 3] {
+    let t = if hi > lo { ((v - lo) / (hi - lo)).clamp(0.0, 1.0) } else { 0.5 };
+    let ch = |a: f64| (255.0 * a).round() as u8;
+    if t < 0.5 {
+        let s = 2.0 * t;
+        [ch(s), ch(s), 255]
+    } else {
+        let s = 2.0 * (1.0 - t);
+        [255, ch(s), ch(s)]
+    }
+}
+
+fn write_ply<W: Write>(w: &mut W, mesh: &TriMesh, name: &str) -> std::io::Result<()> {
+    let (nv, nt) = (mesh.vertices.len(), mesh.triangles.len());
+    write!(w, "ply\nformat binary_little_endian 1.0\ncomment isosurface {name}\n")?;
+    write!(w, "element vertex {nv}\nproperty float x\nproperty float y\nproperty float z\n")?;
+    if mesh.scalars.is_some() {
+        write!(
+            w,
+            "property float value\nproperty uchar red\nproperty uchar green\nproperty uchar blue\n"
+        )?;
+    }
+    write!(w, "element face {nt}\nproperty list uchar int vertex_indices\nend_header\n")?;
+
+    let (lo, hi) = mesh.scalars.as_ref().map_or((0.0, 0.0), |s| {
+        s.iter().fold((f64::INFINITY, f64::NEG_INFINITY), |(a, b), &v| (a.min(v), b.max(v)))
+    });
+    for (n, v) in mesh.vertices.iter().enumerate() {
+        for c in v {
+            w.write_all(&(*c as f32).to_le_bytes())?;
+        }
+        if let Some(s) = &mesh.scalars {
+            w.write_all(&(s[n] as f32).to_le_bytes())?;
+            w.write_all(&diverging_rgb(s[n], lo, hi))?;
+        }
+    }
+    for t in &mesh.triangles {
+        w.write_all(&[3u8])?;
+        for &i in t {
+            w.write_all(&(i as i32).to_le_bytes())?;
+        }
+    }
+    Ok(())
+}
+
+fn write_stl<W: Write>(w: &mut W, mesh: &TriMesh, name: &str) -> std::io::Result<()> {
+    let mut header = [b' '; 80];
+    let title = format!("isosurface {name}");
+    let len = title.len().min(80);
+    header[..len].copy_from_slice(&title.as_bytes()[..len]);
+    w.write_all(&header)?;
+    w.write_all(&(mesh.triangles.len() as u32).to_le_bytes())?;
+    for t in &mesh.triangles {
+        let n = mesh.normal(t);
+        let norm = (n[0] * n[0] + n[1] * n[1] + n[2] * n[2]).sqrt().max(f64::MIN_POSITIVE);
+        for c in n {
+            w.write_all(&((c / norm) as f32).to_le_bytes())?;
+        }
+        for &i in t {
+            for c in mesh.vertices[i as usize] {
+                w.write_all(&(c as f32).to_le_bytes())?;
+            }
+        }
+        w.write_all(&0u16.to_le_bytes())?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tetrahedron(scalars: bool) -> TriMesh {
+        TriMesh {
+            vertices: vec![[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]],
+            triangles: vec![[0, 2, 1], [0, 1, 3], [0, 3, 2], [1, 2, 3]],
+            scalars: scalars.then(|| vec![0.0, 1.0, 2.0, 3.0]),
+        }
+    }
+
+    #[test]
+    fn binary_sizes() {
+        let dir = std::env::temp_dir();
+        let mesh = tetrahedron(true);
+
+        let stl = dir.join("test_mesh.stl");
+        write_mesh(&stl, &mesh, MeshFormat::Stl, "q").unwrap();
+        assert_eq!(std::fs::metadata(&stl).unwrap().len(), 84 + 50 * 4);
+
+        let ply = dir.join("test_mesh.ply");
+        write_mesh(&ply, &mesh, MeshFormat::Ply, "q").unwrap();
+        let bytes = std::fs::read(&ply).unwrap();
+        let end = b"end_header\n";
+        let body = bytes.windows(end.len()).position(|w| w == end).unwrap() + end.len();
+        // 4 vertices × (3 + 1 floats + 3 bytes), 4 faces × (1 + 3 × 4 bytes)
+        assert_eq!(bytes.len() - body, 4 * 19 + 4 * 13);
+
+        let vtk = dir.join("test_mesh.vtk");
+        write_mesh(&vtk, &tetrahedron(false), MeshFormat::Vtk, "q").unwrap();
+        let text = String::from_utf8_lossy(&std::fs::read(&vtk).unwrap()).into_owned();
+        assert!(text.contains("POINTS 4 float") && text.contains("POLYGONS 4 16"));
+        assert!(!text.contains("POINT_DATA"));
+
+        for p in [stl, ply, vtk] {
+            std::fs::remove_file(p).ok();
+        }
+    }
+
+    #[test]
+    fn format_from_extension() {
+        assert_eq!(MeshFormat::from_path(Path::new("q.PLY")), Some(MeshFormat::Ply));
+        assert_eq!(MeshFormat::from_path(Path::new("a/b.vtk")), Some(MeshFormat::Vtk));
+        assert_eq!(MeshFormat::from_path(Path::new("q.h5")), None);
+        assert_eq!(diverging_rgb(0.0, 0.0, 1.0), [0, 0, 255]);
+        assert_eq!(diverging_rgb(1.0, 0.0, 1.0), [255, 0, 0]);
+    }
+}
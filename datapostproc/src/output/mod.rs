@@ -1,3 +1,5 @@
 pub mod dat;
+pub mod mesh;
 pub mod normalize;
+pub mod units;
 pub mod xdmf;
@@ -1,20 +1,31 @@
 /// Wall-unit normalization.
 ///
-/// Mirrors Python `outputData.normalize()`:
-///   - coordinates (other than the wall-normal `dire`) → multiply by utau/nu
-///   - wall-normal coordinate (`dire`)                 → kept as original; `{dire}plus` added
-///   - velocities  (keys containing u/v/w)             → divide by utau
-///   - pressure    (keys containing p)                 → multiply by 1/tau
-use ndarray::Array1;
+/// Mirrors Python `outputData.normalize()`, except that the scaling of each
+/// key follows from its physical dimension in a [`UnitRegistry`] rather than
+/// from the letters it contains (so `uu` gets u_τ², `pu` u_τ³):
+///   - wall-normal coordinate (`dire`) → kept as original; `{dire}plus` added
+///   - every other key, of dimension L^a T^b → divided by (ν/u_τ)^a (ν/u_τ²)^b
+///
+/// Keys the registry cannot resolve are an error.
+///
+/// For spatially developing flow, (nz, nx) fields are normalized station by
+/// station instead ([`fields_to_wall_units`], [`fields_to_outer_units`]), with
+/// the inner scale chosen by [`InnerScaling`] and the outer scales by
+/// [`OuterScaling`].
+use ndarray::{Array1, Array2, Axis};
 use hdf5::Error;
 use std::collections::HashMap;
 
+use super::units::UnitRegistry;
+
 pub struct Profiles {
     pub dire: String,
     pub nu:   f64,
     pub tau:  f64,
     pub utau: f64,
     pub data: HashMap<String, Array1<f64>>,
+    /// Dimensions of the keys in `data`; extend it for custom quantities.
+    pub units: UnitRegistry,
 }
 
 impl Profiles {
@@ -23,7 +34,7 @@ impl Profiles {
             return Err("normalize: tau must be positive".into());
         }
         let utau = tau.sqrt();
-        Ok(Self { dire: dire.to_string(), nu, tau, utau, data })
+        Ok(Self { dire: dire.to_string(), nu, tau, utau, data, units: UnitRegistry::new() })
     }
 
     /// Normalize profiles to wall units and add `{dire}plus`.
@@ -33,26 +44,18 @@ impl Profiles {
     ///   2. Save the normalized wall-normal coord as `{dire}plus`.
     ///   3. Restore the original (physical) wall-normal coord under `dire`.
     pub fn to_wall_units(&self) -> Result<HashMap<String, Array1<f64>>, Error> {
-        let tau_inv = 1.0 / self.tau;
         let mut out: HashMap<String, Array1<f64>> = HashMap::new();
         let mut wall_coord_plus: Option<Array1<f64>> = None;
 
         for (key, arr) in &self.data {
-            let normed = if key.starts_with(['x', 'y', 'z']) {
-                let normalized = arr.mapv(|v| v * self.utau / self.nu);
-                if *key == self.dire {
-                    // Save wall-unit version for `{dire}plus`; restore original below.
-                    wall_coord_plus = Some(normalized);
-                    arr.clone()  // keep original physical coord under `dire`
-                } else {
-                    normalized
-                }
-            } else if key.chars().any(|c| matches!(c, 'u' | 'v' | 'w')) {
-                arr.mapv(|v| v / self.utau)
-            } else if key.contains('p') {
-                arr.mapv(|v| v * tau_inv)
+            let f = self.units.dimension(key)?.wall_factor(self.nu, self.utau);
+            let normalized = arr.mapv(|v| v * f);
+            let normed = if *key == self.dire {
+                // Save wall-unit version for `{dire}plus`; restore original below.
+                wall_coord_plus = Some(normalized);
+                arr.clone()  // keep original physical coord under `dire`
             } else {
-                arr.clone()
+                normalized
             };
             out.insert(key.clone(), normed);
         }
@@ -66,3 +69,222 @@ impl Profiles {
         Ok(out)
     }
 }
+
+// ─── Spatially developing flow: per-station scales ───────────────────────────
+
+/// Choice of u_τ for wall units of (nz, nx) fields.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum InnerScaling {
+    /// Local u_τ(x) at every station.
+    Local,
+    /// Fixed τ_w of a reference (e.g. uncontrolled) case.
+    Reference(f64),
+    /// u_τ of the station with this x index (e.g. upstream of the actuator).
+    Upstream(usize),
+}
+
+/// Choice of outer length and velocity scales of (nz, nx) fields.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OuterScaling {
+    /// Half-channel height h; velocities left as stored (already in U_b units).
+    HalfHeight(f64),
+    /// Local δ99(x), the height where ū first reaches 0.99 max ū, with the
+    /// local maximum velocity U_e(x).
+    Delta99,
+    /// Half-channel height h with the local bulk velocity U_b(x) over [0, 2h].
+    Bulk(f64),
+}
+
+/// Local wall shear stress τ_w(x) = ν ū(z₀, x) / z₀ of a span-averaged
+/// (nz, nx) field, the per-station version of `wall::wall_shear_stress`.
+pub fn local_tau(u: &Array2<f64>, zc: &Array1<f64>, nu: f64) -> Result<Array1<f64>, Error> {
+    if u.nrows() != zc.len() {
+        return Err(format!("local_tau: u has {} rows, zc has {}", u.nrows(), zc.len()).into());
+    }
+    if zc[0] == 0.0 {
+        return Err("local_tau: zc[0] is zero, cannot divide".into());
+    }
+    Ok(u.row(0).mapv(|v| nu * v / zc[0]))
+}
+
+impl InnerScaling {
+    /// u_τ per station from the local wall shear stress `tau`.  Under `Local`
+    /// a station with τ_w ≤ 0 (separated or reversed flow) has no inner
+    /// scale and gets NaN; a non-positive reference or upstream τ_w is an
+    /// error, since it would leave every station unscaled.
+    pub fn utau(&self, tau: &Array1<f64>) -> Result<Array1<f64>, Error> {
+        let t = match *self {
+            Self::Local => return Ok(tau.mapv(|t| if t > 0.0 { t.sqrt() } else { f64::NAN })),
+            Self::Reference(t) => t,
+            Self::Upstream(ix) => *tau.get(ix).ok_or_else(|| Error::from(format!(
+                "upstream station {ix} out of range (nx = {})",
+                tau.len()
+            )))?,
+        };
+        if t <= 0.0 || t.is_nan() {
+            return Err(format!("normalize: reference tau {t} must be positive").into());
+        }
+        Ok(Array1::from_elem(tau.len(), t.sqrt()))
+    }
+}
+
+impl OuterScaling {
+    /// Outer length and velocity scales per station of the (nz, nx) mean `u`.
+    pub fn scales(&self, u: &Array2<f64>, zc: &Array1<f64>) -> Result<(Array1<f64>, Array1<f64>), Error> {
+        let nx = u.ncols();
+        match *self {
+            Self::HalfHeight(h) => Ok((Array1::from_elem(nx, h), Array1::ones(nx))),
+            Self::Delta99 => {
+                let mut len = Array1::zeros(nx);
+                let mut vel = Array1::zeros(nx);
+                for (ix, col) in u.axis_iter(Axis(1)).enumerate() {
+                    let ue = col.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+                    let target = 0.99 * ue;
+                    let k = col.iter().position(|&v| v >= target).unwrap_or(0);
+                    len[ix] = if k == 0 {
+                        zc[0]
+                    } else {
+                        let t = (target - col[k - 1]) / (col[k] - col[k - 1]);
+                        zc[k - 1] + t * (zc[k] - zc[k - 1])
+                    };
+                    vel[ix] = ue;
+                }
+                Ok((len, vel))
+            }
+            Self::Bulk(h) => {
+                // trapezoid over [0, zc.., 2h] with no-slip end points
+                let nz = zc.len();
+                if zc[nz - 1] > 2.0 * h {
+                    return Err(format!("bulk velocity: zc extends beyond 2h = {}", 2.0 * h).into());
+                }
+                let ub = u.axis_iter(Axis(1)).map(|col| {
+                    let mut s = 0.5 * zc[0] * col[0] + 0.5 * (2.0 * h - zc[nz - 1]) * col[nz - 1];
+                    for k in 1..nz {
+                        s += 0.5 * (col[k - 1] + col[k]) * (zc[k] - zc[k - 1]);
+                    }
+                    s / (2.0 * h)
+                });
+                Ok((Array1::from_elem(nx, h), Array1::from_iter(ub)))
+            }
+        }
+    }
+}
+
+/// Rescale each column (station) of every field by the length scale `len`
+/// and velocity scale `uv` of that station and add the scaled wall-normal
+/// coordinate map under `coord_key`.
+fn scale_fields(
+    fields: &HashMap<String, Array2<f64>>,
+    units: &UnitRegistry,
+    zc: &Array1<f64>,
+    uv: &Array1<f64>,
+    len: &Array1<f64>,
+    coord_key: String,
+) -> Result<HashMap<String, Array2<f64>>, Error> {
+    let mut out = HashMap::new();
+    for (key, f) in fields {
+        if f.nrows() != zc.len() || f.ncols() != uv.len() {
+            return Err(format!("normalize: '{key}' shape {:?} is not (nz, nx)", f.shape()).into());
+        }
+        let dim = units.dimension(key)?;
+        let mut g = f.clone();
+        for (ix, mut col) in g.axis_iter_mut(Axis(1)).enumerate() {
+            col *= dim.outer_factor(len[ix], uv[ix]);
+        }
+        out.insert(key.clone(), g);
+    }
+    let zmap = Array2::from_shape_fn((zc.len(), uv.len()), |(k, ix)| zc[k] / len[ix]);
+    out.insert(coord_key, zmap);
+    Ok(out)
+}
+
+/// Normalize named (nz, nx) fields to wall units with a per-station u_τ(x)
+/// and add the `{dire}plus` map (z u_τ(x)/ν).
+pub fn fields_to_wall_units(
+    fields: &HashMap<String, Array2<f64>>,
+    units: &UnitRegistry,
+    dire: &str,
+    zc: &Array1<f64>,
+    nu: f64,
+    utau: &Array1<f64>,
+) -> Result<HashMap<String, Array2<f64>>, Error> {
+    let len = utau.mapv(|ut| nu / ut);
+    let key = format!("{}plus", dire.chars().next().unwrap_or('z'));
+    scale_fields(fields, units, zc, utau, &len, key)
+}
+
+/// Normalize named (nz, nx) fields with per-station outer scales (from
+/// [`OuterScaling::scales`]) and add the `{dire}outer` map (z / L(x)).
+pub fn fields_to_outer_units(
+    fields: &HashMap<String, Array2<f64>>,
+    units: &UnitRegistry,
+    dire: &str,
+    zc: &Array1<f64>,
+    length: &Array1<f64>,
+    velocity: &Array1<f64>,
+) -> Result<HashMap<String, Array2<f64>>, Error> {
+    let key = format!("{}outer", dire.chars().next().unwrap_or('z'));
+    scale_fields(fields, units, zc, velocity, length, key)
+}
+
+// ─── Tests ────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Linear sublayer ū = τ_w(x) z/ν with τ_w growing in x: local scaling
+    /// collapses u⁺ = z⁺, upstream scaling does not.
+    #[test]
+    fn local_inner_scaling_collapses_sublayer() {
+        let nu = 1e-3;
+        let zc = Array1::from_shape_fn(6, |k| 1e-3 * (k as f64 + 0.5));
+        let tau0 = Array1::from_vec(vec![1e-3, 2e-3, 4e-3]);
+        let u = Array2::from_shape_fn((6, 3), |(k, ix)| tau0[ix] * zc[k] / nu);
+        let mut fields = HashMap::new();
+        fields.insert("u".to_string(), u.clone());
+        fields.insert("p".to_string(), Array2::ones((6, 3)));
+
+        let tau = local_tau(&u, &zc, nu).unwrap();
+        let ut = InnerScaling::Local.utau(&tau).unwrap();
+        let w = fields_to_wall_units(&fields, &UnitRegistry::new(), "z", &zc, nu, &ut).unwrap();
+        for ((k, ix), &up) in w["u"].indexed_iter() {
+            assert!((up - w["zplus"][[k, ix]]).abs() < 1e-12);
+            assert!((w["p"][[k, ix]] - 1.0 / tau0[ix]).abs() < 1e-9 / tau0[ix]);
+        }
+
+        let ut0 = InnerScaling::Upstream(0).utau(&tau).unwrap();
+        assert!(ut0.iter().all(|&v| (v - tau0[0].sqrt()).abs() < 1e-15));
+        let w0 = fields_to_wall_units(&fields, &UnitRegistry::new(), "z", &zc, nu, &ut0).unwrap();
+        assert!((w0["u"][[3, 2]] / w0["zplus"][[3, 2]] - 4.0).abs() < 1e-12);
+        assert!(InnerScaling::Reference(-1.0).utau(&tau).is_err());
+
+        // a reversed-flow station loses its own scale only
+        let tau = Array1::from_vec(vec![1e-3, -2e-4, 4e-3]);
+        let ut = InnerScaling::Local.utau(&tau).unwrap();
+        assert!(ut[1].is_nan() && (ut[2] - 4e-3f64.sqrt()).abs() < 1e-15);
+        assert!(InnerScaling::Upstream(1).utau(&tau).is_err());
+    }
+
+    #[test]
+    fn outer_scales_of_poiseuille() {
+        let h = 1.0;
+        let nz = 200;
+        let zc = Array1::from_shape_fn(nz, |k| 2.0 * h * (k as f64 + 0.5) / nz as f64);
+        // ū = 1.5 (2η − η²): U_b = 1, U_cl = 1.5
+        let u = Array2::from_shape_fn((nz, 2), |(k, ix)| (1.0 + ix as f64) * 1.5 * zc[k] * (2.0 - zc[k]));
+        let (l, v) = OuterScaling::Bulk(h).scales(&u, &zc).unwrap();
+        assert!((v[0] - 1.0).abs() < 1e-4 && (v[1] - 2.0).abs() < 2e-4 && l[0] == h);
+
+        let (d99, ue) = OuterScaling::Delta99.scales(&u, &zc).unwrap();
+        // 2η − η² = 0.99 → η = 1 − 0.1
+        assert!((d99[0] - 0.9).abs() < 1e-3 && (d99[1] - 0.9).abs() < 1e-3, "{d99}");
+        assert!((ue[1] / ue[0] - 2.0).abs() < 1e-12);
+
+        let mut fields = HashMap::new();
+        fields.insert("u".to_string(), u);
+        let o = fields_to_outer_units(&fields, &UnitRegistry::new(), "z", &zc, &d99, &ue).unwrap();
+        assert!(o["u"].iter().all(|&x| x <= 1.0 + 1e-12));
+        assert!((o["zouter"][[nz - 1, 0]] - zc[nz - 1] / d99[0]).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,233 @@
+//! Physical dimensions of named output quantities.
+//!
+//! Every quantity in this crate is kinematic (ρ = 1), so its dimension is
+//! L^length · T^time: velocity L T⁻¹, pressure p/ρ L² T⁻², vorticity T⁻¹,
+//! a TKE budget term L² T⁻³.  A scaling with length scale ℓ and time scale t
+//! divides a quantity by ℓ^length · t^time:
+//!
+//!   wall units   ℓ = ν/u_τ,  t = ν/u_τ²
+//!   outer units  ℓ = L,      t = L/U
+//!
+//! Names resolve, in order, through:
+//!   1. the explicit table ([`UnitRegistry::insert`], builtins below),
+//!   2. a `_rms` / `_mean` suffix, which keeps the base dimension,
+//!   3. a derivative `d<a>d<x|y|z>` (e.g. `dudz`, `dpdx`): dim(a) / L,
+//!   4. a product of primitives, each `u`, `v`, `w`, `p` or `om<x|y|z>`
+//!      (e.g. `uu`, `uvw`, `pu`, `omxomx`): the sum of their dimensions.
+//!      A velocity or pressure letter followed by `x`, `y` or `z` is its
+//!      gradient, as in the stored subavg moments (`ux`, `uz`, `pux`,
+//!      `uxux`): dim / L.
+//!
+//! Anything else is an error rather than a guess.
+
+use hdf5::Error;
+use std::collections::HashMap;
+
+/// Dimension L^length · T^time of a kinematic quantity.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Dim {
+    pub length: i32,
+    pub time: i32,
+}
+
+impl Dim {
+    pub const NONE: Dim = Dim { length: 0, time: 0 };
+    pub const LENGTH: Dim = Dim { length: 1, time: 0 };
+    pub const VELOCITY: Dim = Dim { length: 1, time: -1 };
+    pub const PRESSURE: Dim = Dim { length: 2, time: -2 };
+    pub const VORTICITY: Dim = Dim { length: 0, time: -1 };
+    pub const VISCOSITY: Dim = Dim { length: 2, time: -1 };
+    pub const BUDGET: Dim = Dim { length: 2, time: -3 };
+    pub const ACCELERATION: Dim = Dim { length: 1, time: -2 };
+
+    fn mul(self, o: Dim) -> Dim {
+        Dim { length: self.length + o.length, time: self.time + o.time }
+    }
+
+    /// Factor that makes a quantity of this dimension non-dimensional with
+    /// length scale `length` and time scale `time`.
+    pub fn factor(self, length: f64, time: f64) -> f64 {
+        1.0 / (length.powi(self.length) * time.powi(self.time))
+    }
+
+    /// Factor for wall units from ν and u_τ.
+    pub fn wall_factor(self, nu: f64, utau: f64) -> f64 {
+        self.factor(nu / utau, nu / (utau * utau))
+    }
+
+    /// Factor for outer units from a length `l` and a velocity `u`.
+    pub fn outer_factor(self, l: f64, u: f64) -> f64 {
+        self.factor(l, l / u)
+    }
+}
+
+/// Name → dimension lookup for normalization.
+#[derive(Clone, Debug)]
+pub struct UnitRegistry {
+    table: HashMap<String, Dim>,
+}
+
+impl Default for UnitRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl UnitRegistry {
+    /// Registry with the coordinates and derived quantities this crate writes.
+    pub fn new() -> Self {
+        let mut table = HashMap::new();
+        for c in ["x", "y", "z", "xc", "yc", "zc", "lmix", "lambda_y"] {
+            table.insert(c.to_string(), Dim::LENGTH);
+        }
+        for (name, d) in [
+            ("tke", Dim::PRESSURE),
+            ("k", Dim::PRESSURE),
+            ("nut", Dim::VISCOSITY),
+            ("nu", Dim::VISCOSITY),
+            ("ky", Dim { length: -1, time: 0 }),
+            ("mke", Dim::PRESSURE),
+            ("prod", Dim::BUDGET),
+            ("diss", Dim::BUDGET),
+            ("pstrain", Dim::BUDGET),
+            ("transfer", Dim::BUDGET),
+            ("balance", Dim::BUDGET),
+            // stress / TKE budget terms (tke --budget)
+            ("conv", Dim::BUDGET),
+            ("turb_trans", Dim::BUDGET),
+            ("visc_trans", Dim::BUDGET),
+            ("visc_diss", Dim::BUDGET),
+            ("press_strain", Dim::BUDGET),
+            ("press_trans", Dim::BUDGET),
+            ("source", Dim::BUDGET),
+            ("indicator", Dim::NONE),
+            ("rs_frac", Dim::NONE),
+        ] {
+            table.insert(name.to_string(), d);
+        }
+        // mean streamwise momentum balance terms (momentum-budget)
+        for c in ["x", "y", "z"] {
+            for t in ["adv", "rs", "visc", "res"] {
+                table.insert(format!("{t}_{c}"), Dim::ACCELERATION);
+            }
+        }
+        Self { table }
+    }
+
+    /// Register (or override) the dimension of `name`.
+    pub fn insert(&mut self, name: &str, dim: Dim) {
+        self.table.insert(name.to_string(), dim);
+    }
+
+    /// Dimension of `name`; an error for names the rules cannot resolve.
+    pub fn dimension(&self, name: &str) -> Result<Dim, Error> {
+        self.resolve(name)
+            .ok_or_else(|| format!("units: unknown quantity '{name}' (register it with UnitRegistry::insert)").into())
+    }
+
+    fn resolve(&self, name: &str) -> Option<Dim> {
+        if let Some(&d) = self.table.get(name) {
+            return Some(d);
+        }
+        if let Some(base) = name.strip_suffix("_rms").or_else(|| name.strip_suffix("_mean")) {
+            return self.resolve(base);
+        }
+        if let Some((a, axis)) = name.strip_prefix('d').and_then(|r| r.rsplit_once('d'))
+            && matches!(axis, "x" | "y" | "z")
+            && !a.is_empty()
+        {
+            return self.resolve(a).map(|d| d.mul(Dim { length: -1, time: 0 }));
+        }
+        product_dim(name)
+    }
+}
+
+/// Dimension of a product of primitive symbols, `None` if `name` is not one.
+fn product_dim(name: &str) -> Option<Dim> {
+    if name.is_empty() {
+        return None;
+    }
+    let mut d = Dim::NONE;
+    let mut rest = name;
+    while !rest.is_empty() {
+        if let Some(r) = rest.strip_prefix("om") {
+            let c = r.chars().next()?;
+            if !matches!(c, 'x' | 'y' | 'z') {
+                return None;
+            }
+            d = d.mul(Dim::VORTICITY);
+            rest = &r[1..];
+            continue;
+        }
+        let c = rest.chars().next()?;
+        d = d.mul(match c {
+            'u' | 'v' | 'w' => Dim::VELOCITY,
+            'p' => Dim::PRESSURE,
+            _ => return None,
+        });
+        rest = &rest[1..];
+        // gradient suffix: ux = ∂u/∂x
+        if let Some(r) = rest.strip_prefix(['x', 'y', 'z']) {
+            d = d.mul(Dim { length: -1, time: 0 });
+            rest = r;
+        }
+    }
+    Some(d)
+}
+
+// ─── Tests ────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn moments_gradients_and_unknowns() {
+        let r = UnitRegistry::new();
+        let dim = |n: &str| r.dimension(n).unwrap();
+        assert_eq!(dim("u"), Dim::VELOCITY);
+        assert_eq!(dim("uu"), Dim { length: 2, time: -2 });
+        assert_eq!(dim("uvw"), Dim { length: 3, time: -3 });
+        assert_eq!(dim("pu"), Dim { length: 3, time: -3 });
+        assert_eq!(dim("omx_rms"), Dim::VORTICITY);
+        assert_eq!(dim("omxomz"), Dim { length: 0, time: -2 });
+        assert_eq!(dim("dudz"), Dim::VORTICITY);
+        assert_eq!(dim("dpdx"), Dim { length: 1, time: -2 });
+        assert_eq!(dim("zc"), Dim::LENGTH);
+        // stored subavg gradients and gradient products
+        assert_eq!(dim("ux"), Dim::VORTICITY);
+        assert_eq!(dim("uz"), Dim::VORTICITY);
+        assert_eq!(dim("wz"), Dim::VORTICITY);
+        assert_eq!(dim("uxux"), Dim { length: 0, time: -2 });
+        assert_eq!(dim("pux"), Dim::BUDGET);
+        assert_eq!(dim("pwz"), Dim::BUDGET);
+        assert_eq!(dim("uz_rms"), Dim::VORTICITY);
+        for name in ["conv", "turb_trans", "visc_diss", "press_strain", "press_trans", "visc_trans", "source"] {
+            assert_eq!(dim(name), Dim::BUDGET, "{name}");
+        }
+        assert_eq!(dim("rs_z"), Dim::ACCELERATION);
+        // momentum-budget residual vs the stress / TKE budget closure
+        assert_eq!(dim("res_x"), Dim::ACCELERATION);
+        assert_eq!(dim("balance"), Dim::BUDGET);
+        for bad in ["rho", "uq", "omq", "dud", "d", "", "xu", "uxx"] {
+            assert!(r.dimension(bad).is_err(), "{bad}");
+        }
+        let mut r = r;
+        r.insert("rho", Dim::NONE);
+        assert_eq!(r.dimension("rho").unwrap(), Dim::NONE);
+    }
+
+    #[test]
+    fn wall_and_outer_factors() {
+        let (nu, ut) = (1e-3, 0.05);
+        // u⁺ = u/u_τ, uu⁺ = uu/u_τ², p⁺ = p/u_τ², ω⁺ = ων/u_τ², y⁺ = y u_τ/ν
+        assert!((Dim::VELOCITY.wall_factor(nu, ut) - 1.0 / ut).abs() < 1e-12);
+        assert!((Dim { length: 2, time: -2 }.wall_factor(nu, ut) - 1.0 / (ut * ut)).abs() < 1e-9);
+        assert!((Dim::VORTICITY.wall_factor(nu, ut) - nu / (ut * ut)).abs() < 1e-12);
+        assert!((Dim::LENGTH.wall_factor(nu, ut) - ut / nu).abs() < 1e-9);
+        assert!((Dim::BUDGET.wall_factor(nu, ut) - nu / ut.powi(4)).abs() < 1e-6);
+        // outer: p / U², ω L/U
+        assert!((Dim::PRESSURE.outer_factor(2.0, 3.0) - 1.0 / 9.0).abs() < 1e-15);
+        assert!((Dim::VORTICITY.outer_factor(2.0, 3.0) - 2.0 / 3.0).abs() < 1e-15);
+    }
+}